@@ -7,10 +7,18 @@ namespace {
     void set_qstring(QString* val, const char* utf8, int nbytes) {
         *val = QString::fromUtf8(utf8, nbytes);
     }
+    inline void imageViewerDefaultsChanged(ImageViewer* o)
+    {
+        Q_EMIT o->defaultsChanged();
+    }
     inline void imageViewerDisplay_pathChanged(ImageViewer* o)
     {
         Q_EMIT o->display_pathChanged();
     }
+    inline void imageViewerExport_gray_codesChanged(ImageViewer* o)
+    {
+        Q_EMIT o->export_gray_codesChanged();
+    }
     inline void imageViewerHas_pendingChanged(ImageViewer* o)
     {
         Q_EMIT o->has_pendingChanged();
@@ -19,21 +27,89 @@ namespace {
     {
         Q_EMIT o->image_pathChanged();
     }
+    inline void imageViewerLast_export_dirChanged(ImageViewer* o)
+    {
+        Q_EMIT o->last_export_dirChanged();
+    }
+    inline void imageViewerLast_thresholdChanged(ImageViewer* o)
+    {
+        Q_EMIT o->last_thresholdChanged();
+    }
+    inline void imageViewerMask_overlay_pathChanged(ImageViewer* o)
+    {
+        Q_EMIT o->mask_overlay_pathChanged();
+    }
+    inline void imageViewerPreview_pathChanged(ImageViewer* o)
+    {
+        Q_EMIT o->preview_pathChanged();
+    }
+    inline void imageViewerRecent_filesChanged(ImageViewer* o)
+    {
+        Q_EMIT o->recent_filesChanged();
+    }
+    inline void imageViewerResult_pathChanged(ImageViewer* o)
+    {
+        Q_EMIT o->result_pathChanged();
+    }
+    inline void imageViewerWindow_stateChanged(ImageViewer* o)
+    {
+        Q_EMIT o->window_stateChanged();
+    }
 }
 extern "C" {
-    ImageViewer::Private* image_viewer_new(ImageViewer*, void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*));
+    ImageViewer::Private* image_viewer_new(ImageViewer*, void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*), void (*)(ImageViewer*));
     void image_viewer_free(ImageViewer::Private*);
+    void image_viewer_defaults_get(const ImageViewer::Private*, QString*, qstring_set);
     void image_viewer_display_path_get(const ImageViewer::Private*, QString*, qstring_set);
+    bool image_viewer_export_gray_codes_get(const ImageViewer::Private*);
     bool image_viewer_has_pending_get(const ImageViewer::Private*);
     void image_viewer_image_path_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_last_export_dir_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_last_threshold_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_mask_overlay_path_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_preview_path_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_recent_files_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_result_path_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_window_state_get(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_adjust_tone(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_analyze_seams(const ImageViewer::Private*, const ushort*, int, QString*, qstring_set);
+    void image_viewer_apply_color_mapping(const ImageViewer::Private*);
     void image_viewer_apply_threshold_mapping(const ImageViewer::Private*, const ushort*, int);
-    void image_viewer_cleanup_scattered_pixels(const ImageViewer::Private*);
+    void image_viewer_apply_transform(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_cleanup_scattered_pixels(const ImageViewer::Private*, const ushort*, int);
     void image_viewer_cleanup_temp_files(const ImageViewer::Private*);
+    void image_viewer_clear_recent_files(const ImageViewer::Private*);
+    void image_viewer_compare(const ImageViewer::Private*, const ushort*, int, QString*, qstring_set);
+    void image_viewer_denoise(const ImageViewer::Private*, const ushort*, int);
+    quint32 image_viewer_edit_pixels(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_export_color_mapping(const ImageViewer::Private*, const ushort*, int, bool);
+    void image_viewer_get_color_table(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_get_density(const ImageViewer::Private*, QString*, qstring_set);
+    void image_viewer_get_selection_mask(const ImageViewer::Private*, QString*, qstring_set);
     void image_viewer_gray_preview(const ImageViewer::Private*);
+    void image_viewer_grid_geometry(const ImageViewer::Private*, const ushort*, int, QString*, qstring_set);
+    void image_viewer_inspect(const ImageViewer::Private*, qint32, qint32, QString*, qstring_set);
+    quint32 image_viewer_isolate_level(const ImageViewer::Private*, const ushort*, int);
+    quint32 image_viewer_merge_levels(const ImageViewer::Private*, const ushort*, int);
+    quint32 image_viewer_merge_small_regions(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_mode_filter(const ImageViewer::Private*, const ushort*, int, QString*, qstring_set);
+    void image_viewer_preview_threshold_mapping(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_quantize_colors(const ImageViewer::Private*, const ushort*, int);
+    bool image_viewer_redo_edit(const ImageViewer::Private*);
     void image_viewer_refresh_display(const ImageViewer::Private*);
+    quint32 image_viewer_replace_color(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_run_pipeline(const ImageViewer::Private*, const ushort*, int, QString*, qstring_set);
     void image_viewer_save_processed(const ImageViewer::Private*);
+    void image_viewer_set_color_table(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_set_defaults(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_set_density(const ImageViewer::Private*, const ushort*, int);
     void image_viewer_set_image_path(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_set_selection_mask(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_set_window_state(const ImageViewer::Private*, const ushort*, int);
     void image_viewer_start_watcher(const ImageViewer::Private*, const ushort*, int);
+    void image_viewer_tile_preview(const ImageViewer::Private*, const ushort*, int);
+    bool image_viewer_undo_edit(const ImageViewer::Private*);
+    void image_viewer_visible_tiles(const ImageViewer::Private*, const ushort*, int, QString*, qstring_set);
 };
 
 ImageViewer::ImageViewer(bool /*owned*/, QObject *parent):
@@ -46,9 +122,18 @@ ImageViewer::ImageViewer(bool /*owned*/, QObject *parent):
 ImageViewer::ImageViewer(QObject *parent):
     QObject(parent),
     m_d(image_viewer_new(this,
+        imageViewerDefaultsChanged,
         imageViewerDisplay_pathChanged,
+        imageViewerExport_gray_codesChanged,
         imageViewerHas_pendingChanged,
-        imageViewerImage_pathChanged)),
+        imageViewerImage_pathChanged,
+        imageViewerLast_export_dirChanged,
+        imageViewerLast_thresholdChanged,
+        imageViewerMask_overlay_pathChanged,
+        imageViewerPreview_pathChanged,
+        imageViewerRecent_filesChanged,
+        imageViewerResult_pathChanged,
+        imageViewerWindow_stateChanged)),
     m_ownsPrivate(true)
 {
 }
@@ -58,12 +143,22 @@ ImageViewer::~ImageViewer() {
         image_viewer_free(m_d);
     }
 }
+QString ImageViewer::defaults() const
+{
+    QString v;
+    image_viewer_defaults_get(m_d, &v, set_qstring);
+    return v;
+}
 QString ImageViewer::display_path() const
 {
     QString v;
     image_viewer_display_path_get(m_d, &v, set_qstring);
     return v;
 }
+bool ImageViewer::export_gray_codes() const
+{
+    return image_viewer_export_gray_codes_get(m_d);
+}
 bool ImageViewer::has_pending() const
 {
     return image_viewer_has_pending_get(m_d);
@@ -74,35 +169,498 @@ QString ImageViewer::image_path() const
     image_viewer_image_path_get(m_d, &v, set_qstring);
     return v;
 }
+QString ImageViewer::last_export_dir() const
+{
+    QString v;
+    image_viewer_last_export_dir_get(m_d, &v, set_qstring);
+    return v;
+}
+QString ImageViewer::last_threshold() const
+{
+    QString v;
+    image_viewer_last_threshold_get(m_d, &v, set_qstring);
+    return v;
+}
+QString ImageViewer::mask_overlay_path() const
+{
+    QString v;
+    image_viewer_mask_overlay_path_get(m_d, &v, set_qstring);
+    return v;
+}
+QString ImageViewer::preview_path() const
+{
+    QString v;
+    image_viewer_preview_path_get(m_d, &v, set_qstring);
+    return v;
+}
+QString ImageViewer::recent_files() const
+{
+    QString v;
+    image_viewer_recent_files_get(m_d, &v, set_qstring);
+    return v;
+}
+QString ImageViewer::result_path() const
+{
+    QString v;
+    image_viewer_result_path_get(m_d, &v, set_qstring);
+    return v;
+}
+QString ImageViewer::window_state() const
+{
+    QString v;
+    image_viewer_window_state_get(m_d, &v, set_qstring);
+    return v;
+}
+void ImageViewer::adjust_tone(const QString& tone_json) const
+{
+    return image_viewer_adjust_tone(m_d, tone_json.utf16(), tone_json.size());
+}
+QString ImageViewer::analyze_seams(const QString& repeat_json) const
+{
+    QString s;
+    image_viewer_analyze_seams(m_d, repeat_json.utf16(), repeat_json.size(), &s, set_qstring);
+    return s;
+}
+void ImageViewer::apply_color_mapping() const
+{
+    return image_viewer_apply_color_mapping(m_d);
+}
 void ImageViewer::apply_threshold_mapping(const QString& thresholds_json) const
 {
     return image_viewer_apply_threshold_mapping(m_d, thresholds_json.utf16(), thresholds_json.size());
 }
-void ImageViewer::cleanup_scattered_pixels() const
+void ImageViewer::apply_transform(const QString& transform_json) const
+{
+    return image_viewer_apply_transform(m_d, transform_json.utf16(), transform_json.size());
+}
+void ImageViewer::cleanup_scattered_pixels(const QString& wrap_mode) const
 {
-    return image_viewer_cleanup_scattered_pixels(m_d);
+    return image_viewer_cleanup_scattered_pixels(m_d, wrap_mode.utf16(), wrap_mode.size());
 }
 void ImageViewer::cleanup_temp_files() const
 {
     return image_viewer_cleanup_temp_files(m_d);
 }
+void ImageViewer::clear_recent_files() const
+{
+    return image_viewer_clear_recent_files(m_d);
+}
+QString ImageViewer::compare(const QString& compare_json) const
+{
+    QString s;
+    image_viewer_compare(m_d, compare_json.utf16(), compare_json.size(), &s, set_qstring);
+    return s;
+}
+void ImageViewer::denoise(const QString& denoise_json) const
+{
+    return image_viewer_denoise(m_d, denoise_json.utf16(), denoise_json.size());
+}
+quint32 ImageViewer::edit_pixels(const QString& edit_json) const
+{
+    return image_viewer_edit_pixels(m_d, edit_json.utf16(), edit_json.size());
+}
+void ImageViewer::export_color_mapping(const QString& output_path, bool gray_codes) const
+{
+    return image_viewer_export_color_mapping(m_d, output_path.utf16(), output_path.size(), gray_codes);
+}
+QString ImageViewer::get_color_table() const
+{
+    QString s;
+    image_viewer_get_color_table(m_d, &s, set_qstring);
+    return s;
+}
+QString ImageViewer::get_density() const
+{
+    QString s;
+    image_viewer_get_density(m_d, &s, set_qstring);
+    return s;
+}
+QString ImageViewer::get_selection_mask() const
+{
+    QString s;
+    image_viewer_get_selection_mask(m_d, &s, set_qstring);
+    return s;
+}
 void ImageViewer::gray_preview() const
 {
     return image_viewer_gray_preview(m_d);
 }
+QString ImageViewer::grid_geometry(const QString& grid_json) const
+{
+    QString s;
+    image_viewer_grid_geometry(m_d, grid_json.utf16(), grid_json.size(), &s, set_qstring);
+    return s;
+}
+QString ImageViewer::inspect(qint32 x, qint32 y) const
+{
+    QString s;
+    image_viewer_inspect(m_d, x, y, &s, set_qstring);
+    return s;
+}
+quint32 ImageViewer::isolate_level(const QString& isolate_json) const
+{
+    return image_viewer_isolate_level(m_d, isolate_json.utf16(), isolate_json.size());
+}
+quint32 ImageViewer::merge_levels(const QString& merge_json) const
+{
+    return image_viewer_merge_levels(m_d, merge_json.utf16(), merge_json.size());
+}
+quint32 ImageViewer::merge_small_regions(const QString& regions_json) const
+{
+    return image_viewer_merge_small_regions(m_d, regions_json.utf16(), regions_json.size());
+}
+QString ImageViewer::mode_filter(const QString& mode_filter_json) const
+{
+    QString s;
+    image_viewer_mode_filter(m_d, mode_filter_json.utf16(), mode_filter_json.size(), &s, set_qstring);
+    return s;
+}
+void ImageViewer::preview_threshold_mapping(const QString& thresholds_json) const
+{
+    return image_viewer_preview_threshold_mapping(m_d, thresholds_json.utf16(), thresholds_json.size());
+}
+void ImageViewer::quantize_colors(const QString& quantize_json) const
+{
+    return image_viewer_quantize_colors(m_d, quantize_json.utf16(), quantize_json.size());
+}
+bool ImageViewer::redo_edit() const
+{
+    return image_viewer_redo_edit(m_d);
+}
 void ImageViewer::refresh_display() const
 {
     return image_viewer_refresh_display(m_d);
 }
+quint32 ImageViewer::replace_color(const QString& replace_json) const
+{
+    return image_viewer_replace_color(m_d, replace_json.utf16(), replace_json.size());
+}
+QString ImageViewer::run_pipeline(const QString& pipeline_json) const
+{
+    QString s;
+    image_viewer_run_pipeline(m_d, pipeline_json.utf16(), pipeline_json.size(), &s, set_qstring);
+    return s;
+}
 void ImageViewer::save_processed() const
 {
     return image_viewer_save_processed(m_d);
 }
+void ImageViewer::set_color_table(const QString& color_table_json) const
+{
+    return image_viewer_set_color_table(m_d, color_table_json.utf16(), color_table_json.size());
+}
+void ImageViewer::set_defaults(const QString& defaults_json) const
+{
+    return image_viewer_set_defaults(m_d, defaults_json.utf16(), defaults_json.size());
+}
+void ImageViewer::set_density(const QString& density_json) const
+{
+    return image_viewer_set_density(m_d, density_json.utf16(), density_json.size());
+}
 void ImageViewer::set_image_path(const QString& path) const
 {
     return image_viewer_set_image_path(m_d, path.utf16(), path.size());
 }
+void ImageViewer::set_selection_mask(const QString& mask_json) const
+{
+    return image_viewer_set_selection_mask(m_d, mask_json.utf16(), mask_json.size());
+}
+void ImageViewer::set_window_state(const QString& window_json) const
+{
+    return image_viewer_set_window_state(m_d, window_json.utf16(), window_json.size());
+}
 void ImageViewer::start_watcher(const QString& path) const
 {
     return image_viewer_start_watcher(m_d, path.utf16(), path.size());
 }
+void ImageViewer::tile_preview(const QString& repeat_json) const
+{
+    return image_viewer_tile_preview(m_d, repeat_json.utf16(), repeat_json.size());
+}
+bool ImageViewer::undo_edit() const
+{
+    return image_viewer_undo_edit(m_d);
+}
+QString ImageViewer::visible_tiles(const QString& viewport_json) const
+{
+    QString s;
+    image_viewer_visible_tiles(m_d, viewport_json.utf16(), viewport_json.size(), &s, set_qstring);
+    return s;
+}
+namespace {
+}
+extern "C" {
+    quint32 palette_stats_data_bbox_height(const PaletteStats::Private*, int);
+    quint32 palette_stats_data_bbox_width(const PaletteStats::Private*, int);
+    quint32 palette_stats_data_bbox_x(const PaletteStats::Private*, int);
+    quint32 palette_stats_data_bbox_y(const PaletteStats::Private*, int);
+    void palette_stats_data_color(const PaletteStats::Private*, int, QString*, qstring_set);
+    double palette_stats_data_percentage(const PaletteStats::Private*, int);
+    quint64 palette_stats_data_pixel_count(const PaletteStats::Private*, int);
+    quint32 palette_stats_data_region_count(const PaletteStats::Private*, int);
+    void palette_stats_sort(PaletteStats::Private*, unsigned char column, Qt::SortOrder order = Qt::AscendingOrder);
+
+    int palette_stats_row_count(const PaletteStats::Private*);
+    bool palette_stats_insert_rows(PaletteStats::Private*, int, int);
+    bool palette_stats_remove_rows(PaletteStats::Private*, int, int);
+    bool palette_stats_can_fetch_more(const PaletteStats::Private*);
+    void palette_stats_fetch_more(PaletteStats::Private*);
+}
+int PaletteStats::columnCount(const QModelIndex &parent) const
+{
+    return (parent.isValid()) ? 0 : 1;
+}
+
+bool PaletteStats::hasChildren(const QModelIndex &parent) const
+{
+    return rowCount(parent) > 0;
+}
+
+int PaletteStats::rowCount(const QModelIndex &parent) const
+{
+    return (parent.isValid()) ? 0 : palette_stats_row_count(m_d);
+}
+
+bool PaletteStats::insertRows(int row, int count, const QModelIndex &)
+{
+    return palette_stats_insert_rows(m_d, row, count);
+}
+
+bool PaletteStats::removeRows(int row, int count, const QModelIndex &)
+{
+    return palette_stats_remove_rows(m_d, row, count);
+}
+
+QModelIndex PaletteStats::index(int row, int column, const QModelIndex &parent) const
+{
+    if (!parent.isValid() && row >= 0 && row < rowCount(parent) && column >= 0 && column < 1) {
+        return createIndex(row, column, (quintptr)row);
+    }
+    return QModelIndex();
+}
+
+QModelIndex PaletteStats::parent(const QModelIndex &) const
+{
+    return QModelIndex();
+}
+
+bool PaletteStats::canFetchMore(const QModelIndex &parent) const
+{
+    return (parent.isValid()) ? 0 : palette_stats_can_fetch_more(m_d);
+}
+
+void PaletteStats::fetchMore(const QModelIndex &parent)
+{
+    if (!parent.isValid()) {
+        palette_stats_fetch_more(m_d);
+    }
+}
+void PaletteStats::updatePersistentIndexes() {}
+
+void PaletteStats::sort(int column, Qt::SortOrder order)
+{
+    palette_stats_sort(m_d, column, order);
+}
+Qt::ItemFlags PaletteStats::flags(const QModelIndex &i) const
+{
+    auto flags = QAbstractItemModel::flags(i);
+    return flags;
+}
+
+quint32 PaletteStats::bbox_height(int row) const
+{
+    return palette_stats_data_bbox_height(m_d, row);
+}
+
+quint32 PaletteStats::bbox_width(int row) const
+{
+    return palette_stats_data_bbox_width(m_d, row);
+}
+
+quint32 PaletteStats::bbox_x(int row) const
+{
+    return palette_stats_data_bbox_x(m_d, row);
+}
+
+quint32 PaletteStats::bbox_y(int row) const
+{
+    return palette_stats_data_bbox_y(m_d, row);
+}
+
+QString PaletteStats::color(int row) const
+{
+    QString s;
+    palette_stats_data_color(m_d, row, &s, set_qstring);
+    return s;
+}
+
+double PaletteStats::percentage(int row) const
+{
+    return palette_stats_data_percentage(m_d, row);
+}
+
+quint64 PaletteStats::pixel_count(int row) const
+{
+    return palette_stats_data_pixel_count(m_d, row);
+}
+
+quint32 PaletteStats::region_count(int row) const
+{
+    return palette_stats_data_region_count(m_d, row);
+}
+
+QVariant PaletteStats::data(const QModelIndex &index, int role) const
+{
+    Q_ASSERT(rowCount(index.parent()) > index.row());
+    switch (index.column()) {
+    case 0:
+        switch (role) {
+        case Qt::UserRole + 0:
+            return QVariant::fromValue(bbox_height(index.row()));
+        case Qt::UserRole + 1:
+            return QVariant::fromValue(bbox_width(index.row()));
+        case Qt::UserRole + 2:
+            return QVariant::fromValue(bbox_x(index.row()));
+        case Qt::UserRole + 3:
+            return QVariant::fromValue(bbox_y(index.row()));
+        case Qt::UserRole + 4:
+            return QVariant::fromValue(color(index.row()));
+        case Qt::UserRole + 5:
+            return QVariant::fromValue(percentage(index.row()));
+        case Qt::UserRole + 6:
+            return QVariant::fromValue(pixel_count(index.row()));
+        case Qt::UserRole + 7:
+            return QVariant::fromValue(region_count(index.row()));
+        }
+        break;
+    }
+    return QVariant();
+}
+
+int PaletteStats::role(const char* name) const {
+    auto names = roleNames();
+    auto i = names.constBegin();
+    while (i != names.constEnd()) {
+        if (i.value() == name) {
+            return i.key();
+        }
+        ++i;
+    }
+    return -1;
+}
+QHash<int, QByteArray> PaletteStats::roleNames() const {
+    QHash<int, QByteArray> names = QAbstractItemModel::roleNames();
+    names.insert(Qt::UserRole + 0, "bbox_height");
+    names.insert(Qt::UserRole + 1, "bbox_width");
+    names.insert(Qt::UserRole + 2, "bbox_x");
+    names.insert(Qt::UserRole + 3, "bbox_y");
+    names.insert(Qt::UserRole + 4, "color");
+    names.insert(Qt::UserRole + 5, "percentage");
+    names.insert(Qt::UserRole + 6, "pixel_count");
+    names.insert(Qt::UserRole + 7, "region_count");
+    return names;
+}
+QVariant PaletteStats::headerData(int section, Qt::Orientation orientation, int role) const
+{
+    if (orientation != Qt::Horizontal) {
+        return QVariant();
+    }
+    return m_headerData.value(qMakePair(section, (Qt::ItemDataRole)role), role == Qt::DisplayRole ?QString::number(section + 1) :QVariant());
+}
+
+bool PaletteStats::setHeaderData(int section, Qt::Orientation orientation, const QVariant &value, int role)
+{
+    if (orientation != Qt::Horizontal) {
+        return false;
+    }
+    m_headerData.insert(qMakePair(section, (Qt::ItemDataRole)role), value);
+    return true;
+}
+
+extern "C" {
+    PaletteStats::Private* palette_stats_new(PaletteStats*,
+        void (*)(const PaletteStats*),
+        void (*)(PaletteStats*),
+        void (*)(PaletteStats*),
+        void (*)(PaletteStats*, quintptr, quintptr),
+        void (*)(PaletteStats*),
+        void (*)(PaletteStats*),
+        void (*)(PaletteStats*, int, int),
+        void (*)(PaletteStats*),
+        void (*)(PaletteStats*, int, int, int),
+        void (*)(PaletteStats*),
+        void (*)(PaletteStats*, int, int),
+        void (*)(PaletteStats*));
+    void palette_stats_free(PaletteStats::Private*);
+    void palette_stats_refresh(PaletteStats::Private*, const ushort*, int);
+};
+
+PaletteStats::PaletteStats(bool /*owned*/, QObject *parent):
+    QAbstractItemModel(parent),
+    m_d(nullptr),
+    m_ownsPrivate(false)
+{
+    initHeaderData();
+}
+
+PaletteStats::PaletteStats(QObject *parent):
+    QAbstractItemModel(parent),
+    m_d(palette_stats_new(this,
+        [](const PaletteStats* o) {
+            Q_EMIT o->newDataReady(QModelIndex());
+        },
+        [](PaletteStats* o) {
+            Q_EMIT o->layoutAboutToBeChanged();
+        },
+        [](PaletteStats* o) {
+            o->updatePersistentIndexes();
+            Q_EMIT o->layoutChanged();
+        },
+        [](PaletteStats* o, quintptr first, quintptr last) {
+            o->dataChanged(o->createIndex(first, 0, first),
+                       o->createIndex(last, 0, last));
+        },
+        [](PaletteStats* o) {
+            o->beginResetModel();
+        },
+        [](PaletteStats* o) {
+            o->endResetModel();
+        },
+        [](PaletteStats* o, int first, int last) {
+            o->beginInsertRows(QModelIndex(), first, last);
+        },
+        [](PaletteStats* o) {
+            o->endInsertRows();
+        },
+        [](PaletteStats* o, int first, int last, int destination) {
+            o->beginMoveRows(QModelIndex(), first, last, QModelIndex(), destination);
+        },
+        [](PaletteStats* o) {
+            o->endMoveRows();
+        },
+        [](PaletteStats* o, int first, int last) {
+            o->beginRemoveRows(QModelIndex(), first, last);
+        },
+        [](PaletteStats* o) {
+            o->endRemoveRows();
+        }
+)),
+    m_ownsPrivate(true)
+{
+    connect(this, &PaletteStats::newDataReady, this, [this](const QModelIndex& i) {
+        this->fetchMore(i);
+    }, Qt::QueuedConnection);
+    initHeaderData();
+}
+
+PaletteStats::~PaletteStats() {
+    if (m_ownsPrivate) {
+        palette_stats_free(m_d);
+    }
+}
+void PaletteStats::initHeaderData() {
+}
+void PaletteStats::refresh(const QString& image_path)
+{
+    return palette_stats_refresh(m_d, image_path.utf16(), image_path.size());
+}
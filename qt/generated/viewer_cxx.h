@@ -6,6 +6,7 @@
 #include <QtCore/QAbstractItemModel>
 
 class ImageViewer;
+class PaletteStats;
 
 class ImageViewer : public QObject
 {
@@ -15,27 +16,134 @@ public:
 private:
     Private * m_d;
     bool m_ownsPrivate;
+    Q_PROPERTY(QString defaults READ defaults NOTIFY defaultsChanged FINAL)
     Q_PROPERTY(QString display_path READ display_path NOTIFY display_pathChanged FINAL)
+    Q_PROPERTY(bool export_gray_codes READ export_gray_codes NOTIFY export_gray_codesChanged FINAL)
     Q_PROPERTY(bool has_pending READ has_pending NOTIFY has_pendingChanged FINAL)
     Q_PROPERTY(QString image_path READ image_path NOTIFY image_pathChanged FINAL)
+    Q_PROPERTY(QString last_export_dir READ last_export_dir NOTIFY last_export_dirChanged FINAL)
+    Q_PROPERTY(QString last_threshold READ last_threshold NOTIFY last_thresholdChanged FINAL)
+    Q_PROPERTY(QString mask_overlay_path READ mask_overlay_path NOTIFY mask_overlay_pathChanged FINAL)
+    Q_PROPERTY(QString preview_path READ preview_path NOTIFY preview_pathChanged FINAL)
+    Q_PROPERTY(QString recent_files READ recent_files NOTIFY recent_filesChanged FINAL)
+    Q_PROPERTY(QString result_path READ result_path NOTIFY result_pathChanged FINAL)
+    Q_PROPERTY(QString window_state READ window_state NOTIFY window_stateChanged FINAL)
     explicit ImageViewer(bool owned, QObject *parent);
 public:
     explicit ImageViewer(QObject *parent = nullptr);
     ~ImageViewer();
+    QString defaults() const;
     QString display_path() const;
+    bool export_gray_codes() const;
     bool has_pending() const;
     QString image_path() const;
+    QString last_export_dir() const;
+    QString last_threshold() const;
+    QString mask_overlay_path() const;
+    QString preview_path() const;
+    QString recent_files() const;
+    QString result_path() const;
+    QString window_state() const;
+    Q_INVOKABLE void adjust_tone(const QString& tone_json) const;
+    Q_INVOKABLE QString analyze_seams(const QString& repeat_json) const;
+    Q_INVOKABLE void apply_color_mapping() const;
     Q_INVOKABLE void apply_threshold_mapping(const QString& thresholds_json) const;
-    Q_INVOKABLE void cleanup_scattered_pixels() const;
+    Q_INVOKABLE void apply_transform(const QString& transform_json) const;
+    Q_INVOKABLE void cleanup_scattered_pixels(const QString& wrap_mode) const;
     Q_INVOKABLE void cleanup_temp_files() const;
+    Q_INVOKABLE void clear_recent_files() const;
+    Q_INVOKABLE QString compare(const QString& compare_json) const;
+    Q_INVOKABLE void denoise(const QString& denoise_json) const;
+    Q_INVOKABLE quint32 edit_pixels(const QString& edit_json) const;
+    Q_INVOKABLE void export_color_mapping(const QString& output_path, bool gray_codes) const;
+    Q_INVOKABLE QString get_color_table() const;
+    Q_INVOKABLE QString get_density() const;
+    Q_INVOKABLE QString get_selection_mask() const;
     Q_INVOKABLE void gray_preview() const;
+    Q_INVOKABLE QString grid_geometry(const QString& grid_json) const;
+    Q_INVOKABLE QString inspect(qint32 x, qint32 y) const;
+    Q_INVOKABLE quint32 isolate_level(const QString& isolate_json) const;
+    Q_INVOKABLE quint32 merge_levels(const QString& merge_json) const;
+    Q_INVOKABLE quint32 merge_small_regions(const QString& regions_json) const;
+    Q_INVOKABLE QString mode_filter(const QString& mode_filter_json) const;
+    Q_INVOKABLE void preview_threshold_mapping(const QString& thresholds_json) const;
+    Q_INVOKABLE void quantize_colors(const QString& quantize_json) const;
+    Q_INVOKABLE bool redo_edit() const;
     Q_INVOKABLE void refresh_display() const;
+    Q_INVOKABLE quint32 replace_color(const QString& replace_json) const;
+    Q_INVOKABLE QString run_pipeline(const QString& pipeline_json) const;
     Q_INVOKABLE void save_processed() const;
+    Q_INVOKABLE void set_color_table(const QString& color_table_json) const;
+    Q_INVOKABLE void set_defaults(const QString& defaults_json) const;
+    Q_INVOKABLE void set_density(const QString& density_json) const;
     Q_INVOKABLE void set_image_path(const QString& path) const;
+    Q_INVOKABLE void set_selection_mask(const QString& mask_json) const;
+    Q_INVOKABLE void set_window_state(const QString& window_json) const;
     Q_INVOKABLE void start_watcher(const QString& path) const;
+    Q_INVOKABLE void tile_preview(const QString& repeat_json) const;
+    Q_INVOKABLE bool undo_edit() const;
+    Q_INVOKABLE QString visible_tiles(const QString& viewport_json) const;
 Q_SIGNALS:
+    void defaultsChanged();
     void display_pathChanged();
+    void export_gray_codesChanged();
     void has_pendingChanged();
     void image_pathChanged();
+    void last_export_dirChanged();
+    void last_thresholdChanged();
+    void mask_overlay_pathChanged();
+    void preview_pathChanged();
+    void recent_filesChanged();
+    void result_pathChanged();
+    void window_stateChanged();
+};
+
+class PaletteStats : public QAbstractItemModel
+{
+    Q_OBJECT
+public:
+    class Private;
+private:
+    Private * m_d;
+    bool m_ownsPrivate;
+    explicit PaletteStats(bool owned, QObject *parent);
+public:
+    explicit PaletteStats(QObject *parent = nullptr);
+    ~PaletteStats();
+    Q_INVOKABLE void refresh(const QString& image_path);
+
+    int columnCount(const QModelIndex &parent = QModelIndex()) const override;
+    QVariant data(const QModelIndex &index, int role = Qt::DisplayRole) const override;
+    QModelIndex index(int row, int column, const QModelIndex &parent = QModelIndex()) const override;
+    QModelIndex parent(const QModelIndex &index) const override;
+    bool hasChildren(const QModelIndex &parent = QModelIndex()) const override;
+    int rowCount(const QModelIndex &parent = QModelIndex()) const override;
+    bool canFetchMore(const QModelIndex &parent) const override;
+    void fetchMore(const QModelIndex &parent) override;
+    Qt::ItemFlags flags(const QModelIndex &index) const override;
+    void sort(int column, Qt::SortOrder order = Qt::AscendingOrder) override;
+    int role(const char* name) const;
+    QHash<int, QByteArray> roleNames() const override;
+    QVariant headerData(int section, Qt::Orientation orientation, int role = Qt::DisplayRole) const override;
+    bool setHeaderData(int section, Qt::Orientation orientation, const QVariant &value, int role = Qt::EditRole) override;
+    Q_INVOKABLE bool insertRows(int row, int count, const QModelIndex &parent = QModelIndex()) override;
+    Q_INVOKABLE bool removeRows(int row, int count, const QModelIndex &parent = QModelIndex()) override;
+    Q_INVOKABLE quint32 bbox_height(int row) const;
+    Q_INVOKABLE quint32 bbox_width(int row) const;
+    Q_INVOKABLE quint32 bbox_x(int row) const;
+    Q_INVOKABLE quint32 bbox_y(int row) const;
+    Q_INVOKABLE QString color(int row) const;
+    Q_INVOKABLE double percentage(int row) const;
+    Q_INVOKABLE quint64 pixel_count(int row) const;
+    Q_INVOKABLE quint32 region_count(int row) const;
+
+Q_SIGNALS:
+    // new data is ready to be made available to the model with fetchMore()
+    void newDataReady(const QModelIndex &parent) const;
+private:
+    QHash<QPair<int,Qt::ItemDataRole>, QVariant> m_headerData;
+    void initHeaderData();
+    void updatePersistentIndexes();
+Q_SIGNALS:
 };
 #endif // VIEWER_CXX_H
@@ -18,6 +18,9 @@ int main(int argc, char *argv[]) {
     // 由 rust_qt_binding_generator 生成的 C++ 包装类
     ImageViewer viewer;
     engine.rootContext()->setContextProperty("viewer", &viewer);
+    // 颜色统计列表模型，QML 在处理结果变化后刷新
+    PaletteStats paletteStats;
+    engine.rootContext()->setContextProperty("paletteStats", &paletteStats);
 
     // 主 QML 文件通过资源系统加载
     const QUrl url(QStringLiteral("qrc:/qml/main.qml"));
@@ -6,7 +6,11 @@ import QtQuick.Controls 2.15
 // - 输入段数并自动生成等距初值
 // - 在单条轨道上拖动多个滑块以设定各段阈值（0..255）
 // - 选择“平均/分段”两种映射模式
+// - 选择抖动方式（无 / 误差扩散 / 有序 Bayer）
+// - 调整滑块、模式或抖动时发出 previewRequested，主界面以缩小的代理图实时预览
 // - 点击“确认映射”后，通过信号将 stops 与模式传回主界面
+// - 确认后可选择某一段单独查看（其余段淡化或隐藏）
+// - 打开时可用 restore() 恢复上次确认的阈值、模式与抖动选项
 Window {
     id: dlg
     width: 520
@@ -25,8 +29,54 @@ Window {
     property var segmentStops: []
     // 映射模式：true为平均模式，false为分段模式
     property bool averageMode: true
+    // 抖动选项，对应 Rust 侧 DitherOptions（mode 为 "none" 时不抖动）
+    property var dither: ({ mode: "none" })
 
-    signal confirmed(var stops, bool isAverageMode) // 确认下方按钮时发出，stops为[0..255]数组，isAverageMode为映射模式
+    signal confirmed(var stops, bool isAverageMode, var dither) // 确认下方按钮时发出，stops为[0..255]数组，isAverageMode为映射模式，dither为抖动选项
+    signal previewRequested(var stops, bool isAverageMode, var dither) // 参数同 confirmed，拖动过程中合并为每 30ms 最多一次
+    signal previewEnded() // 关闭窗口时发出，主界面结束预览
+    signal levelSelected(int level, bool hideOthers) // 选择单独查看的段，level 为 -1 时退出单段查看
+
+    // 已确认映射的段数（单段查看基于工程中已确认的映射）与当前单独查看的段
+    property int confirmedLevels: 0
+    property int isolatedLevel: -1
+    property bool hideOthers: false
+
+    // 从小到大排序并去掉重复值（Rust 侧要求 stops 严格递增，两个滑块重合时只算一个阈值）
+    function sortedStops() {
+        return segmentStops.slice(0).sort(function(a, b){ return a - b; }).filter(function(v, i, a){ return i === 0 || v !== a[i - 1]; });
+    }
+    // 恢复一次阈值映射（格式同 apply_threshold_mapping 的 JSON），不触发预览
+    function restore(mapping) {
+        var stops = mapping.stops || []
+        if (stops.length > 7) return
+        var d = mapping.dither || { mode: "none" }
+        segmentCount = stops.length
+        segmentStops = stops.slice(0)
+        averageMode = mapping.averageMode !== false
+        dither = { mode: d.mode || "none", bayerSize: d.bayerSize || 4, serpentine: d.serpentine !== false, opaqueOnly: d.opaqueOnly !== false }
+        countInput.text = String(stops.length)
+        for (var i = 0; i < ditherBox.model.length; i++) {
+            var item = ditherBox.model[i]
+            if (item.mode === dither.mode && (item.mode !== "bayer" || item.bayerSize === dither.bayerSize)) ditherBox.currentIndex = i
+        }
+        serpentineBox.checked = dither.serpentine
+        opaqueOnlyBox.checked = dither.opaqueOnly
+        previewTimer.stop()
+    }
+    // 合并短时间内的多次调整，避免每个鼠标事件都重新渲染预览
+    function requestPreview() { previewTimer.restart() }
+
+    Timer {
+        id: previewTimer
+        interval: 30
+        onTriggered: if (segmentStops.length > 0) dlg.previewRequested(dlg.sortedStops(), averageMode, dither)
+    }
+
+    onSegmentStopsChanged: requestPreview()
+    onAverageModeChanged: requestPreview()
+    onDitherChanged: requestPreview()
+    onClosing: { previewTimer.stop(); dlg.previewEnded() }
 
     Column {
         anchors.fill: parent
@@ -181,15 +231,93 @@ Window {
             }
         }
 
+        // 抖动方式选择
+        Row {
+            spacing: 16
+            Text {
+                color: "#e8eaed"
+                text: "抖动： (Dithering:)"
+                anchors.verticalCenter: parent.verticalCenter
+            }
+            ComboBox {
+                id: ditherBox
+                width: 260
+                textRole: "label"
+                model: [
+                    { label: "无 (None)", mode: "none" },
+                    { label: "Floyd–Steinberg", mode: "floydSteinberg" },
+                    { label: "Atkinson", mode: "atkinson" },
+                    { label: "Jarvis–Judice–Ninke", mode: "jarvisJudiceNinke" },
+                    { label: "Bayer 2×2", mode: "bayer", bayerSize: 2 },
+                    { label: "Bayer 4×4", mode: "bayer", bayerSize: 4 },
+                    { label: "Bayer 8×8", mode: "bayer", bayerSize: 8 }
+                ]
+                onActivated: {
+                    var item = model[currentIndex]
+                    dlg.dither = { mode: item.mode, bayerSize: item.bayerSize || 4, serpentine: serpentineBox.checked, opaqueOnly: opaqueOnlyBox.checked }
+                }
+            }
+            CheckBox {
+                id: serpentineBox
+                text: "蛇形 (Serpentine)"
+                checked: true
+                onToggled: { dlg.dither.serpentine = checked; dlg.requestPreview() }
+            }
+            CheckBox {
+                id: opaqueOnlyBox
+                text: "仅不透明 (Opaque only)"
+                checked: true
+                onToggled: { dlg.dither.opaqueOnly = checked; dlg.requestPreview() }
+            }
+        }
+
         Button {
             text: "确认映射 (Confirm Mapping)"
             onClicked: {
-                // 输出排序后的 stops，确保从小到大
-                var sorted = segmentStops.slice(0).sort(function(a, b){ return a - b; });
-                dlg.confirmed(sorted, averageMode);
+                // 输出排序后的 stops，确保从小到大；确认后以全分辨率结果替换预览
+                previewTimer.stop();
+                dlg.confirmed(dlg.sortedStops(), averageMode, dither);
+                dlg.confirmedLevels = dlg.sortedStops().length + 1;
+                dlg.isolatedLevel = -1;
                 // 不关闭窗口，保持打开状态
             }
         }
+
+        // 单段查看：点击段号只显示该段，再次点击退出
+        Row {
+            spacing: 8
+            visible: dlg.confirmedLevels > 0
+            Text {
+                color: "#e8eaed"
+                text: "单段查看： (Isolate level:)"
+                anchors.verticalCenter: parent.verticalCenter
+            }
+            Repeater {
+                model: dlg.confirmedLevels
+                delegate: Rectangle {
+                    width: 32; height: 32
+                    radius: 4
+                    color: dlg.isolatedLevel === index ? "#1e8e3e" : "#3c4043"
+                    border.color: dlg.isolatedLevel === index ? "#34a853" : "#5f6368"
+                    Text { anchors.centerIn: parent; color: "#e8eaed"; text: index }
+                    MouseArea {
+                        anchors.fill: parent
+                        onClicked: {
+                            dlg.isolatedLevel = dlg.isolatedLevel === index ? -1 : index
+                            dlg.levelSelected(dlg.isolatedLevel, dlg.hideOthers)
+                        }
+                    }
+                }
+            }
+            CheckBox {
+                text: "隐藏其余段 (Hide others)"
+                checked: dlg.hideOthers
+                onToggled: {
+                    dlg.hideOthers = checked
+                    if (dlg.isolatedLevel >= 0) dlg.levelSelected(dlg.isolatedLevel, checked)
+                }
+            }
+        }
     }
 }
 
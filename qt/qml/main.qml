@@ -13,12 +13,183 @@ Window {
     property real zoom: 1.0
     property string currentPath: typeof argvPath !== 'undefined' ? argvPath : ""
     property int cacheBust: 0
+    // 阈值实时预览图单独计数：预览每次刷新只重新加载代理图，不重新加载原尺寸的显示图与选区叠加层
+    property int previewBust: 0
+    // 是否显示选区叠加层（M 键切换）
+    property bool showMask: true
+    // 循环平铺预览：T 键依次切换 关闭 -> 直接 -> 半落 -> 半砖 -> 镜像
+    property var tileModes: ["straight", "halfDrop", "halfBrick", "mirror"]
+    property int tileMode: -1
+    property string seamReport: ""
+    // 清理散点的循环边界：W 键依次切换 none -> horizontal -> vertical -> both -> halfDrop
+    property var wrapModes: ["none", "horizontal", "vertical", "both", "halfDrop"]
+    property string wrapMode: "none"
+    // 像素编辑工具：P 键铅笔、B 键油漆桶，再按一次取消；Ctrl+Z 撤销、Ctrl+Y 重做
+    property string editTool: ""
+    property var penColor: [0, 0, 0, 255]
+    // 鼠标悬停处的像素信息（inspect 返回的 JSON 格式化后的文本）
+    property string pixelInfo: ""
+    // 前后对比：C 键依次切换 关闭 -> 并排 -> 分割（在图上拖动分割线）-> 差异，V 键切换对比对象（原图 / 上一步）
+    property var compareModes: ["sideBySide", "split", "difference"]
+    property int compareMode: -1
+    property string compareBefore: "original"
+    property real compareSplit: 0.5
+    // 分割对比时的对比对象图片（叠放在当前结果上并按分割线裁剪），不在分割对比时为空
+    property string compareBeforePath: ""
+    // 差异图中变化像素与分割线的颜色
+    property var diffColor: [255, 0, 0, 255]
+    property string compareReport: ""
+    // 单段查看：-1 表示关闭；isolateReport 显示该段的像素数
+    property int isolatedLevel: -1
+    property bool isolateHideOthers: false
+    property string isolateReport: ""
+    // 织物网格：G 键切换；每隔 gridMajor 格画粗线，厘米刻度按工程中的经纬密度换算
+    property bool showGrid: false
+    property int gridMajor: 10
+    // 最近打开的文件（应用设置中保存，最近的在前）
+    property var recentFiles: {
+        try { return JSON.parse(viewer.recent_files) } catch (e) { return [] }
+    }
+    // 透明像素与灰度预览的默认选项（应用设置中保存）：{ opaqueOnly, grayKeepAlpha, grayOnOpen }
+    property var processingDefaults: {
+        try { return JSON.parse(viewer.defaults) } catch (e) { return { opaqueOnly: true, grayKeepAlpha: false, grayOnOpen: false } }
+    }
+    function setDefault(key, value) {
+        var d = { opaqueOnly: processingDefaults.opaqueOnly, grayKeepAlpha: processingDefaults.grayKeepAlpha, grayOnOpen: processingDefaults.grayOnOpen }
+        d[key] = value
+        viewer.set_defaults(JSON.stringify(d))
+    }
+
+    // 启动时恢复上次的窗口大小与缩放倍数，关闭时保存
+    Component.onCompleted: {
+        if (viewer.window_state.length === 0) return
+        try {
+            var state = JSON.parse(viewer.window_state)
+            width = state.width
+            height = state.height
+            zoom = state.zoom
+        } catch (e) {}
+    }
+    onClosing: viewer.set_window_state(JSON.stringify({ width: width, height: height, zoom: zoom }))
+
+    function openImage(p) {
+        viewer.set_image_path(p)
+        cacheBust = cacheBust + 1
+    }
+    // 分块显示：超大图片由 Rust 侧按视口与缩放倍数切出可见分块，tileLayout 为 visible_tiles 返回的布局
+    // plainSource 为整张显示时 img 的来源，由 updateTiles 设置，保证超大图片不会先被整张加载
+    property var tileLayout: null
+    property bool tiledView: tileLayout !== null && tileLayout.tiled
+    property string plainSource: ""
+    property int imageWidth: tileLayout ? tileLayout.width : img.implicitWidth
+    property int imageHeight: tileLayout ? tileLayout.height : img.implicitHeight
+
+    function updateTiles() {
+        if (viewer.display_path.length === 0) { tileLayout = null; plainSource = ""; return }
+        var viewport = {
+            x: (flick.contentX - content.x) / zoom,
+            y: (flick.contentY - content.y) / zoom,
+            width: flick.width / zoom,
+            height: flick.height / zoom,
+            zoom: zoom
+        }
+        var layout = null
+        try { layout = JSON.parse(viewer.visible_tiles(JSON.stringify(viewport))) } catch (e) { layout = null }
+        tileLayout = layout
+        // 无法取得分块布局（如读取文件头失败）时退回整张显示
+        plainSource = layout !== null && layout.tiled ? "" : ("file:///" + viewer.display_path + "?v=" + cacheBust)
+    }
+
+    function isolateLevel(level, hideOthers) {
+        isolatedLevel = level
+        isolateHideOthers = hideOthers
+        if (level < 0) {
+            viewer.isolate_level("")
+            isolateReport = ""
+        } else {
+            var n = viewer.isolate_level(JSON.stringify({ level: level, others: hideOthers ? "hide" : "dim" }))
+            isolateReport = "单段查看 (isolate) #" + level + "  像素 (pixels): " + n
+        }
+        cacheBust = cacheBust + 1
+    }
+
+    function updateCompare() {
+        if (compareMode < 0) {
+            viewer.compare("")
+            compareReport = ""
+            compareBeforePath = ""
+        } else {
+            var report = null
+            try {
+                report = JSON.parse(viewer.compare(JSON.stringify({ before: compareBefore, mode: compareModes[compareMode], highlight: diffColor })))
+            } catch (e) { report = null }
+            compareBeforePath = report && report.beforePath ? report.beforePath : ""
+            compareReport = "对比 (compare) " + compareModes[compareMode] + ": " + (compareBefore === "original" ? "原图 (original)" : "上一步 (previous)") + " / 当前 (current)"
+                + (report && report.changed !== null ? "  变化像素 (changed): " + report.changed : "")
+        }
+        cacheBust = cacheBust + 1
+    }
+
+    function cycleCompare() {
+        compareMode = compareMode + 1 < compareModes.length ? compareMode + 1 : -1
+        updateCompare()
+    }
+
+    function inspectAt(viewX, viewY) {
+        var x = Math.floor((flick.contentX + viewX - content.x) / zoom)
+        var y = Math.floor((flick.contentY + viewY - content.y) / zoom)
+        var info = null
+        try { info = JSON.parse(viewer.inspect(x, y)) } catch (e) { info = null }
+        if (!info) { pixelInfo = ""; return }
+        var text = "(" + info.x + ", " + info.y + ") 原图 RGBA " + info.original.join(",") + "  亮度 (luma) " + info.luma
+        if (info.processed) text += "  结果 (result) " + info.processed.join(",")
+        if (info.level !== null && info.level !== undefined) text += "  段 (level) " + info.level
+        if (info.yarn) text += "  纱线 (yarn) " + info.yarn
+        if (info.structure) text += "  组织 (structure) " + info.structure
+        pixelInfo = text
+    }
+
+    function cycleTilePreview() {
+        tileMode = tileMode + 1 < tileModes.length ? tileMode + 1 : -1
+        if (tileMode < 0) {
+            viewer.tile_preview("")
+            seamReport = ""
+        } else {
+            var repeat = JSON.stringify({ mode: tileModes[tileMode], columns: 3, rows: 3 })
+            viewer.tile_preview(repeat)
+            var seams = {}
+            try { seams = JSON.parse(viewer.analyze_seams(repeat)) } catch (e) { seams = {} }
+            seamReport = seams.leftRight
+                ? (tileModes[tileMode] + " 接缝不一致 (seam mismatches): 左右 " + seams.leftRight.mismatched + "/" + seams.leftRight.total
+                   + " 行, 上下 " + seams.topBottom.mismatched + "/" + seams.topBottom.total + " 列")
+                : ""
+        }
+        cacheBust = cacheBust + 1
+    }
 
     // 当后端报告 image_path 变化时，更新一次 cacheBust 以强制刷新 Image 源
     Connections {
         target: viewer
         function onImage_pathChanged() { cacheBust = cacheBust + 1 }
+        function onMask_overlay_pathChanged() { cacheBust = cacheBust + 1 }
+        function onPreview_pathChanged() { previewBust = previewBust + 1 }
+        function onDisplay_pathChanged() { win.updateTiles() }
+        // 处理结果变化后重新统计颜色
+        function onResult_pathChanged() {
+            paletteStats.refresh(viewer.result_path)
+            // 分割对比叠放的是图片文件，处理结果变化后重新确定对比对象
+            if (win.compareBeforePath.length > 0) win.updateCompare()
+        }
+    }
+
+    // 滚动、缩放或图片变化后合并刷新可见分块
+    Timer {
+        id: tileTimer
+        interval: 30
+        onTriggered: win.updateTiles()
     }
+    onZoomChanged: tileTimer.restart()
+    onCacheBustChanged: updateTiles()
 
     Rectangle {
         anchors.fill: parent
@@ -32,18 +203,26 @@ Window {
             clip: true
             interactive: true
             boundsBehavior: Flickable.StopAtBounds
+            onContentXChanged: tileTimer.restart()
+            onContentYChanged: tileTimer.restart()
+            onWidthChanged: tileTimer.restart()
+            onHeightChanged: tileTimer.restart()
 
             Item {
                 id: content
-                width: img.implicitWidth * win.zoom
-                height: img.implicitHeight * win.zoom
+                width: win.imageWidth * win.zoom
+                height: win.imageHeight * win.zoom
                 x: width < flick.width ? (flick.width - width) / 2 : 0
                 y: height < flick.height ? (flick.height - height) / 2 : 0
 
-                // 棋盘格背景：便于观察透明像素区域
+                // 棋盘格背景：便于观察透明像素区域。只覆盖视口内的部分，
+                // 左上角按两格（32 像素）对齐，图案随内容滚动而不错位
                 Rectangle {
                     id: checkerboard
-                    anchors.fill: parent
+                    x: Math.floor(Math.max(0, flick.contentX - content.x) / 32) * 32
+                    y: Math.floor(Math.max(0, flick.contentY - content.y) / 32) * 32
+                    width: Math.max(0, Math.min(content.width, flick.contentX - content.x + flick.width) - x)
+                    height: Math.max(0, Math.min(content.height, flick.contentY - content.y + flick.height) - y)
                     color: "#ffffff"
                     
                     // 使用Canvas绘制棋盘格
@@ -85,13 +264,16 @@ Window {
                     }
                 }
 
-                // 实际展示的图片：优先显示 display_path（临时处理结果），否则显示原图
+                // 实际展示的图片：优先显示 display_path（临时处理结果），否则显示原图。
+                // 尺寸由 visible_tiles 读取文件头得到；超大图片改为分块显示，不整张加载（source 为空）
                 Image {
                     id: img
                     anchors.fill: parent
                     cache: false
                     fillMode: Image.Stretch
-                    source: viewer.display_path && viewer.display_path.length > 0 ? ("file:///" + viewer.display_path + "?v=" + win.cacheBust) : (viewer.image_path && viewer.image_path.length > 0 ? ("file:///" + viewer.image_path + "?v=" + win.cacheBust) : "")
+                    // 阈值实时预览时隐藏
+                    visible: viewer.preview_path.length === 0
+                    source: win.plainSource
                     smooth: false
                     antialiasing: false
                     onStatusChanged: {
@@ -102,12 +284,146 @@ Window {
                     }
                     Component.onCompleted: console.log("Image completed, source:", source)
                 }
+
+                // 分块显示：每个分块按其在原图中的位置与大小拉伸显示（粗层分块覆盖多个原图像素）
+                Repeater {
+                    model: win.tiledView ? win.tileLayout.tiles : []
+                    Image {
+                        x: modelData.x * win.zoom
+                        y: modelData.y * win.zoom
+                        width: modelData.width * win.zoom
+                        height: modelData.height * win.zoom
+                        visible: viewer.preview_path.length === 0
+                        fillMode: Image.Stretch
+                        // 分块文件名带有版本号，内容变化时路径随之变化，可使用图片缓存
+                        source: "file:///" + modelData.path
+                        smooth: false
+                        antialiasing: false
+                    }
+                }
+
+                // 分割对比：对比对象只加载一次，裁剪到分割线左侧叠放在当前结果上；拖动分割线只改变裁剪宽度。
+                // 分块显示时限制解码尺寸，不整张加载超大图片
+                Item {
+                    id: splitClip
+                    anchors.left: parent.left
+                    anchors.top: parent.top
+                    anchors.bottom: parent.bottom
+                    width: parent.width * win.compareSplit
+                    clip: true
+                    visible: win.compareBeforePath.length > 0 && viewer.preview_path.length === 0
+                    Image {
+                        width: splitClip.parent.width
+                        height: splitClip.height
+                        cache: false
+                        fillMode: Image.Stretch
+                        sourceSize: win.tiledView ? Qt.size(4096, 4096) : undefined
+                        source: win.compareBeforePath.length > 0 ? ("file:///" + win.compareBeforePath + "?v=" + win.cacheBust) : ""
+                        smooth: false
+                        antialiasing: false
+                    }
+                }
+                Rectangle {
+                    visible: splitClip.visible
+                    x: splitClip.width
+                    width: Math.max(1, win.zoom)
+                    height: parent.height
+                    color: Qt.rgba(win.diffColor[0] / 255, win.diffColor[1] / 255, win.diffColor[2] / 255, win.diffColor[3] / 255)
+                }
+
+                // 阈值实时预览：缩小的代理图拉伸到原图尺寸显示
+                Image {
+                    id: previewImg
+                    anchors.fill: parent
+                    cache: false
+                    fillMode: Image.Stretch
+                    visible: viewer.preview_path.length > 0
+                    source: viewer.preview_path.length > 0 ? ("file:///" + viewer.preview_path + "?v=" + win.previewBust) : ""
+                    smooth: false
+                    antialiasing: false
+                }
+
+                // 选区预览：选区外覆盖半透明暗色。叠加层与原图同尺寸，只在显示且非分块显示时加载
+                Image {
+                    id: maskOverlay
+                    anchors.fill: parent
+                    cache: false
+                    fillMode: Image.Stretch
+                    visible: win.showMask && viewer.mask_overlay_path.length > 0
+                    source: win.showMask && !win.tiledView && viewer.mask_overlay_path.length > 0 ? ("file:///" + viewer.mask_overlay_path + "?v=" + win.cacheBust) : ""
+                    smooth: false
+                    antialiasing: false
+                }
+
+                // 裁剪框：变换窗口打开时在图上拖拽框选（图像坐标 = 屏幕坐标 / zoom）
+                Rectangle {
+                    id: cropBox
+                    visible: transformWin.visible && transformWin.cropW > 0 && transformWin.cropH > 0
+                    x: transformWin.cropX * win.zoom
+                    y: transformWin.cropY * win.zoom
+                    width: transformWin.cropW * win.zoom
+                    height: transformWin.cropH * win.zoom
+                    color: "transparent"
+                    border.color: "#fbbc04"
+                    border.width: 2
+                }
+                // 像素编辑：铅笔拖拽时以直线连接相邻两次的位置
+                MouseArea {
+                    anchors.fill: parent
+                    enabled: win.editTool.length > 0 && !transformWin.visible
+                    preventStealing: true
+                    property int lastX: -1
+                    property int lastY: -1
+                    function edit(step) { viewer.edit_pixels(JSON.stringify(step)); win.cacheBust = win.cacheBust + 1 }
+                    onPressed: {
+                        var x = Math.floor(mouse.x / win.zoom), y = Math.floor(mouse.y / win.zoom)
+                        if (win.editTool === "fill") {
+                            edit({ tool: "fill", x: x, y: y, color: win.penColor, connectivity: 4 })
+                        } else {
+                            edit({ tool: "pixel", x: x, y: y, color: win.penColor })
+                            lastX = x; lastY = y
+                        }
+                    }
+                    onPositionChanged: {
+                        if (win.editTool !== "pencil") return
+                        var x = Math.floor(mouse.x / win.zoom), y = Math.floor(mouse.y / win.zoom)
+                        if (x === lastX && y === lastY) return
+                        edit({ tool: "line", x0: lastX, y0: lastY, x1: x, y1: y, color: win.penColor })
+                        lastX = x; lastY = y
+                    }
+                }
+                // 分割对比：拖动分割线，只移动叠放图片的裁剪边界，不重新生成对比图
+                MouseArea {
+                    anchors.fill: parent
+                    enabled: win.compareBeforePath.length > 0 && win.editTool.length === 0 && !transformWin.visible
+                    preventStealing: true
+                    function moveSplit(x) { win.compareSplit = Math.max(0, Math.min(1, x / width)) }
+                    onPressed: moveSplit(mouse.x)
+                    onPositionChanged: moveSplit(mouse.x)
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    enabled: transformWin.visible
+                    // 拖拽框选时不让 Flickable 抢走鼠标
+                    preventStealing: true
+                    property real startX: 0
+                    property real startY: 0
+                    function clampX(v) { return Math.max(0, Math.min(win.imageWidth, Math.round(v / win.zoom))) }
+                    function clampY(v) { return Math.max(0, Math.min(win.imageHeight, Math.round(v / win.zoom))) }
+                    onPressed: { startX = clampX(mouse.x); startY = clampY(mouse.y); transformWin.setCrop(startX, startY, 0, 0) }
+                    onPositionChanged: {
+                        var x = clampX(mouse.x), y = clampY(mouse.y)
+                        transformWin.setCrop(Math.min(startX, x), Math.min(startY, y), Math.abs(x - startX), Math.abs(y - startY))
+                    }
+                }
             }
 
             MouseArea {
                 anchors.fill: parent
                 acceptedButtons: Qt.NoButton
                 hoverEnabled: true
+                onPositionChanged: win.inspectAt(mouse.x, mouse.y)
+                onExited: win.pixelInfo = ""
                 onWheel: function(event) {
                     var oldZoom = win.zoom
                     var factor = event.angleDelta.y > 0 ? 1.1 : 1/1.1
@@ -148,6 +464,20 @@ Window {
                 text: "path=" + win.currentPath + "\nsource=" + img.source
             }
 
+            Text {
+                anchors.left: parent.left
+                anchors.leftMargin: 8
+                anchors.top: parent.top
+                anchors.topMargin: 8
+                color: "#fbbc04"
+                text: (win.pixelInfo.length > 0 ? win.pixelInfo + "\n" : "")
+                      + (win.editTool.length > 0 ? "编辑工具 (tool): " + win.editTool + "\n" : "")
+                      + (win.wrapMode !== "none" ? "循环边界 (wrap): " + win.wrapMode + "\n" : "")
+                      + (win.compareReport.length > 0 ? win.compareReport + "\n" : "")
+                      + (win.isolateReport.length > 0 ? win.isolateReport + "\n" : "") + win.seamReport
+                visible: text.length > 0
+            }
+
             Rectangle {
                 id: refreshBtn
                 width: refreshText.implicitWidth + 16; height: 32
@@ -265,15 +595,30 @@ Window {
                     onClicked: {
                         var w = Qt.createComponent("qrc:/qml/ThresholdWindow.qml").createObject(win)
                         if (w) {
-                            w.confirmed.connect(function(stops, isAverageMode){
+                            // 以上次确认的阈值映射为初值；没有时按默认选项设置“仅不透明”
+                            if (viewer.last_threshold.length > 0) {
+                                try { w.restore(JSON.parse(viewer.last_threshold)) } catch (e) {}
+                            } else {
+                                w.restore({ stops: [], dither: { mode: "none", opaqueOnly: win.processingDefaults.opaqueOnly } })
+                            }
+                            w.previewRequested.connect(function(stops, isAverageMode, dither){
+                                viewer.preview_threshold_mapping(JSON.stringify({ stops: stops, averageMode: isAverageMode, dither: dither }))
+                            })
+                            w.previewEnded.connect(function(){ viewer.preview_threshold_mapping("") })
+                            w.levelSelected.connect(function(level, hideOthers){ win.isolateLevel(level, hideOthers) })
+                            w.confirmed.connect(function(stops, isAverageMode, dither){
                                 console.log("阈值段数:", stops.length, "stops:", stops, "平均模式:", isAverageMode)
-                                // 将 stops 数组和映射模式转换为 JSON 字符串传给 Rust
+                                // 将 stops 数组、映射模式和抖动选项转换为 JSON 字符串传给 Rust
                                 var data = {
                                     stops: stops,
-                                    averageMode: isAverageMode
+                                    averageMode: isAverageMode,
+                                    dither: dither
                                 }
                                 var jsonStr = JSON.stringify(data)
                                 viewer.apply_threshold_mapping(jsonStr)
+                                colorWin.levelCount = stops.length + 1
+                                win.isolatedLevel = -1
+                                win.isolateReport = ""
                                 win.cacheBust = win.cacheBust + 1
                             })
                             w.visible = true
@@ -301,36 +646,717 @@ Window {
                 MouseArea {
                     anchors.fill: parent
                     onClicked: {
-                        viewer.cleanup_scattered_pixels()
+                        viewer.cleanup_scattered_pixels(win.wrapMode)
                         if (viewer.has_pending) { win.cacheBust = win.cacheBust + 1 }
                     }
                 }
             }
 
+            Rectangle {
+                id: colorMapBtn
+                width: colorMapText.implicitWidth + 16; height: 32
+                anchors.left: cleanupBtn.right
+                anchors.leftMargin: 8
+                anchors.bottom: parent.bottom
+                anchors.bottomMargin: 8
+                radius: 4
+                color: "#3c4043"
+                border.color: "#5f6368"
+                Text { 
+                    id: colorMapText
+                    anchors.centerIn: parent
+                    color: "#e8eaed"
+                    text: "颜色映射 (Colors)"
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: { colorWin.loadTable(); colorWin.visible = true }
+                }
+            }
+
+            Rectangle {
+                id: transformBtn
+                width: transformText.implicitWidth + 16; height: 32
+                anchors.left: colorMapBtn.right
+                anchors.leftMargin: 8
+                anchors.bottom: parent.bottom
+                anchors.bottomMargin: 8
+                radius: 4
+                color: "#3c4043"
+                border.color: "#5f6368"
+                Text { 
+                    id: transformText
+                    anchors.centerIn: parent
+                    color: "#e8eaed"
+                    text: "变换 (Transform)"
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: { transformWin.visible = true }
+                }
+            }
+
+            Rectangle {
+                id: statsBtn
+                width: statsText.implicitWidth + 16; height: 32
+                anchors.left: transformBtn.right
+                anchors.leftMargin: 8
+                anchors.bottom: parent.bottom
+                anchors.bottomMargin: 8
+                radius: 4
+                color: "#3c4043"
+                border.color: "#5f6368"
+                Text { 
+                    id: statsText
+                    anchors.centerIn: parent
+                    color: "#e8eaed"
+                    text: "统计 (Stats)"
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: { paletteStats.refresh(viewer.result_path); statsWin.visible = true }
+                }
+            }
+
+            Rectangle {
+                id: gridBtn
+                width: gridText.implicitWidth + 16; height: 32
+                anchors.left: statsBtn.right
+                anchors.leftMargin: 8
+                anchors.bottom: parent.bottom
+                anchors.bottomMargin: 8
+                radius: 4
+                color: "#3c4043"
+                border.color: "#5f6368"
+                Text { 
+                    id: gridText
+                    anchors.centerIn: parent
+                    color: "#e8eaed"
+                    text: "网格 (Grid)"
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: { gridWin.loadDensity(); gridWin.visible = true }
+                }
+            }
+
+            Rectangle {
+                id: recentBtn
+                width: recentText.implicitWidth + 16; height: 32
+                anchors.left: gridBtn.right
+                anchors.leftMargin: 8
+                anchors.bottom: parent.bottom
+                anchors.bottomMargin: 8
+                radius: 4
+                color: "#3c4043"
+                border.color: "#5f6368"
+                Text { 
+                    id: recentText
+                    anchors.centerIn: parent
+                    color: "#e8eaed"
+                    text: "最近 (Recent)"
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: recentWin.visible = true
+                }
+            }
+
+            Rectangle {
+                id: defaultsBtn
+                width: defaultsText.implicitWidth + 16; height: 32
+                anchors.left: recentBtn.right
+                anchors.leftMargin: 8
+                anchors.bottom: parent.bottom
+                anchors.bottomMargin: 8
+                radius: 4
+                color: "#3c4043"
+                border.color: "#5f6368"
+                Text {
+                    id: defaultsText
+                    anchors.centerIn: parent
+                    color: "#e8eaed"
+                    text: "默认 (Defaults)"
+                }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: defaultsWin.visible = true
+                }
+            }
+
             Keys.onPressed: {
                 if (event.key === Qt.Key_Plus || event.text === "+") { win.zoom = Math.min(win.zoom * 1.1, 20); event.accepted = true }
                 else if (event.key === Qt.Key_Minus || event.text === "-") { win.zoom = Math.max(win.zoom / 1.1, 0.05); event.accepted = true }
                 else if (event.key === Qt.Key_0) { win.zoom = 1.0; event.accepted = true }
-                else if (event.key === Qt.Key_Escape) { colorWin.visible = false }
+                else if (event.key === Qt.Key_M) { win.showMask = !win.showMask; event.accepted = true }
+                else if (event.key === Qt.Key_T) { win.cycleTilePreview(); event.accepted = true }
+                else if (event.key === Qt.Key_G) { win.showGrid = !win.showGrid; event.accepted = true }
+                else if (event.key === Qt.Key_C) { win.cycleCompare(); event.accepted = true }
+                else if (event.key === Qt.Key_V) {
+                    win.compareBefore = win.compareBefore === "original" ? "previous" : "original"
+                    if (win.compareMode >= 0) win.updateCompare()
+                    event.accepted = true
+                }
+                else if (event.key === Qt.Key_Z && (event.modifiers & Qt.ControlModifier)) { if (viewer.undo_edit()) win.cacheBust = win.cacheBust + 1; event.accepted = true }
+                else if (event.key === Qt.Key_Y && (event.modifiers & Qt.ControlModifier)) { if (viewer.redo_edit()) win.cacheBust = win.cacheBust + 1; event.accepted = true }
+                else if (event.key === Qt.Key_P) { win.editTool = win.editTool === "pencil" ? "" : "pencil"; event.accepted = true }
+                else if (event.key === Qt.Key_B) { win.editTool = win.editTool === "fill" ? "" : "fill"; event.accepted = true }
+                else if (event.key === Qt.Key_W) {
+                    win.wrapMode = win.wrapModes[(win.wrapModes.indexOf(win.wrapMode) + 1) % win.wrapModes.length]
+                    event.accepted = true
+                }
+                else if (event.key === Qt.Key_Escape) { colorWin.visible = false; transformWin.visible = false; statsWin.visible = false; gridWin.visible = false; recentWin.visible = false; defaultsWin.visible = false }
             }
             focus: true
         }
+
+        // 织物网格与厘米刻度：只绘制视口内的线，坐标由 Rust 侧 grid_geometry 按视口计算
+        Canvas {
+            id: gridCanvas
+            anchors.fill: flick
+            visible: win.showGrid
+            property var geometry: null
+
+            function refresh() {
+                if (!win.showGrid) return
+                var viewport = {
+                    x: (flick.contentX - content.x) / win.zoom,
+                    y: (flick.contentY - content.y) / win.zoom,
+                    width: flick.width / win.zoom,
+                    height: flick.height / win.zoom,
+                    zoom: win.zoom,
+                    major: win.gridMajor
+                }
+                try { geometry = JSON.parse(viewer.grid_geometry(JSON.stringify(viewport))) } catch (e) { geometry = null }
+                requestPaint()
+            }
+
+            onPaint: {
+                var ctx = getContext("2d")
+                ctx.clearRect(0, 0, width, height)
+                if (!geometry) return
+                // 图像坐标 -> 画布坐标
+                var ox = content.x - flick.contentX, oy = content.y - flick.contentY
+                var top = Math.max(0, oy), bottom = Math.min(height, oy + content.height)
+                var left = Math.max(0, ox), right = Math.min(width, ox + content.width)
+                for (var i = 0; i < geometry.lines.length; i++) {
+                    var line = geometry.lines[i]
+                    ctx.strokeStyle = line.major ? "rgba(66, 133, 244, 0.9)" : "rgba(128, 128, 128, 0.5)"
+                    ctx.lineWidth = line.major ? 2 : 1
+                    ctx.beginPath()
+                    if (line.vertical) {
+                        var x = Math.round(ox + line.position * win.zoom) + 0.5
+                        ctx.moveTo(x, top); ctx.lineTo(x, bottom)
+                    } else {
+                        var y = Math.round(oy + line.position * win.zoom) + 0.5
+                        ctx.moveTo(left, y); ctx.lineTo(right, y)
+                    }
+                    ctx.stroke()
+                }
+                // 刻度画在视口的上边与左边
+                ctx.strokeStyle = "#fbbc04"
+                ctx.fillStyle = "#fbbc04"
+                ctx.lineWidth = 2
+                ctx.font = "11px sans-serif"
+                for (var j = 0; j < geometry.ticks.length; j++) {
+                    var tick = geometry.ticks[j]
+                    ctx.beginPath()
+                    if (tick.vertical) {
+                        var tx = ox + tick.position * win.zoom
+                        ctx.moveTo(tx, 0); ctx.lineTo(tx, 10)
+                        ctx.stroke()
+                        ctx.fillText(tick.label, tx + 3, 20)
+                    } else {
+                        var ty = oy + tick.position * win.zoom
+                        ctx.moveTo(0, ty); ctx.lineTo(10, ty)
+                        ctx.stroke()
+                        ctx.fillText(tick.label, 12, ty + 4)
+                    }
+                }
+            }
+
+            onWidthChanged: refresh()
+            onHeightChanged: refresh()
+            onVisibleChanged: refresh()
+            Connections {
+                target: flick
+                function onContentXChanged() { gridCanvas.refresh() }
+                function onContentYChanged() { gridCanvas.refresh() }
+            }
+            Connections {
+                target: win
+                function onZoomChanged() { gridCanvas.refresh() }
+                function onGridMajorChanged() { gridCanvas.refresh() }
+                function onCacheBustChanged() { gridCanvas.refresh() }
+            }
+        }
     }
 
+    // 网格设置窗口：粗线间隔（8 / 10 / 自定义格数）与经纬密度（每厘米根数，保存到工程）
+    Window {
+        id: gridWin
+        width: 420; height: 200
+        title: "网格与密度 (Grid & Density)"
+        visible: false
+        modality: Qt.NonModal
+        flags: Qt.Dialog
+
+        function loadDensity() {
+            var density = null
+            try { density = JSON.parse(viewer.get_density()) } catch (e) { density = null }
+            warpInput.text = density ? density.warp : ""
+            weftInput.text = density ? density.weft : ""
+        }
+        function storeDensity() {
+            var warp = parseFloat(warpInput.text), weft = parseFloat(weftInput.text)
+            viewer.set_density(warp > 0 && weft > 0 ? JSON.stringify({ warp: warp, weft: weft }) : "")
+            gridCanvas.refresh()
+        }
+
+        Rectangle { anchors.fill: parent; color: "#2b2f33" }
+        Column {
+            anchors.fill: parent
+            anchors.margins: 12
+            spacing: 8
+
+            Row {
+                spacing: 8
+                Text { color: "#e8eaed"; text: "粗线间隔 (Major every):"; anchors.verticalCenter: parent.verticalCenter }
+                Repeater {
+                    model: [8, 10]
+                    delegate: Rectangle {
+                        width: 40; height: 28
+                        radius: 4
+                        color: win.gridMajor === modelData ? "#1e8e3e" : "#3c4043"
+                        border.color: "#5f6368"
+                        Text { anchors.centerIn: parent; color: "#e8eaed"; text: modelData }
+                        MouseArea { anchors.fill: parent; onClicked: win.gridMajor = modelData }
+                    }
+                }
+                TextInput {
+                    width: 48
+                    color: "#e8eaed"
+                    text: win.gridMajor
+                    validator: IntValidator { bottom: 1 }
+                    anchors.verticalCenter: parent.verticalCenter
+                    onEditingFinished: win.gridMajor = parseInt(text) || 10
+                }
+            }
+            Row {
+                spacing: 8
+                Text { color: "#e8eaed"; text: "经密 (Warp ends/cm):"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: warpInput; width: 64; color: "#e8eaed"; validator: DoubleValidator { bottom: 0 }; onEditingFinished: gridWin.storeDensity() }
+                Text { color: "#e8eaed"; text: "纬密 (Weft picks/cm):"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: weftInput; width: 64; color: "#e8eaed"; validator: DoubleValidator { bottom: 0 }; onEditingFinished: gridWin.storeDensity() }
+            }
+            Rectangle {
+                width: gridToggleText.implicitWidth + 16; height: 32
+                radius: 4
+                color: win.showGrid ? "#1e8e3e" : "#3c4043"
+                border.color: "#5f6368"
+                Text { id: gridToggleText; anchors.centerIn: parent; color: "#e8eaed"; text: win.showGrid ? "隐藏网格 (Hide grid)" : "显示网格 (Show grid)" }
+                MouseArea { anchors.fill: parent; onClicked: win.showGrid = !win.showGrid }
+            }
+        }
+    }
+
+    // 颜色映射窗口：为阈值映射的每一段指定纱线颜色（#rrggbb）与名称，保存到工程
     Window {
         id: colorWin
-        width: 360; height: 240
+        width: 700; height: 480
         title: "颜色映射 (Color Mapping)"
         visible: false
         modality: Qt.NonModal
         flags: Qt.Dialog
 
+        // 段数 = 阈值数 + 1，由阈值窗口确认时更新
+        property int levelCount: 0
+        // 每段的颜色表条目 { level, color: [r,g,b], name }
+        property var entries: []
+
+        function toHex(c) {
+            function h(v) { var s = v.toString(16); return s.length < 2 ? "0" + s : s }
+            return "#" + h(c[0]) + h(c[1]) + h(c[2])
+        }
+        function fromHex(t) {
+            var m = /^#?([0-9a-fA-F]{2})([0-9a-fA-F]{2})([0-9a-fA-F]{2})$/.exec(t)
+            return m ? [parseInt(m[1], 16), parseInt(m[2], 16), parseInt(m[3], 16)] : null
+        }
+        function loadTable() {
+            var table = []
+            try { table = JSON.parse(viewer.get_color_table()) } catch (e) { table = [] }
+            var list = []
+            for (var i = 0; i < levelCount; i++) list.push({ level: i, color: [128, 128, 128], name: "" })
+            for (var j = 0; j < table.length; j++) {
+                if (table[j].level < list.length) list[table[j].level] = table[j]
+            }
+            entries = list
+        }
+        function storeTable() { viewer.set_color_table(JSON.stringify(entries)) }
+        // 最近一次改色/合并影响的像素数
+        property string recolorResult: ""
+
         Rectangle { anchors.fill: parent; color: "#2b2f33" }
         Column {
             anchors.fill: parent
             anchors.margins: 12
             spacing: 8
-            Text { color: "#e8eaed"; text: "颜色映射占位（后续添加滑块）(Color Mapping Placeholder - Sliders to be added later)" }
+
+            Text { color: "#e8eaed"; text: colorWin.levelCount > 0 ? "段数 (Levels): " + colorWin.levelCount : "请先确认阈值映射 (Apply threshold mapping first)" }
+
+            Repeater {
+                model: colorWin.entries.length
+                delegate: Row {
+                    spacing: 8
+                    Rectangle {
+                        width: 24; height: 24
+                        color: colorWin.toHex(colorWin.entries[index].color)
+                        border.color: "#5f6368"
+                    }
+                    Text { color: "#e8eaed"; width: 48; text: "#" + index; anchors.verticalCenter: parent.verticalCenter }
+                    TextInput {
+                        width: 80
+                        color: "#e8eaed"
+                        text: colorWin.toHex(colorWin.entries[index].color)
+                        onEditingFinished: {
+                            var c = colorWin.fromHex(text)
+                            if (!c) return
+                            colorWin.entries[index].color = c
+                            colorWin.entries = colorWin.entries.slice()
+                            colorWin.storeTable()
+                        }
+                    }
+                    TextInput {
+                        width: 160
+                        color: "#e8eaed"
+                        text: colorWin.entries[index].name || "纱线名称 (Yarn)"
+                        onEditingFinished: {
+                            colorWin.entries[index].name = text
+                            colorWin.storeTable()
+                        }
+                    }
+                    TextInput {
+                        width: 100
+                        color: "#e8eaed"
+                        text: colorWin.entries[index].structure || "组织 (Structure)"
+                        onEditingFinished: {
+                            colorWin.entries[index].structure = text
+                            colorWin.storeTable()
+                        }
+                    }
+                    // 单段查看：再次点击退出
+                    Rectangle {
+                        width: isolateText.implicitWidth + 16; height: 24
+                        radius: 4
+                        color: win.isolatedLevel === index ? "#1e8e3e" : "#3c4043"
+                        border.color: "#5f6368"
+                        Text { id: isolateText; anchors.centerIn: parent; color: "#e8eaed"; text: "只看 (Isolate)" }
+                        MouseArea {
+                            anchors.fill: parent
+                            onClicked: win.isolateLevel(win.isolatedLevel === index ? -1 : index, win.isolateHideOthers)
+                        }
+                    }
+                }
+            }
+
+            // 合并段：把段 A 的像素改为段 B（作用于当前处理结果）
+            Row {
+                spacing: 8
+                Text { color: "#e8eaed"; text: "合并段 (Merge level)"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: mergeFrom; width: 32; color: "#e8eaed"; text: "0"; validator: IntValidator { bottom: 0 }; anchors.verticalCenter: parent.verticalCenter }
+                Text { color: "#e8eaed"; text: "→"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: mergeInto; width: 32; color: "#e8eaed"; text: "1"; validator: IntValidator { bottom: 0 }; anchors.verticalCenter: parent.verticalCenter }
+                Rectangle {
+                    width: mergeText.implicitWidth + 16; height: 32
+                    radius: 4; color: "#3c4043"; border.color: "#5f6368"
+                    Text { id: mergeText; anchors.centerIn: parent; color: "#e8eaed"; text: "合并 (Merge)" }
+                    MouseArea {
+                        anchors.fill: parent
+                        onClicked: {
+                            var n = viewer.merge_levels(JSON.stringify({ from: parseInt(mergeFrom.text), into: parseInt(mergeInto.text) }))
+                            colorWin.recolorResult = "合并影响像素 (pixels): " + n
+                            win.cacheBust = win.cacheBust + 1
+                        }
+                    }
+                }
+            }
+
+            // 替换颜色：#rrggbb -> #rrggbb，容差为每通道最大差值
+            Row {
+                spacing: 8
+                Text { color: "#e8eaed"; text: "替换颜色 (Replace)"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: replaceFrom; width: 72; color: "#e8eaed"; text: "#000000"; anchors.verticalCenter: parent.verticalCenter }
+                Text { color: "#e8eaed"; text: "→"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: replaceTo; width: 72; color: "#e8eaed"; text: "#ffffff"; anchors.verticalCenter: parent.verticalCenter }
+                Text { color: "#e8eaed"; text: "±"; anchors.verticalCenter: parent.verticalCenter }
+                TextInput { id: replaceTolerance; width: 32; color: "#e8eaed"; text: "0"; validator: IntValidator { bottom: 0; top: 255 }; anchors.verticalCenter: parent.verticalCenter }
+                Rectangle {
+                    width: replaceText.implicitWidth + 16; height: 32
+                    radius: 4; color: "#3c4043"; border.color: "#5f6368"
+                    Text { id: replaceText; anchors.centerIn: parent; color: "#e8eaed"; text: "替换 (Replace)" }
+                    MouseArea {
+                        anchors.fill: parent
+                        onClicked: {
+                            var from = colorWin.fromHex(replaceFrom.text), to = colorWin.fromHex(replaceTo.text)
+                            if (!from || !to) return
+                            var n = viewer.replace_color(JSON.stringify({ from: from, to: to, tolerance: parseInt(replaceTolerance.text) || 0 }))
+                            colorWin.recolorResult = "替换影响像素 (pixels): " + n
+                            win.cacheBust = win.cacheBust + 1
+                        }
+                    }
+                }
+            }
+
+            Text { color: "#9aa0a6"; text: colorWin.recolorResult; visible: text.length > 0 }
+
+            Row {
+                spacing: 8
+                Rectangle {
+                    width: applyColorText.implicitWidth + 16; height: 32
+                    radius: 4; color: "#3c4043"; border.color: "#5f6368"
+                    Text { id: applyColorText; anchors.centerIn: parent; color: "#e8eaed"; text: "应用 (Apply)" }
+                    MouseArea {
+                        anchors.fill: parent
+                        onClicked: { colorWin.storeTable(); viewer.apply_color_mapping(); win.cacheBust = win.cacheBust + 1 }
+                    }
+                }
+                // 上次使用的导出方式以绿色边框标出
+                Rectangle {
+                    width: exportColorText.implicitWidth + 16; height: 32
+                    radius: 4; color: "#3c4043"; border.color: viewer.export_gray_codes ? "#5f6368" : "#34a853"
+                    Text { id: exportColorText; anchors.centerIn: parent; color: "#e8eaed"; text: "导出彩色图 (Export Colored)" }
+                    MouseArea {
+                        anchors.fill: parent
+                        onClicked: { exportDialog.grayCodes = false; exportDialog.open() }
+                    }
+                }
+                Rectangle {
+                    width: exportCodesText.implicitWidth + 16; height: 32
+                    radius: 4; color: "#3c4043"; border.color: viewer.export_gray_codes ? "#34a853" : "#5f6368"
+                    Text { id: exportCodesText; anchors.centerIn: parent; color: "#e8eaed"; text: "导出编码+颜色表 (Export Codes)" }
+                    MouseArea {
+                        anchors.fill: parent
+                        onClicked: { exportDialog.grayCodes = true; exportDialog.open() }
+                    }
+                }
+            }
+        }
+    }
+
+    // 几何变换窗口：裁剪（数值输入或在图上拖拽）、旋转、翻转、裁掉透明边框
+    Window {
+        id: transformWin
+        width: 420; height: 200
+        title: "几何变换 (Transform)"
+        visible: false
+        modality: Qt.NonModal
+        flags: Qt.Dialog
+
+        property int cropX: 0
+        property int cropY: 0
+        property int cropW: 0
+        property int cropH: 0
+
+        function setCrop(x, y, w, h) { cropX = x; cropY = y; cropW = w; cropH = h }
+        function run(step) { viewer.apply_transform(JSON.stringify(step)); win.cacheBust = win.cacheBust + 1 }
+
+        Rectangle { anchors.fill: parent; color: "#2b2f33" }
+        Column {
+            anchors.fill: parent
+            anchors.margins: 12
+            spacing: 8
+
+            Text { color: "#e8eaed"; text: "裁剪区域 (Crop): x, y, 宽 (w), 高 (h) — 可在图上拖拽框选" }
+            Row {
+                spacing: 8
+                Repeater {
+                    model: ["cropX", "cropY", "cropW", "cropH"]
+                    delegate: TextInput {
+                        width: 64
+                        color: "#e8eaed"
+                        validator: IntValidator { bottom: 0 }
+                        text: transformWin[modelData]
+                        onEditingFinished: transformWin[modelData] = parseInt(text) || 0
+                    }
+                }
+            }
+
+            Row {
+                spacing: 8
+                Repeater {
+                    model: [
+                        { label: "裁剪 (Crop)", crop: true },
+                        { label: "裁掉透明边 (Trim)", step: { op: "trim" } },
+                        { label: "旋转 90°", step: { op: "rotate", degrees: 90 } },
+                        { label: "旋转 180°", step: { op: "rotate", degrees: 180 } },
+                        { label: "旋转 270°", step: { op: "rotate", degrees: 270 } }
+                    ]
+                    delegate: Rectangle {
+                        width: stepText.implicitWidth + 16; height: 32
+                        radius: 4; color: "#3c4043"; border.color: "#5f6368"
+                        Text { id: stepText; anchors.centerIn: parent; color: "#e8eaed"; text: modelData.label }
+                        MouseArea {
+                            anchors.fill: parent
+                            onClicked: transformWin.run(modelData.crop
+                                ? { op: "crop", x: transformWin.cropX, y: transformWin.cropY, width: transformWin.cropW, height: transformWin.cropH }
+                                : modelData.step)
+                        }
+                    }
+                }
+            }
+
+            Row {
+                spacing: 8
+                Repeater {
+                    model: [
+                        { label: "水平翻转 (Flip H)", direction: "horizontal" },
+                        { label: "垂直翻转 (Flip V)", direction: "vertical" }
+                    ]
+                    delegate: Rectangle {
+                        width: flipText.implicitWidth + 16; height: 32
+                        radius: 4; color: "#3c4043"; border.color: "#5f6368"
+                        Text { id: flipText; anchors.centerIn: parent; color: "#e8eaed"; text: modelData.label }
+                        MouseArea {
+                            anchors.fill: parent
+                            onClicked: transformWin.run({ op: "flip", direction: modelData.direction })
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // 颜色统计窗口：处理结果中每种颜色的像素数、占不透明面积的比例、连通区域数与外接矩形
+    Window {
+        id: statsWin
+        width: 560; height: 400
+        title: "颜色统计 (Palette Statistics)"
+        visible: false
+        modality: Qt.NonModal
+        flags: Qt.Dialog
+
+        Rectangle { anchors.fill: parent; color: "#2b2f33" }
+        Column {
+            anchors.fill: parent
+            anchors.margins: 12
+            spacing: 8
+
+            Text { color: "#e8eaed"; text: "颜色 (Color) · 像素 (Pixels) · 占比 (%) · 区域 (Regions) · 外接矩形 (Bounds x, y, w × h)" }
+            ListView {
+                width: parent.width
+                height: parent.height - 32
+                clip: true
+                model: paletteStats
+                delegate: Row {
+                    spacing: 12
+                    height: 24
+                    Rectangle { width: 20; height: 20; color: model.color; border.color: "#5f6368" }
+                    Text { width: 70; color: "#e8eaed"; text: model.color }
+                    Text { width: 80; color: "#e8eaed"; text: model.pixel_count }
+                    Text { width: 60; color: "#e8eaed"; text: model.percentage.toFixed(2) + "%" }
+                    Text { width: 50; color: "#e8eaed"; text: model.region_count }
+                    Text {
+                        color: "#e8eaed"
+                        text: model.bbox_x + ", " + model.bbox_y + ", " + model.bbox_width + " × " + model.bbox_height
+                    }
+                }
+            }
+        }
+    }
+
+    // 最近打开的文件：点击打开，已不存在的文件在启动时去除
+    Window {
+        id: recentWin
+        width: 560; height: 320
+        title: "最近打开 (Recent Files)"
+        visible: false
+        modality: Qt.NonModal
+        flags: Qt.Dialog
+
+        Rectangle { anchors.fill: parent; color: "#2b2f33" }
+        Column {
+            anchors.fill: parent
+            anchors.margins: 12
+            spacing: 8
+
+            ListView {
+                width: parent.width
+                height: parent.height - 40
+                clip: true
+                model: win.recentFiles
+                delegate: Rectangle {
+                    width: ListView.view.width; height: 28
+                    radius: 4
+                    color: recentArea.containsMouse ? "#3c4043" : "transparent"
+                    Text { anchors.verticalCenter: parent.verticalCenter; x: 8; color: "#e8eaed"; text: modelData; elide: Text.ElideMiddle; width: parent.width - 16 }
+                    MouseArea {
+                        id: recentArea
+                        anchors.fill: parent
+                        hoverEnabled: true
+                        onClicked: { win.openImage(modelData); recentWin.visible = false }
+                    }
+                }
+            }
+            Rectangle {
+                width: clearRecentText.implicitWidth + 16; height: 32
+                radius: 4; color: "#3c4043"; border.color: "#5f6368"
+                Text { id: clearRecentText; anchors.centerIn: parent; color: "#e8eaed"; text: "清空 (Clear)" }
+                MouseArea {
+                    anchors.fill: parent
+                    onClicked: viewer.clear_recent_files()
+                }
+            }
+        }
+    }
+
+    // 默认选项：阈值抖动“仅不透明”的初值、灰度预览是否保留半透明 alpha、打开图片后是否直接灰度预览
+    Window {
+        id: defaultsWin
+        width: 420; height: 160
+        title: "默认选项 (Defaults)"
+        visible: false
+        modality: Qt.NonModal
+        flags: Qt.Dialog
+
+        Rectangle { anchors.fill: parent; color: "#2b2f33" }
+        Column {
+            anchors.fill: parent
+            anchors.margins: 12
+            spacing: 8
+
+            Repeater {
+                model: [
+                    { key: "opaqueOnly", label: "抖动仅不透明 (Dither opaque only)" },
+                    { key: "grayKeepAlpha", label: "灰度保留半透明 (Gray keeps alpha)" },
+                    { key: "grayOnOpen", label: "打开后灰度预览 (Gray on open)" }
+                ]
+                delegate: Rectangle {
+                    width: parent.width; height: 32
+                    radius: 4
+                    color: win.processingDefaults[modelData.key] ? "#1e8e3e" : "#3c4043"
+                    border.color: "#5f6368"
+                    Text { anchors.verticalCenter: parent.verticalCenter; x: 8; color: "#e8eaed"; text: modelData.label }
+                    MouseArea { anchors.fill: parent; onClicked: win.setDefault(modelData.key, !win.processingDefaults[modelData.key]) }
+                }
+            }
+        }
+    }
+
+    Labs.FileDialog {
+        id: exportDialog
+        property bool grayCodes: false
+        title: "导出 PNG (Export PNG)"
+        // 从上次导出的目录开始
+        folder: viewer.last_export_dir.length > 0 ? "file://" + viewer.last_export_dir : ""
+        fileMode: Labs.FileDialog.SaveFile
+        nameFilters: ["PNG Files (*.png)"]
+        onAccepted: {
+            if (!file) return
+            var p = file.toString()
+            if (p.startsWith("file://")) {
+                p = p.replace("file://", "")
+            }
+            viewer.export_color_mapping(p, grayCodes)
         }
     }
 
@@ -346,8 +1372,7 @@ Window {
             if (p.startsWith("file://")) {
                 p = p.replace("file://", "")
             }
-            viewer.set_image_path(p)
-            win.cacheBust = win.cacheBust + 1
+            win.openImage(p)
         }
     }
 }
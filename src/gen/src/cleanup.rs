@@ -0,0 +1,427 @@
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use wrap::WrapMode;
+
+// 清理散点：统计 8 邻域中不透明像素的颜色，若中心像素的颜色在邻域中一次都没出现，
+// 则改为邻域中出现最多的颜色；数量相同时选择较深的颜色（亮度也相同时取邻域扫描顺序中先出现者）。
+// wrap 决定图片边缘的邻域是否从对边接续（循环织造时）。
+// 只读取原图、逐行写入结果，因此按行带并行处理；邻域颜色用固定大小的数组计数，不做逐像素分配。
+pub fn scattered_pixels(img: &image::RgbaImage, wrap: WrapMode) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let mut cleaned_img = img.clone();
+    let row_len = width as usize * 4;
+    if row_len == 0 {
+        return cleaned_img;
+    }
+    let src = img.as_raw();
+    cleaned_img
+        .par_chunks_mut(row_len * BAND_ROWS)
+        .enumerate()
+        .for_each(|(band, rows)| {
+            for (i, row) in rows.chunks_exact_mut(row_len).enumerate() {
+                let y = (band * BAND_ROWS + i) as u32;
+                for x in 0..width {
+                    let idx = x as usize * 4;
+                    // 跳过透明像素
+                    if row[idx + 3] == 0 {
+                        continue;
+                    }
+                    let counts = neighbour_colors(src, x, y, width, height, wrap);
+                    // 如果周围有非透明像素且颜色不同，则改为出现最多的颜色（保留原 alpha）
+                    if !counts.contains([row[idx], row[idx + 1], row[idx + 2]]) {
+                        if let Some(c) = counts.most_common() {
+                            row[idx..idx + 3].copy_from_slice(&c);
+                        }
+                    }
+                }
+            }
+        });
+    cleaned_img
+}
+
+// 平局时“较深者优先”所比较的亮度：(r+g+b)/3。清理散点、众数滤波与形态学共用，保证取舍一致
+pub fn brightness(c: [u8; 3]) -> u32 {
+    (c[0] as u32 + c[1] as u32 + c[2] as u32) / 3
+}
+
+// 并行处理时每个任务负责的行数
+const BAND_ROWS: usize = 16;
+
+// 8 邻域的扫描顺序：逐行从左上到右下
+const NEIGHBOURS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+// 邻域颜色计数：8 邻域最多出现 8 种颜色
+struct NeighbourCounts {
+    colors: [[u8; 3]; 8],
+    counts: [u8; 8],
+    len: usize,
+}
+
+impl NeighbourCounts {
+    fn add(&mut self, color: [u8; 3]) {
+        for i in 0..self.len {
+            if self.colors[i] == color {
+                self.counts[i] += 1;
+                return;
+            }
+        }
+        self.colors[self.len] = color;
+        self.counts[self.len] = 1;
+        self.len += 1;
+    }
+
+    fn contains(&self, color: [u8; 3]) -> bool {
+        self.colors[..self.len].contains(&color)
+    }
+
+    // 出现最多的颜色；数量相同时取较深者（见 brightness），仍相同时取先出现者。没有不透明邻居时为 None
+    fn most_common(&self) -> Option<[u8; 3]> {
+        let mut best: Option<usize> = None;
+        for i in 0..self.len {
+            best = match best {
+                Some(b) if self.counts[b] > self.counts[i] => Some(b),
+                Some(b) if self.counts[b] == self.counts[i] && brightness(self.colors[b]) <= brightness(self.colors[i]) => Some(b),
+                _ => Some(i),
+            };
+        }
+        best.map(|i| self.colors[i])
+    }
+}
+
+// 统计 (x, y) 的 8 邻域中不透明像素的颜色；内部像素直接按偏移读取，边缘像素按 wrap 解析越界坐标
+fn neighbour_colors(src: &[u8], x: u32, y: u32, width: u32, height: u32, wrap: WrapMode) -> NeighbourCounts {
+    let mut counts = NeighbourCounts { colors: [[0; 3]; 8], counts: [0; 8], len: 0 };
+    let interior = x > 0 && y > 0 && x + 1 < width && y + 1 < height;
+    for &(dx, dy) in &NEIGHBOURS {
+        let (nx, ny) = if interior {
+            ((x as i32 + dx) as u32, (y as i32 + dy) as u32)
+        } else {
+            match wrap.resolve(x as i32 + dx, y as i32 + dy, width, height) {
+                Some(p) => p,
+                None => continue,
+            }
+        };
+        let o = (ny as usize * width as usize + nx as usize) * 4;
+        // 只考虑非透明像素
+        if src[o + 3] > 0 {
+            counts.add([src[o], src[o + 1], src[o + 2]]);
+        }
+    }
+    counts
+}
+
+// 众数（多数）滤波：以 (2r+1)×(2r+1) 窗口统计不透明像素颜色（含中心），
+// 当出现最多的颜色不是当前颜色、且其占窗口内不透明像素的比例不低于 min_agreement 时改为该颜色。
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ModeFilterData {
+    #[serde(default = "default_radius")]
+    pub radius: u32,
+    // 0..1，例如 0.5 表示至少半数邻域同意才改色
+    #[serde(default)]
+    pub min_agreement: f32,
+    // 迭代次数；until_stable 为 true 时忽略，改为迭代到不再变化（最多 MAX_STABLE_ITERATIONS 次）
+    #[serde(default = "default_iterations")]
+    pub iterations: u32,
+    #[serde(default)]
+    pub until_stable: bool,
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    #[serde(default)]
+    pub wrap: WrapMode,
+}
+
+// 清理散点的参数（流水线步骤）
+#[derive(Deserialize, Clone, Default)]
+pub struct CleanupData {
+    #[serde(default)]
+    pub wrap: WrapMode,
+}
+
+// 多个颜色数量相同时的取舍规则
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase", tag = "policy", content = "colors")]
+pub enum TieBreak {
+    // 较深者优先（与清理散点一致）
+    Darker,
+    // 较浅者优先
+    Lighter,
+    // 当前颜色在并列之中时保持不变，否则较深者优先
+    Original,
+    // 按给定颜色列表靠前者优先，未列出的颜色排在其后并按较深者优先
+    Priority(Vec<[u8; 3]>),
+}
+
+impl Default for TieBreak {
+    fn default() -> Self {
+        TieBreak::Darker
+    }
+}
+
+fn default_radius() -> u32 {
+    1
+}
+
+fn default_iterations() -> u32 {
+    1
+}
+
+// 迭代到稳定时的安全上限
+const MAX_STABLE_ITERATIONS: u32 = 100;
+// 窗口半径上限：每个像素的计数量随半径平方增长
+const MAX_MODE_RADIUS: u32 = 16;
+
+impl ModeFilterData {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.radius > MAX_MODE_RADIUS {
+            return Err(format!("Mode filter radius must be at most {}, got {}", MAX_MODE_RADIUS, self.radius));
+        }
+        if !(0.0..=1.0).contains(&self.min_agreement) {
+            return Err(format!("Minimum agreement must be between 0 and 1, got {}", self.min_agreement));
+        }
+        Ok(())
+    }
+}
+
+impl TieBreak {
+    // 从并列颜色中选出一个，深浅按 brightness 比较；亮度相同时按 RGB 从小到大，保证结果确定
+    fn choose(&self, tied: &[[u8; 3]], original: [u8; 3]) -> [u8; 3] {
+        let darker = |c: &[u8; 3]| (brightness(*c), *c);
+        match self {
+            TieBreak::Lighter => *tied.iter().max_by_key(|c| (brightness(**c), Reverse(**c))).unwrap(),
+            TieBreak::Original if tied.contains(&original) => original,
+            TieBreak::Priority(list) => *tied
+                .iter()
+                .min_by_key(|c| (list.iter().position(|p| p == *c).unwrap_or(list.len()), darker(c)))
+                .unwrap(),
+            _ => *tied.iter().min_by_key(|c| darker(c)).unwrap(),
+        }
+    }
+}
+
+// 返回（结果图片，每次迭代改变的像素数）；某次迭代没有改变时提前结束
+pub fn mode_filter(img: &image::RgbaImage, data: &ModeFilterData) -> (image::RgbaImage, Vec<u32>) {
+    let (width, height) = img.dimensions();
+    let r = data.radius as i32;
+    let max_iterations = if data.until_stable { MAX_STABLE_ITERATIONS } else { data.iterations };
+    let mut current = img.clone();
+    let mut changes = Vec::new();
+    // 窗口内颜色计数，颜色种类通常很少，线性查找即可
+    let mut counts: Vec<([u8; 3], u32)> = Vec::new();
+    let mut tied: Vec<[u8; 3]> = Vec::new();
+
+    for _ in 0..max_iterations {
+        let mut next = current.clone();
+        let mut changed = 0;
+        for y in 0..height {
+            for x in 0..width {
+                let p = current.get_pixel(x, y);
+                if p[3] == 0 {
+                    continue;
+                }
+                let original = [p[0], p[1], p[2]];
+                counts.clear();
+                let mut total = 0;
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let (nx, ny) = match data.wrap.resolve(x as i32 + dx, y as i32 + dy, width, height) {
+                            Some(p) => p,
+                            None => continue,
+                        };
+                        let n = current.get_pixel(nx, ny);
+                        if n[3] == 0 {
+                            continue;
+                        }
+                        let c = [n[0], n[1], n[2]];
+                        total += 1;
+                        match counts.iter_mut().find(|e| e.0 == c) {
+                            Some(e) => e.1 += 1,
+                            None => counts.push((c, 1)),
+                        }
+                    }
+                }
+                let max_count = counts.iter().map(|e| e.1).max().unwrap_or(0);
+                tied.clear();
+                tied.extend(counts.iter().filter(|e| e.1 == max_count).map(|e| e.0));
+                let mode = data.tie_break.choose(&tied, original);
+                if mode != original && max_count as f32 >= data.min_agreement * total as f32 {
+                    next.put_pixel(x, y, image::Rgba([mode[0], mode[1], mode[2], p[3]]));
+                    changed += 1;
+                }
+            }
+        }
+        current = next;
+        changes.push(changed);
+        if changed == 0 {
+            break;
+        }
+    }
+    (current, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 改写前逐像素分配 HashMap 的实现，作为对照。原实现在亮度也相同的并列颜色之间按 HashMap 的
+    // 遍历顺序取舍，结果不确定；这里另记颜色首次出现的顺序，取先出现者，与文档中的规则一致
+    fn reference_scattered_pixels(img: &image::RgbaImage, wrap: WrapMode) -> image::RgbaImage {
+        let (width, height) = img.dimensions();
+        let mut cleaned_img = img.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = img.get_pixel(x, y);
+                let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+                if a == 0 {
+                    continue;
+                }
+                let mut neighbor_colors = std::collections::HashMap::new();
+                let mut order = Vec::new();
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        if dx == 0 && dy == 0 {
+                            continue;
+                        }
+                        let (nx, ny) = match wrap.resolve(x as i32 + dx, y as i32 + dy, width, height) {
+                            Some(p) => p,
+                            None => continue,
+                        };
+                        let n = img.get_pixel(nx, ny);
+                        if n[3] > 0 {
+                            let count = neighbor_colors.entry((n[0], n[1], n[2])).or_insert(0u32);
+                            if *count == 0 {
+                                order.push((n[0], n[1], n[2]));
+                            }
+                            *count += 1;
+                        }
+                    }
+                }
+                if neighbor_colors.is_empty() || neighbor_colors.contains_key(&(r, g, b)) {
+                    continue;
+                }
+                let max_count = *neighbor_colors.values().max().unwrap();
+                let mut darkest: Option<(u8, u8, u8)> = None;
+                for &color in &order {
+                    if neighbor_colors[&color] != max_count {
+                        continue;
+                    }
+                    let brightness = |c: (u8, u8, u8)| (c.0 as u32 + c.1 as u32 + c.2 as u32) / 3;
+                    if darkest.map_or(true, |d| brightness(color) < brightness(d)) {
+                        darkest = Some(color);
+                    }
+                }
+                let c = darkest.unwrap();
+                cleaned_img.put_pixel(x, y, image::Rgba([c.0, c.1, c.2, a]));
+            }
+        }
+        cleaned_img
+    }
+
+    const WRAPS: [WrapMode; 5] = [WrapMode::None, WrapMode::Horizontal, WrapMode::Vertical, WrapMode::Both, WrapMode::HalfDrop];
+
+    // 模拟阈值映射后的设计图：成块分布并夹杂散点与透明区域。
+    // 调色板中纯红、纯蓝与 (85, 85, 85) 的 (r+g+b)/3 相同，用于检验亮度相同时的取舍
+    fn design(width: u32, height: u32, seed: u64) -> image::RgbaImage {
+        let palette = [[0, 0, 0, 255], [85, 85, 85, 255], [255, 0, 0, 255], [0, 0, 255, 255], [255, 255, 255, 200], [0, 0, 0, 0]];
+        let mut seed = seed | 1;
+        image::RgbaImage::from_fn(width, height, |x, y| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let i = if seed % 100 < 30 { (seed >> 8) as usize } else { ((x / 4) * 7 + (y / 3) * 3) as usize };
+            image::Rgba(palette[i % palette.len()])
+        })
+    }
+
+    #[test]
+    fn matches_reference_for_all_wrap_modes() {
+        for &(width, height) in &[(37, 29), (1, 9), (9, 1), (2, 2), (64, 40)] {
+            let img = design(width, height, width as u64 * 31 + height as u64);
+            for &wrap in &WRAPS {
+                assert!(scattered_pixels(&img, wrap) == reference_scattered_pixels(&img, wrap));
+            }
+        }
+    }
+
+    #[test]
+    fn equal_count_ties_pick_darker_then_first_seen() {
+        let black = image::Rgba([0, 0, 0, 255]);
+        let red = image::Rgba([255, 0, 0, 255]);
+        let blue = image::Rgba([0, 0, 255, 255]);
+        let white = image::Rgba([255, 255, 255, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+        // 中心白色，邻域四个红、四个黑：黑色较深
+        let mut img = image::RgbaImage::from_pixel(3, 3, red);
+        for &(x, y) in &[(1, 0), (0, 1), (2, 1), (1, 2)] {
+            img.put_pixel(x, y, black);
+        }
+        img.put_pixel(1, 1, white);
+        assert_eq!(*scattered_pixels(&img, WrapMode::None).get_pixel(1, 1), black);
+        // 红、蓝亮度相同：取扫描顺序中先出现的蓝色（左上角）
+        let mut img = image::RgbaImage::from_pixel(3, 3, clear);
+        img.put_pixel(0, 0, blue);
+        img.put_pixel(2, 2, red);
+        img.put_pixel(1, 1, white);
+        assert_eq!(*scattered_pixels(&img, WrapMode::None).get_pixel(1, 1), blue);
+    }
+
+    #[test]
+    fn edges_follow_wrap_mode() {
+        // 左边缘的散点：不循环时只有右侧邻居，左右相接时从右边缘接续
+        let mut img = image::RgbaImage::from_pixel(4, 1, image::Rgba([0, 0, 0, 255]));
+        img.put_pixel(0, 0, image::Rgba([255, 255, 255, 255]));
+        img.put_pixel(1, 0, image::Rgba([255, 0, 0, 255]));
+        img.put_pixel(3, 0, image::Rgba([0, 0, 255, 255]));
+        assert_eq!(scattered_pixels(&img, WrapMode::None).get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(scattered_pixels(&img, WrapMode::Horizontal).get_pixel(0, 0).0, [0, 0, 255, 255]);
+        // 透明像素不变
+        let clear = image::RgbaImage::from_pixel(2, 2, image::Rgba([9, 9, 9, 0]));
+        assert!(scattered_pixels(&clear, WrapMode::Both) == clear);
+    }
+
+    #[test]
+    fn mode_filter_ties_use_cleanup_brightness() {
+        // 按 (r+g+b)/3 灰色较深，按加权亮度则红色较深
+        let (red, gray) = ([255, 0, 0], [80, 80, 80]);
+        assert_eq!(TieBreak::Darker.choose(&[red, gray], [0, 0, 0]), gray);
+        assert_eq!(TieBreak::Lighter.choose(&[red, gray], [0, 0, 0]), red);
+        assert_eq!(TieBreak::Original.choose(&[red, gray], red), red);
+        assert_eq!(TieBreak::Priority(vec![red]).choose(&[gray, red], gray), red);
+    }
+
+    #[test]
+    fn mode_filter_parameters_are_validated() {
+        let data = |radius, min_agreement| ModeFilterData {
+            radius,
+            min_agreement,
+            iterations: 1,
+            until_stable: false,
+            tie_break: TieBreak::Darker,
+            wrap: WrapMode::None,
+        };
+        assert!(data(MAX_MODE_RADIUS, 0.0).validate().is_ok());
+        assert!(data(1, 1.0).validate().is_ok());
+        assert!(data(MAX_MODE_RADIUS + 1, 0.5).validate().is_err());
+        assert!(data(1, 1.5).validate().is_err());
+        assert!(data(1, -0.1).validate().is_err());
+        assert!(data(1, std::f32::NAN).validate().is_err());
+        let step: ::pipeline::Step = serde_json::from_str(r#"{ "op": "modeFilter", "radius": 2, "minAgreement": 2 }"#).unwrap();
+        assert!(step.validate().is_err());
+    }
+
+    // 性能对照：cargo test --release -- --ignored cleanup_speedup --nocapture
+    #[test]
+    #[ignore]
+    fn cleanup_speedup() {
+        let img = design(1024, 1024, 7);
+        let start = std::time::Instant::now();
+        let expected = reference_scattered_pixels(&img, WrapMode::None);
+        let reference = start.elapsed();
+        let start = std::time::Instant::now();
+        let actual = scattered_pixels(&img, WrapMode::None);
+        let current = start.elapsed();
+        assert!(actual == expected);
+        println!("scattered_pixels 1024x1024: reference {:?}, current {:?}", reference, current);
+    }
+}
@@ -0,0 +1,101 @@
+// 前后对比：将两个状态（原图或上一步结果 vs 当前结果）并排显示、按可拖动的分割线左右拼接，
+// 或生成差异图（变化的像素以指定颜色标出，未变化的像素淡化显示），并统计变化的像素数。
+// 分割模式不生成拼接图：QML 叠放两张图片并按分割线裁剪，拖动时无需重新生成。
+use recolor::changed_pixels;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CompareSource {
+    // 原图
+    Original,
+    // 上一步处理结果（还没有处理步骤时为原图）
+    Previous,
+}
+
+impl Default for CompareSource {
+    fn default() -> Self {
+        CompareSource::Original
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CompareMode {
+    // 左侧对比对象、右侧当前结果，中间留出间隔
+    SideBySide,
+    // 分割线左侧显示对比对象、右侧显示当前结果（由 QML 裁剪叠放）
+    Split,
+    // 差异图
+    Difference,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareData {
+    #[serde(default)]
+    pub before: CompareSource,
+    pub mode: CompareMode,
+    // 差异图中变化像素的颜色
+    #[serde(default = "default_highlight")]
+    pub highlight: [u8; 4],
+}
+
+fn default_highlight() -> [u8; 4] {
+    [255, 0, 0, 255]
+}
+
+// 并排显示时两图之间的透明间隔
+const SIDE_GAP: u32 = 8;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareReport {
+    // 变化的像素数（两个透明像素视为相同）；尺寸不一致（如经过几何变换）时为 null
+    pub changed: Option<u32>,
+    pub width: u32,
+    pub height: u32,
+    // 分割模式下对比对象的图片路径，QML 将其裁剪后叠放在当前结果上
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before_path: Option<String>,
+}
+
+// 生成对比图；分割模式只检查尺寸并统计变化，不生成图片（返回 None）
+pub fn render(before: &image::RgbaImage, after: &image::RgbaImage, data: &CompareData) -> Result<(Option<image::RgbaImage>, CompareReport), String> {
+    let same_size = before.dimensions() == after.dimensions();
+    let changed = if same_size { Some(changed_pixels(before, after)) } else { None };
+    let out = match data.mode {
+        CompareMode::Split => {
+            if !same_size {
+                return Err("Split comparison needs both images to have the same size".to_string());
+            }
+            let (width, height) = after.dimensions();
+            return Ok((None, CompareReport { changed, width, height, before_path: None }));
+        }
+        CompareMode::SideBySide => {
+            let (bw, bh) = before.dimensions();
+            let (aw, ah) = after.dimensions();
+            let mut out = image::RgbaImage::new(bw + SIDE_GAP + aw, bh.max(ah));
+            image::imageops::replace(&mut out, before, 0, 0);
+            image::imageops::replace(&mut out, after, (bw + SIDE_GAP) as i64, 0);
+            out
+        }
+        CompareMode::Difference => {
+            if !same_size {
+                return Err("Difference comparison needs both images to have the same size".to_string());
+            }
+            let mut out = after.clone();
+            for (p, b) in out.pixels_mut().zip(before.pixels()) {
+                if *p != *b && !(p[3] == 0 && b[3] == 0) {
+                    *p = image::Rgba(data.highlight);
+                } else {
+                    // 未变化的像素降低不透明度，突出变化
+                    p[3] /= 3;
+                }
+            }
+            out
+        }
+    };
+    let (width, height) = out.dimensions();
+    Ok((Some(out), CompareReport { changed, width, height, before_path: None }))
+}
@@ -0,0 +1,232 @@
+// 降噪预处理：中值、高斯模糊与保边的双边滤波，用于阈值映射之前，减少阈值化后的散点。
+// 三种滤波都按 RGB 通道处理，保留每个像素原有的 alpha。
+// 透明像素（alpha 为 0）既不参与邻域计算，也不会被填充，保持原值不变。
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "filter", rename_all = "camelCase")]
+pub enum DenoiseData {
+    // 窗口 (2r+1)x(2r+1) 内各通道分别取中值
+    Median {
+        #[serde(default = "default_radius")]
+        radius: u32,
+    },
+    // 高斯模糊，核半径取 ceil(3 * sigma)
+    Gaussian { sigma: f64 },
+    // 双边滤波：空间权重由 sigma 决定，颜色差权重由 range（RGB 欧氏距离）决定，核半径取 ceil(2 * sigma)
+    Bilateral { sigma: f64, range: f64 },
+}
+
+fn default_radius() -> u32 {
+    1
+}
+
+// 参数上限：核大小随半径平方增长，过大的半径或 sigma 会导致超大的核或长时间计算
+const MAX_RADIUS: u32 = 32;
+const MAX_SIGMA: f64 = 20.0;
+
+impl DenoiseData {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DenoiseData::Median { radius } if *radius == 0 => Err("Median radius must be at least 1".to_string()),
+            DenoiseData::Median { radius } if *radius > MAX_RADIUS => {
+                Err(format!("Median radius must be at most {}, got {}", MAX_RADIUS, radius))
+            }
+            DenoiseData::Gaussian { sigma } if !(*sigma > 0.0) => Err("Gaussian sigma must be greater than 0".to_string()),
+            DenoiseData::Gaussian { sigma } if *sigma > MAX_SIGMA => {
+                Err(format!("Gaussian sigma must be at most {}, got {}", MAX_SIGMA, sigma))
+            }
+            DenoiseData::Bilateral { sigma, range } if !(*sigma > 0.0 && *range > 0.0) => {
+                Err("Bilateral sigma and range must be greater than 0".to_string())
+            }
+            DenoiseData::Bilateral { sigma, .. } if *sigma > MAX_SIGMA => {
+                Err(format!("Bilateral sigma must be at most {}, got {}", MAX_SIGMA, sigma))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn apply(img: &image::RgbaImage, data: &DenoiseData) -> image::RgbaImage {
+    match data {
+        DenoiseData::Median { radius } => median(img, *radius),
+        DenoiseData::Gaussian { sigma } => gaussian(img, *sigma),
+        DenoiseData::Bilateral { sigma, range } => bilateral(img, *sigma, *range),
+    }
+}
+
+fn median(img: &image::RgbaImage, radius: u32) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let r = radius as i32;
+    let mut out = img.clone();
+    let mut channels: [Vec<u8>; 3] = [Vec::new(), Vec::new(), Vec::new()];
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] == 0 {
+            continue;
+        }
+        for c in channels.iter_mut() {
+            c.clear();
+        }
+        for ny in (y as i32 - r).max(0)..=(y as i32 + r).min(height as i32 - 1) {
+            for nx in (x as i32 - r).max(0)..=(x as i32 + r).min(width as i32 - 1) {
+                let q = img.get_pixel(nx as u32, ny as u32);
+                if q[3] != 0 {
+                    for (i, c) in channels.iter_mut().enumerate() {
+                        c.push(q[i]);
+                    }
+                }
+            }
+        }
+        let o = out.get_pixel_mut(x, y);
+        for (i, c) in channels.iter_mut().enumerate() {
+            c.sort_unstable();
+            o[i] = c[c.len() / 2];
+        }
+    }
+    out
+}
+
+// 归一化卷积：分别对 (颜色 * 不透明标记) 与不透明标记做可分离高斯模糊，再相除，
+// 这样透明像素不会把颜色拉暗
+fn gaussian(img: &image::RgbaImage, sigma: f64) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+    let r = (3.0 * sigma).ceil() as i32;
+    let kernel: Vec<f64> = (-r..=r).map(|d| (-((d * d) as f64) / (2.0 * sigma * sigma)).exp()).collect();
+
+    // 每个像素 4 个分量：R、G、B 的加权和与权重
+    let mut src = vec![0.0f64; w * h * 4];
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] != 0 {
+            let i = (y as usize * w + x as usize) * 4;
+            src[i] = p[0] as f64;
+            src[i + 1] = p[1] as f64;
+            src[i + 2] = p[2] as f64;
+            src[i + 3] = 1.0;
+        }
+    }
+    let blur = |src: &[f64], horizontal: bool| -> Vec<f64> {
+        let mut dst = vec![0.0f64; w * h * 4];
+        for y in 0..h {
+            for x in 0..w {
+                let mut acc = [0.0f64; 4];
+                for (k, weight) in kernel.iter().enumerate() {
+                    let d = k as i32 - r;
+                    let (nx, ny) = if horizontal { (x as i32 + d, y as i32) } else { (x as i32, y as i32 + d) };
+                    if nx < 0 || ny < 0 || nx >= w as i32 || ny >= h as i32 {
+                        continue;
+                    }
+                    let j = (ny as usize * w + nx as usize) * 4;
+                    for c in 0..4 {
+                        acc[c] += weight * src[j + c];
+                    }
+                }
+                dst[(y * w + x) * 4..(y * w + x) * 4 + 4].copy_from_slice(&acc);
+            }
+        }
+        dst
+    };
+    let blurred = blur(&blur(&src, true), false);
+
+    let mut out = img.clone();
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        if p[3] == 0 {
+            continue;
+        }
+        let i = (y as usize * w + x as usize) * 4;
+        for c in 0..3 {
+            p[c] = (blurred[i + c] / blurred[i + 3]).round().max(0.0).min(255.0) as u8;
+        }
+    }
+    out
+}
+
+fn bilateral(img: &image::RgbaImage, sigma: f64, range: f64) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let r = (2.0 * sigma).ceil() as i32;
+    let spatial: Vec<f64> = (-r..=r)
+        .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+        .map(|(dx, dy)| (-((dx * dx + dy * dy) as f64) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let side = (2 * r + 1) as usize;
+    let mut out = img.clone();
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] == 0 {
+            continue;
+        }
+        let mut acc = [0.0f64; 3];
+        let mut total = 0.0;
+        for ny in (y as i32 - r).max(0)..=(y as i32 + r).min(height as i32 - 1) {
+            for nx in (x as i32 - r).max(0)..=(x as i32 + r).min(width as i32 - 1) {
+                let q = img.get_pixel(nx as u32, ny as u32);
+                if q[3] == 0 {
+                    continue;
+                }
+                let dist2: f64 = (0..3).map(|c| (q[c] as f64 - p[c] as f64).powi(2)).sum();
+                let s = spatial[(ny - y as i32 + r) as usize * side + (nx - x as i32 + r) as usize];
+                let weight = s * (-dist2 / (2.0 * range * range)).exp();
+                for c in 0..3 {
+                    acc[c] += weight * q[c] as f64;
+                }
+                total += weight;
+            }
+        }
+        let o = out.get_pixel_mut(x, y);
+        for c in 0..3 {
+            o[c] = (acc[c] / total).round().max(0.0).min(255.0) as u8;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filters() -> Vec<DenoiseData> {
+        vec![
+            DenoiseData::Median { radius: 2 },
+            DenoiseData::Gaussian { sigma: 1.5 },
+            DenoiseData::Bilateral { sigma: 1.5, range: 30.0 },
+        ]
+    }
+
+    #[test]
+    fn flat_image_is_unchanged() {
+        let mut img = image::RgbaImage::from_pixel(6, 5, image::Rgba([90, 140, 200, 180]));
+        // 透明像素保持原值，也不影响周围像素
+        img.put_pixel(2, 2, image::Rgba([7, 8, 9, 0]));
+        for data in filters() {
+            assert_eq!(apply(&img, &data), img);
+        }
+    }
+
+    #[test]
+    fn isolated_speck_is_removed_by_median() {
+        let mut img = image::RgbaImage::from_pixel(5, 5, image::Rgba([255, 255, 255, 255]));
+        img.put_pixel(2, 2, image::Rgba([0, 0, 0, 255]));
+        let out = apply(&img, &DenoiseData::Median { radius: 1 });
+        assert_eq!(out.get_pixel(2, 2).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn limits_are_enforced() {
+        for data in filters() {
+            assert!(data.validate().is_ok());
+        }
+        assert!(DenoiseData::Median { radius: MAX_RADIUS }.validate().is_ok());
+        assert!(DenoiseData::Gaussian { sigma: MAX_SIGMA }.validate().is_ok());
+        let invalid = vec![
+            DenoiseData::Median { radius: 0 },
+            DenoiseData::Median { radius: MAX_RADIUS + 1 },
+            DenoiseData::Gaussian { sigma: 0.0 },
+            DenoiseData::Gaussian { sigma: std::f64::NAN },
+            DenoiseData::Gaussian { sigma: MAX_SIGMA + 0.5 },
+            DenoiseData::Bilateral { sigma: 1.0, range: 0.0 },
+            DenoiseData::Bilateral { sigma: MAX_SIGMA + 0.5, range: 30.0 },
+        ];
+        for data in invalid {
+            assert!(data.validate().is_err());
+        }
+    }
+}
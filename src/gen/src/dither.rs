@@ -0,0 +1,284 @@
+// 阈值映射的抖动：误差扩散（Floyd–Steinberg / Atkinson / Jarvis–Judice–Ninke）与有序 Bayer 抖动。
+// 抖动只改变像素落入哪一段，输出颜色仍然只取各段允许的输出值。
+// 误差在亮度域中计算，每段的代表亮度取该段上下界的中点（与平均模式一致）。
+use serde::{Deserialize, Serialize};
+use threshold::{luma, map_levels, segment_index};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DitherMode {
+    None,
+    FloydSteinberg,
+    Atkinson,
+    JarvisJudiceNinke,
+    Bayer,
+}
+
+impl Default for DitherMode {
+    fn default() -> Self {
+        DitherMode::None
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct DitherOptions {
+    #[serde(default)]
+    pub mode: DitherMode,
+    // Bayer 矩阵边长：2、4 或 8
+    #[serde(default = "default_bayer_size")]
+    pub bayer_size: u32,
+    // 蛇形扫描：奇数行从右向左处理，扩散核随之水平镜像
+    #[serde(default = "default_true")]
+    pub serpentine: bool,
+    // 仅向不透明像素扩散误差；被跳过的权重按比例分给其余不透明邻居
+    #[serde(default = "default_true")]
+    pub opaque_only: bool,
+}
+
+fn default_bayer_size() -> u32 {
+    4
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for DitherOptions {
+    fn default() -> Self {
+        DitherOptions { mode: DitherMode::None, bayer_size: default_bayer_size(), serpentine: true, opaque_only: true }
+    }
+}
+
+impl DitherOptions {
+    pub fn is_none(&self) -> bool {
+        self.mode == DitherMode::None
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == DitherMode::Bayer && ![2, 4, 8].contains(&self.bayer_size) {
+            return Err(format!("Unsupported Bayer matrix size {}, expected 2, 4 or 8", self.bayer_size));
+        }
+        Ok(())
+    }
+}
+
+// 误差扩散核：(dx, dy, 权重)，以及权重的除数
+const FLOYD_STEINBERG: (&[(i32, usize, f32)], f32) = (&[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)], 16.0);
+// Atkinson 只扩散 6/8 的误差，这是该算法的特点
+const ATKINSON: (&[(i32, usize, f32)], f32) = (&[(1, 0, 1.0), (2, 0, 1.0), (-1, 1, 1.0), (0, 1, 1.0), (1, 1, 1.0), (0, 2, 1.0)], 8.0);
+const JARVIS_JUDICE_NINKE: (&[(i32, usize, f32)], f32) = (
+    &[
+        (1, 0, 7.0), (2, 0, 5.0),
+        (-2, 1, 3.0), (-1, 1, 5.0), (0, 1, 7.0), (1, 1, 5.0), (2, 1, 3.0),
+        (-2, 2, 1.0), (-1, 2, 3.0), (0, 2, 5.0), (1, 2, 3.0), (2, 2, 1.0),
+    ],
+    48.0,
+);
+
+// 每段的代表亮度：上下界中点，最后一段上界为 255
+fn representatives(stops: &[u8]) -> Vec<f32> {
+    (0..=stops.len())
+        .map(|i| {
+            let lower = if i == 0 { 0.0 } else { stops[i - 1] as f32 };
+            let upper = if i < stops.len() { stops[i] as f32 } else { 255.0 };
+            (lower + upper) / 2.0
+        })
+        .collect()
+}
+
+fn level_of(stops: &[u8], value: f32) -> usize {
+    segment_index(stops, value.round().max(0.0).min(255.0) as u8)
+}
+
+// 按抖动选项进行阈值映射；colors 为各段输出颜色，透明像素保持为(0,0,0,0)
+pub fn dither_levels(img: &image::RgbaImage, stops: &[u8], colors: &[[u8; 4]], options: &DitherOptions) -> image::RgbaImage {
+    match options.mode {
+        DitherMode::None => map_levels(img, stops, colors),
+        DitherMode::FloydSteinberg => diffuse(img, stops, colors, FLOYD_STEINBERG, options),
+        DitherMode::Atkinson => diffuse(img, stops, colors, ATKINSON, options),
+        DitherMode::JarvisJudiceNinke => diffuse(img, stops, colors, JARVIS_JUDICE_NINKE, options),
+        DitherMode::Bayer => ordered(img, stops, colors, options.bayer_size),
+    }
+}
+
+fn diffuse(img: &image::RgbaImage, stops: &[u8], colors: &[[u8; 4]], kernel: (&[(i32, usize, f32)], f32), options: &DitherOptions) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let (weights, divisor) = kernel;
+    let reps = representatives(stops);
+    let total: f32 = weights.iter().map(|w| w.2).sum::<f32>() / divisor;
+    // 误差缓冲只保留当前行及其后两行（核最多向下延伸两行）
+    let mut rows: Vec<Vec<f32>> = vec![vec![0.0; width as usize]; 3];
+    let mut out = image::RgbaImage::new(width, height);
+    let opaque = |x: i32, y: u32| x >= 0 && x < width as i32 && y < height && img.get_pixel(x as u32, y)[3] != 0;
+
+    for y in 0..height {
+        let reverse = options.serpentine && y % 2 == 1;
+        for i in 0..width {
+            let x = if reverse { width - 1 - i } else { i };
+            let p = img.get_pixel(x, y);
+            if p[3] == 0 {
+                out.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+                continue;
+            }
+            let value = luma(p[0], p[1], p[2]) as f32 + rows[0][x as usize];
+            let level = level_of(stops, value);
+            out.put_pixel(x, y, image::Rgba(colors[level]));
+            let error = value - reps[level];
+
+            // 反向扫描时核水平镜像；限制为不透明目标时，把跳过的权重按比例补给其余目标
+            let dir = if reverse { -1 } else { 1 };
+            let accept = |tx: i32, dy: usize| tx >= 0 && tx < width as i32 && (!options.opaque_only || opaque(tx, y + dy as u32));
+            let used: f32 = weights
+                .iter()
+                .filter(|&&(dx, dy, _)| accept(x as i32 + dx * dir, dy))
+                .map(|w| w.2)
+                .sum::<f32>()
+                / divisor;
+            let scale = if options.opaque_only && used > 0.0 { total / used } else { 1.0 };
+            for &(dx, dy, w) in weights {
+                let tx = x as i32 + dx * dir;
+                if accept(tx, dy) {
+                    rows[dy][tx as usize] += error * w / divisor * scale;
+                }
+            }
+        }
+        rows.rotate_left(1);
+        for e in rows[2].iter_mut() {
+            *e = 0.0;
+        }
+    }
+    out
+}
+
+// 生成 n×n Bayer 矩阵（n 为 2 的幂），元素为 0..n²-1
+fn bayer_matrix(n: usize) -> Vec<Vec<u32>> {
+    let mut m = vec![vec![0u32]];
+    let mut size = 1;
+    while size < n {
+        let mut next = vec![vec![0u32; size * 2]; size * 2];
+        for y in 0..size {
+            for x in 0..size {
+                let v = m[y][x] * 4;
+                next[y][x] = v;
+                next[y][x + size] = v + 2;
+                next[y + size][x] = v + 3;
+                next[y + size][x + size] = v + 1;
+            }
+        }
+        m = next;
+        size *= 2;
+    }
+    m
+}
+
+// 有序抖动：在相邻两段的代表亮度之间按 Bayer 阈值选择其一
+fn ordered(img: &image::RgbaImage, stops: &[u8], colors: &[[u8; 4]], size: u32) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let reps = representatives(stops);
+    let matrix = bayer_matrix(size as usize);
+    let cells = (size * size) as f32;
+    let mut out = image::RgbaImage::new(width, height);
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] == 0 {
+            out.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            continue;
+        }
+        let value = luma(p[0], p[1], p[2]) as f32;
+        // 找到包围该亮度的两段代表值 reps[lo] <= value <= reps[lo + 1]
+        let hi = reps.iter().position(|&r| value <= r).unwrap_or(reps.len() - 1);
+        let level = if hi == 0 || value == reps[hi] {
+            hi
+        } else {
+            let lo = hi - 1;
+            let t = (value - reps[lo]) / (reps[hi] - reps[lo]);
+            let threshold = (matrix[(y % size) as usize][(x % size) as usize] as f32 + 0.5) / cells;
+            if t > threshold { hi } else { lo }
+        };
+        out.put_pixel(x, y, image::Rgba(colors[level]));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFF: [u8; 4] = [0, 0, 0, 255];
+    const ON: [u8; 4] = [255, 255, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    fn options(mode: DitherMode, serpentine: bool, opaque_only: bool) -> DitherOptions {
+        DitherOptions { mode, bayer_size: 4, serpentine, opaque_only }
+    }
+
+    fn gray(width: u32, height: u32, value: u8) -> image::RgbaImage {
+        image::RgbaImage::from_pixel(width, height, image::Rgba([value, value, value, 255]))
+    }
+
+    fn on_ratio(img: &image::RgbaImage) -> f64 {
+        img.pixels().filter(|p| p.0 == ON).count() as f64 / (img.width() * img.height()) as f64
+    }
+
+    #[test]
+    fn flat_gray_diffuses_to_expected_ratio() {
+        // 代表亮度为 64 与 191.5，亮度 128 的平面应约有一半像素落入亮段
+        let expected = (128.0 - 64.0) / (191.5 - 64.0);
+        for &mode in &[DitherMode::FloydSteinberg, DitherMode::Atkinson, DitherMode::JarvisJudiceNinke] {
+            for &serpentine in &[false, true] {
+                let out = dither_levels(&gray(64, 64, 128), &[128], &[OFF, ON], &options(mode, serpentine, true));
+                let ratio = on_ratio(&out);
+                assert!((ratio - expected).abs() < 0.03, "ratio {} for serpentine {}", ratio, serpentine);
+            }
+        }
+    }
+
+    #[test]
+    fn serpentine_mirrors_the_kernel_on_odd_rows() {
+        // 2×2 亮度 100：第二行的误差来自第一行，先处理的像素把 7/16 的误差推给同行的另一个像素
+        let img = gray(2, 2, 100);
+        let forward = dither_levels(&img, &[128], &[OFF, ON], &options(DitherMode::FloydSteinberg, false, false));
+        let serpentine = dither_levels(&img, &[128], &[OFF, ON], &options(DitherMode::FloydSteinberg, true, false));
+        let row = |img: &image::RgbaImage, y| vec![img.get_pixel(0, y).0, img.get_pixel(1, y).0];
+        assert_eq!(row(&forward, 0), vec![OFF, OFF]);
+        assert_eq!(row(&serpentine, 0), vec![OFF, OFF]);
+        assert_eq!(row(&forward, 1), vec![OFF, ON]);
+        assert_eq!(row(&serpentine, 1), vec![ON, OFF]);
+    }
+
+    #[test]
+    fn opaque_only_reweights_around_transparent_pixels() {
+        // 左下角透明：仅不透明时，原本扩散给它的误差按比例补给其余邻居，使右上角像素进入亮段
+        let mut img = gray(2, 2, 100);
+        img.put_pixel(0, 1, image::Rgba([100, 100, 100, 0]));
+        let reweighted = dither_levels(&img, &[128], &[OFF, ON], &options(DitherMode::FloydSteinberg, true, true));
+        let plain = dither_levels(&img, &[128], &[OFF, ON], &options(DitherMode::FloydSteinberg, true, false));
+        assert_eq!(reweighted.get_pixel(1, 0).0, ON);
+        assert_eq!(plain.get_pixel(1, 0).0, OFF);
+        assert_eq!(reweighted.get_pixel(0, 1).0, CLEAR);
+        assert_eq!(plain.get_pixel(0, 1).0, CLEAR);
+    }
+
+    #[test]
+    fn bayer_matrices_hold_each_threshold_once() {
+        assert_eq!(bayer_matrix(2), vec![vec![0, 2], vec![3, 1]]);
+        for &n in &[2usize, 4, 8] {
+            let mut values: Vec<u32> = bayer_matrix(n).into_iter().flatten().collect();
+            values.sort();
+            assert_eq!(values, (0..(n * n) as u32).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn bayer_splits_flat_gray_evenly_per_tile() {
+        let mut img = gray(8, 8, 128);
+        img.put_pixel(3, 3, image::Rgba([128, 128, 128, 0]));
+        let out = dither_levels(&img, &[128], &[OFF, ON], &options(DitherMode::Bayer, true, true));
+        // 每个 4×4 单元中阈值低于 0.502 的 8 个位置落入亮段；(3, 3) 是其中之一，透明后保持透明
+        assert_eq!(bayer_matrix(4)[3][3], 5);
+        assert_eq!(out.get_pixel(3, 3).0, CLEAR);
+        assert_eq!(out.pixels().filter(|p| p.0 == ON).count(), 31);
+        assert!(options(DitherMode::Bayer, true, true).validate().is_ok());
+        assert!(DitherOptions { bayer_size: 3, ..options(DitherMode::Bayer, true, true) }.validate().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+// 像素级编辑：画点、直线（Bresenham）、实心矩形、4/8 邻接的油漆桶填充。
+// 坐标均为图像坐标，超出图片的部分忽略；颜色为 RGBA，alpha 为 0 即擦除为透明。
+use regions::Connectivity;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "tool", rename_all = "camelCase")]
+pub enum EditData {
+    Pixel { x: i32, y: i32, color: [u8; 4] },
+    Line { x0: i32, y0: i32, x1: i32, y1: i32, color: [u8; 4] },
+    Rect { x: i32, y: i32, width: u32, height: u32, color: [u8; 4] },
+    // 从 (x, y) 开始填充与起点颜色相同的连通区域（透明像素之间视为相同）
+    Fill {
+        x: i32,
+        y: i32,
+        color: [u8; 4],
+        #[serde(default)]
+        connectivity: Connectivity,
+    },
+}
+
+fn put(img: &mut image::RgbaImage, x: i32, y: i32, color: [u8; 4]) {
+    let (width, height) = img.dimensions();
+    if x >= 0 && y >= 0 && x < width as i32 && y < height as i32 {
+        img.put_pixel(x as u32, y as u32, image::Rgba(color));
+    }
+}
+
+// 将线段裁剪到图片范围内（Liang–Barsky），避免远在图片外的端点使 Bresenham 走过大量无效像素。
+// 两端点都在图片内时原样返回；线段与图片不相交时返回 None
+fn clip_line(x0: i32, y0: i32, x1: i32, y1: i32, width: u32, height: u32) -> Option<(i32, i32, i32, i32)> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let (max_x, max_y) = ((width - 1) as f64, (height - 1) as f64);
+    let (fx0, fy0) = (x0 as f64, y0 as f64);
+    let (dx, dy) = (x1 as f64 - fx0, y1 as f64 - fy0);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for &(p, q) in &[(-dx, fx0), (dx, max_x - fx0), (-dy, fy0), (dy, max_y - fy0)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let r = q / p;
+            if p < 0.0 {
+                t0 = t0.max(r);
+            } else {
+                t1 = t1.min(r);
+            }
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    let clamp_x = |v: f64| v.round().max(0.0).min(max_x) as i32;
+    let clamp_y = |v: f64| v.round().max(0.0).min(max_y) as i32;
+    Some((clamp_x(fx0 + t0 * dx), clamp_y(fy0 + t0 * dy), clamp_x(fx0 + t1 * dx), clamp_y(fy0 + t1 * dy)))
+}
+
+fn same(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> bool {
+    (a[3] == 0 && b[3] == 0) || a == b
+}
+
+pub fn apply(img: &image::RgbaImage, data: &EditData) -> image::RgbaImage {
+    let mut out = img.clone();
+    match *data {
+        EditData::Pixel { x, y, color } => put(&mut out, x, y, color),
+        EditData::Line { x0, y0, x1, y1, color } => {
+            let (width, height) = img.dimensions();
+            let (x0, y0, x1, y1) = match clip_line(x0, y0, x1, y1, width, height) {
+                Some(segment) => segment,
+                None => return out,
+            };
+            let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+            let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+            let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+            loop {
+                put(&mut out, x, y, color);
+                if x == x1 && y == y1 {
+                    break;
+                }
+                let e2 = 2 * err;
+                if e2 >= dy {
+                    err += dy;
+                    x += sx;
+                }
+                if e2 <= dx {
+                    err += dx;
+                    y += sy;
+                }
+            }
+        }
+        EditData::Rect { x, y, width, height, color } => {
+            // 先将矩形限制在图片范围内，超大的宽高不会逐个遍历图片外的像素
+            let (img_width, img_height) = img.dimensions();
+            let (x, y) = (x as i64, y as i64);
+            let (left, right) = (x.max(0), (x + width as i64).min(img_width as i64));
+            let (top, bottom) = (y.max(0), (y + height as i64).min(img_height as i64));
+            for py in top..bottom {
+                for px in left..right {
+                    out.put_pixel(px as u32, py as u32, image::Rgba(color));
+                }
+            }
+        }
+        EditData::Fill { x, y, color, connectivity } => {
+            let (width, height) = img.dimensions();
+            if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                return out;
+            }
+            let target = *img.get_pixel(x as u32, y as u32);
+            let neighbours: &[(i32, i32)] = match connectivity {
+                Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+                Connectivity::Eight => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)],
+            };
+            let mut visited = vec![false; (width * height) as usize];
+            let mut queue = vec![(x, y)];
+            visited[(y as u32 * width + x as u32) as usize] = true;
+            while let Some((cx, cy)) = queue.pop() {
+                out.put_pixel(cx as u32, cy as u32, image::Rgba(color));
+                for &(dx, dy) in neighbours {
+                    let (nx, ny) = (cx + dx, cy + dy);
+                    if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let idx = (ny as u32 * width + nx as u32) as usize;
+                    if !visited[idx] && same(img.get_pixel(nx as u32, ny as u32), &target) {
+                        visited[idx] = true;
+                        queue.push((nx, ny));
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INK: [u8; 4] = [255, 0, 0, 255];
+
+    fn blank(width: u32, height: u32) -> image::RgbaImage {
+        image::RgbaImage::from_pixel(width, height, image::Rgba([255, 255, 255, 255]))
+    }
+
+    fn inked(img: &image::RgbaImage) -> Vec<(u32, u32)> {
+        img.enumerate_pixels().filter(|(_, _, p)| p.0 == INK).map(|(x, y, _)| (x, y)).collect()
+    }
+
+    #[test]
+    fn rect_is_clamped_to_image() {
+        let out = apply(&blank(4, 3), &EditData::Rect { x: -2, y: 1, width: 4_000_000_000, height: u32::MAX, color: INK });
+        assert_eq!(inked(&out), vec![(0, 1), (1, 1), (2, 1), (3, 1), (0, 2), (1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn line_inside_image_is_unchanged_by_clipping() {
+        let out = apply(&blank(5, 5), &EditData::Line { x0: 0, y0: 0, x1: 4, y1: 2, color: INK });
+        assert_eq!(inked(&out), vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn far_out_line_is_clipped() {
+        let out = apply(&blank(5, 5), &EditData::Line { x0: i32::MIN, y0: 2, x1: i32::MAX, y1: 2, color: INK });
+        assert_eq!(inked(&out), vec![(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)]);
+        let diagonal = apply(&blank(5, 5), &EditData::Line { x0: -3, y0: -3, x1: 7, y1: 7, color: INK });
+        assert_eq!(inked(&diagonal), vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+        let missed = apply(&blank(5, 5), &EditData::Line { x0: -100, y0: -1, x1: 100, y1: -1, color: INK });
+        assert!(inked(&missed).is_empty());
+    }
+
+    #[test]
+    fn fill_respects_connectivity() {
+        // 对角相连的两个白色区域被黑色隔开
+        let mut img = blank(3, 3);
+        for &(x, y) in &[(1, 0), (0, 1), (2, 1), (1, 2)] {
+            img.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+        }
+        let four = apply(&img, &EditData::Fill { x: 0, y: 0, color: INK, connectivity: Connectivity::Four });
+        assert_eq!(inked(&four), vec![(0, 0)]);
+        let eight = apply(&img, &EditData::Fill { x: 0, y: 0, color: INK, connectivity: Connectivity::Eight });
+        assert_eq!(inked(&eight), vec![(0, 0), (2, 0), (1, 1), (0, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn fill_treats_transparent_pixels_as_equal() {
+        let mut img = image::RgbaImage::from_pixel(3, 1, image::Rgba([0, 0, 0, 0]));
+        img.put_pixel(1, 0, image::Rgba([9, 9, 9, 0]));
+        let out = apply(&img, &EditData::Fill { x: 0, y: 0, color: INK, connectivity: Connectivity::Four });
+        assert_eq!(inked(&out).len(), 3);
+        let outside = apply(&img, &EditData::Fill { x: 5, y: 0, color: INK, connectivity: Connectivity::Four });
+        assert!(inked(&outside).is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+// 织物网格：高倍缩放时为每根经纱（像素列）与纬纱（像素行）画细线，每隔 major 格画粗线，
+// 并按经/纬密度换算出厘米刻度。只生成当前视口内的线段坐标，由 QML 绘制，开销与图片尺寸无关。
+use serde::{Deserialize, Serialize};
+
+// 经纬密度，随工程保存
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Density {
+    // 经密：每厘米的经纱根数（每个像素列为一根经纱）
+    pub warp: f64,
+    // 纬密：每厘米的纬纱根数（每个像素行为一根纬纱）
+    pub weft: f64,
+}
+
+impl Density {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.warp > 0.0 && self.weft > 0.0) || !self.warp.is_finite() || !self.weft.is_finite() {
+            return Err(format!("Density must be positive, got warp {} and weft {}", self.warp, self.weft));
+        }
+        Ok(())
+    }
+}
+
+// 视口：图像坐标下的可见区域与缩放倍数（屏幕像素 / 图像像素）
+#[derive(Deserialize)]
+pub struct GridRequest {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub zoom: f64,
+    // 每隔多少格画粗线，如 8 或 10
+    #[serde(default = "default_major")]
+    pub major: u32,
+}
+
+fn default_major() -> u32 {
+    10
+}
+
+// 相邻两条线在屏幕上至少相隔的像素数，更密时不画
+const MIN_SPACING: f64 = 4.0;
+// 厘米刻度在屏幕上至少相隔的像素数
+const MIN_TICK_SPACING: f64 = 60.0;
+// 可选的刻度间隔（厘米）
+const TICK_STEPS: [f64; 10] = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+
+#[derive(Serialize)]
+pub struct GridLine {
+    // true 为竖线（经纱之间），position 为 x；false 为横线，position 为 y
+    pub vertical: bool,
+    pub position: u32,
+    pub major: bool,
+}
+
+#[derive(Serialize)]
+pub struct RulerTick {
+    pub vertical: bool,
+    // 图像坐标
+    pub position: f64,
+    pub label: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GridGeometry {
+    pub lines: Vec<GridLine>,
+    // 没有设置密度时为空
+    pub ticks: Vec<RulerTick>,
+    // 刻度间隔（厘米），没有刻度时为 0
+    pub tick_step: f64,
+}
+
+impl GridRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.zoom > 0.0) {
+            return Err(format!("Zoom must be positive, got {}", self.zoom));
+        }
+        if self.major == 0 {
+            return Err("Major line interval must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 视口在一个方向上覆盖的网格线位置范围（线位于像素边界 0..=size）；视口完全在图片外时为空
+fn visible_range(start: f64, length: f64, size: u32) -> std::ops::RangeInclusive<u32> {
+    let first = start.max(0.0).ceil() as i64;
+    let last = (start + length).min(size as f64).floor() as i64;
+    if last < first {
+        return 1..=0;
+    }
+    first as u32..=last as u32
+}
+
+fn lines(vertical: bool, range: std::ops::RangeInclusive<u32>, zoom: f64, major: u32, out: &mut Vec<GridLine>) {
+    let show_minor = zoom >= MIN_SPACING;
+    if !show_minor && zoom * (major as f64) < MIN_SPACING {
+        return;
+    }
+    for position in range {
+        let is_major = position % major == 0;
+        if is_major || show_minor {
+            out.push(GridLine { vertical, position, major: is_major });
+        }
+    }
+}
+
+fn format_cm(value: f64) -> String {
+    // 刻度间隔最小 0.1 厘米，保留一位小数即可，整数时不显示小数
+    let rounded = (value * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{} cm", rounded as i64)
+    } else {
+        format!("{:.1} cm", rounded)
+    }
+}
+
+fn ticks(vertical: bool, start: f64, length: f64, size: u32, per_cm: f64, step: f64, out: &mut Vec<RulerTick>) {
+    let pixels = per_cm * step;
+    let first = (start.max(0.0) / pixels).ceil() as i64;
+    let last = ((start + length).min(size as f64) / pixels).floor() as i64;
+    for i in first..=last {
+        out.push(RulerTick { vertical, position: i as f64 * pixels, label: format_cm(i as f64 * step) });
+    }
+}
+
+pub fn geometry(request: &GridRequest, width: u32, height: u32, density: Option<&Density>) -> GridGeometry {
+    let mut grid_lines = Vec::new();
+    lines(true, visible_range(request.x, request.width, width), request.zoom, request.major, &mut grid_lines);
+    lines(false, visible_range(request.y, request.height, height), request.zoom, request.major, &mut grid_lines);
+
+    let mut ruler = Vec::new();
+    let mut tick_step = 0.0;
+    if let Some(density) = density {
+        // 两个方向使用同一刻度间隔，按较稀的方向（每厘米像素较少）选择
+        let per_cm = density.warp.min(density.weft);
+        if let Some(&step) = TICK_STEPS.iter().find(|&&s| s * per_cm * request.zoom >= MIN_TICK_SPACING) {
+            tick_step = step;
+            ticks(true, request.x, request.width, width, density.warp, step, &mut ruler);
+            ticks(false, request.y, request.height, height, density.weft, step, &mut ruler);
+        }
+    }
+    GridGeometry { lines: grid_lines, ticks: ruler, tick_step }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(range: std::ops::RangeInclusive<u32>) -> Vec<u32> {
+        range.collect()
+    }
+
+    fn request(x: f64, width: f64, zoom: f64) -> GridRequest {
+        GridRequest { x, y: 0.0, width, height: 0.5, zoom, major: 10 }
+    }
+
+    #[test]
+    fn visible_range_is_clamped_to_image() {
+        assert_eq!(positions(visible_range(2.3, 3.0, 10)), vec![3, 4, 5]);
+        assert_eq!(positions(visible_range(-4.0, 6.0, 10)), vec![0, 1, 2]);
+        assert_eq!(positions(visible_range(7.5, 100.0, 10)), vec![8, 9, 10]);
+        assert_eq!(positions(visible_range(0.0, 10.0, 10)), (0..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn viewport_outside_image_has_no_lines() {
+        assert!(positions(visible_range(-50.0, 20.0, 10)).is_empty());
+        assert!(positions(visible_range(15.0, 20.0, 10)).is_empty());
+        assert!(positions(visible_range(3.2, 0.5, 10)).is_empty());
+    }
+
+    #[test]
+    fn minor_lines_depend_on_zoom() {
+        let vertical = |geometry: GridGeometry| -> Vec<(u32, bool)> {
+            geometry.lines.iter().filter(|l| l.vertical).map(|l| (l.position, l.major)).collect()
+        };
+        let close = vertical(geometry(&request(8.0, 4.0, 4.0), 100, 100, None));
+        assert_eq!(close, vec![(8, false), (9, false), (10, true), (11, false), (12, false)]);
+        let far = vertical(geometry(&request(0.0, 35.0, 1.0), 100, 100, None));
+        assert_eq!(far, vec![(0, true), (10, true), (20, true), (30, true)]);
+        assert!(vertical(geometry(&request(0.0, 100.0, 0.3), 100, 100, None)).is_empty());
+    }
+
+    #[test]
+    fn ticks_use_each_direction_density() {
+        let mut out = Vec::new();
+        ticks(true, 5.0, 30.0, 100, 10.0, 1.0, &mut out);
+        let found: Vec<(f64, &str)> = out.iter().map(|t| (t.position, t.label.as_str())).collect();
+        assert_eq!(found, vec![(10.0, "1 cm"), (20.0, "2 cm"), (30.0, "3 cm")]);
+
+        out.clear();
+        ticks(false, 0.0, 4.0, 100, 3.0, 0.5, &mut out);
+        let labels: Vec<&str> = out.iter().map(|t| t.label.as_str()).collect();
+        assert_eq!(labels, vec!["0 cm", "0.5 cm", "1 cm"]);
+
+        out.clear();
+        ticks(true, 90.0, 50.0, 95, 10.0, 1.0, &mut out);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].position, 90.0);
+    }
+
+    #[test]
+    fn tick_step_follows_the_sparser_density() {
+        // 经密 10、纬密 20：按每厘米 10 像素选择，间隔至少 60 屏幕像素
+        let density = Density { warp: 10.0, weft: 20.0 };
+        let grid = geometry(&GridRequest { x: 0.0, y: 0.0, width: 250.0, height: 250.0, zoom: 1.0, major: 10 }, 1000, 1000, Some(&density));
+        assert_eq!(grid.tick_step, 10.0);
+        let vertical: Vec<f64> = grid.ticks.iter().filter(|t| t.vertical).map(|t| t.position).collect();
+        let horizontal: Vec<f64> = grid.ticks.iter().filter(|t| !t.vertical).map(|t| t.position).collect();
+        assert_eq!(vertical, vec![0.0, 100.0, 200.0]);
+        assert_eq!(horizontal, vec![0.0, 200.0]);
+        assert!(geometry(&request(0.0, 10.0, 1.0), 100, 100, None).ticks.is_empty());
+    }
+}
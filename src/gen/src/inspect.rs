@@ -0,0 +1,47 @@
+// 像素检查：查询图像坐标处的原图颜色、亮度、当前处理结果的颜色、所属阈值段及该段的纱线/组织设置，
+// 供界面在鼠标悬停时实时显示。
+use project::Project;
+use serde::Serialize;
+use threshold::{luma, segment_index};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PixelInfo {
+    pub x: u32,
+    pub y: u32,
+    pub original: [u8; 4],
+    // 与灰色预览相同的亮度
+    pub luma: u8,
+    // 当前处理结果在该坐标的颜色；尺寸不一致（如经过几何变换）时为 null
+    pub processed: Option<[u8; 4]>,
+    // 按工程中的阈值映射计算的段；没有阈值映射或像素透明时为 null
+    pub level: Option<usize>,
+    // 该段在颜色表中的纱线名称与组织
+    pub yarn: Option<String>,
+    pub structure: Option<String>,
+}
+
+pub fn inspect(original: &image::RgbaImage, processed: Option<&image::RgbaImage>, project: &Project, x: i32, y: i32) -> Option<PixelInfo> {
+    let (width, height) = original.dimensions();
+    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+        return None;
+    }
+    let (x, y) = (x as u32, y as u32);
+    let p = original.get_pixel(x, y);
+    let gray = luma(p[0], p[1], p[2]);
+    let level = match &project.threshold {
+        Some(mapping) if p[3] != 0 => Some(segment_index(&mapping.stops, gray)),
+        _ => None,
+    };
+    let entry = level.and_then(|level| project.color_table.iter().find(|e| e.level == level));
+    Some(PixelInfo {
+        x,
+        y,
+        original: p.0,
+        luma: gray,
+        processed: processed.filter(|img| img.dimensions() == (width, height)).map(|img| img.get_pixel(x, y).0),
+        level,
+        yarn: entry.map(|e| e.name.clone()).filter(|n| !n.is_empty()),
+        structure: entry.and_then(|e| e.structure.clone()),
+    })
+}
@@ -0,0 +1,104 @@
+// 单段查看：只以完全不透明显示选中的段，其余像素淡化或隐藏，便于检查某一段在图中的分布。
+// 像素所属的段按当前处理结果的颜色判断：等于该段的阈值映射编码或颜色表中的纱线颜色（与合并段一致）；
+// 不同段共用同一颜色时无法判断，拒绝执行。
+use recolor::LevelColors;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum Others {
+    // 其余像素降低不透明度
+    Dim,
+    // 其余像素完全透明
+    Hide,
+}
+
+impl Default for Others {
+    fn default() -> Self {
+        Others::Dim
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct IsolateData {
+    pub level: usize,
+    #[serde(default)]
+    pub others: Others,
+}
+
+impl IsolateData {
+    pub fn validate(&self, levels: &LevelColors) -> Result<(), String> {
+        let count = levels.codes.len();
+        if count == 0 {
+            return Err("Isolating a level needs a threshold mapping in the project".to_string());
+        }
+        if self.level >= count {
+            return Err(format!("Level index out of range: {} levels", count));
+        }
+        levels.check_distinct()
+    }
+}
+
+// 返回（显示用图片，该段的像素数）
+pub fn isolate(img: &image::RgbaImage, data: &IsolateData, levels: &LevelColors) -> Result<(image::RgbaImage, u32), String> {
+    data.validate(levels)?;
+    let code = &levels.codes[data.level][..3];
+    let color = &levels.colors[data.level][..3];
+    let mut out = img.clone();
+    let mut count = 0;
+    for p in out.pixels_mut() {
+        if p[3] == 0 {
+            continue;
+        }
+        let rgb = &p.0[..3];
+        if rgb == code || rgb == color {
+            p[3] = 255;
+            count += 1;
+        } else {
+            match data.others {
+                Others::Dim => p[3] /= 5,
+                Others::Hide => *p = image::Rgba([0, 0, 0, 0]),
+            }
+        }
+    }
+    Ok((out, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use recolor::{merge_levels, MergeLevelData};
+
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+    const GRAY: [u8; 4] = [128, 128, 128, 255];
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+    const RED: [u8; 4] = [200, 0, 0, 255];
+
+    fn strip(colors: &[[u8; 4]]) -> image::RgbaImage {
+        image::RgbaImage::from_fn(colors.len() as u32, 1, |x, _| image::Rgba(colors[x as usize]))
+    }
+
+    #[test]
+    fn counts_codes_and_yarn_colors_of_the_level() {
+        // 段 0 未着色（纱线颜色即编码），段 1 着红色
+        let levels = LevelColors { codes: vec![BLACK, WHITE], colors: vec![BLACK, RED] };
+        let img = strip(&[BLACK, RED, WHITE, [1, 2, 3, 0]]);
+        let (out, count) = isolate(&img, &IsolateData { level: 1, others: Others::Hide }, &levels).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(out.get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(out.get_pixel(1, 0).0, RED);
+        assert_eq!(out.get_pixel(3, 0).0[3], 0);
+    }
+
+    #[test]
+    fn shared_colors_between_levels_are_rejected() {
+        // 段 2 的纱线颜色与段 1 的编码相同，灰色像素无法判断属于哪一段
+        let levels = LevelColors { codes: vec![BLACK, GRAY, WHITE], colors: vec![BLACK, RED, GRAY] };
+        let img = strip(&[GRAY]);
+        assert!(isolate(&img, &IsolateData { level: 1, others: Others::Dim }, &levels).is_err());
+        assert!(merge_levels(&img, &MergeLevelData { from: 1, into: 0 }, &levels).is_err());
+        let distinct = LevelColors { codes: vec![BLACK, GRAY, WHITE], colors: vec![BLACK, RED, WHITE] };
+        let (merged, affected) = merge_levels(&img, &MergeLevelData { from: 1, into: 0 }, &distinct).unwrap();
+        assert_eq!((merged.get_pixel(0, 0).0, affected), (BLACK, 1));
+    }
+}
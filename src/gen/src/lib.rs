@@ -3,8 +3,34 @@ extern crate libc;
 extern crate notify;
 extern crate serde;
 extern crate serde_json;
+extern crate rayon;
+extern crate dirs;
 mod viewer_interface;
 mod viewer_impl;
+mod threshold;
+mod dither;
+mod project;
+mod quantize;
+mod regions;
+mod cleanup;
+mod morphology;
+mod pipeline;
+mod mask;
+mod tone;
+mod denoise;
+mod transform;
+mod repeat;
+mod wrap;
+mod recolor;
+mod edit;
+mod inspect;
+mod stats;
+mod preview;
+mod compare;
+mod isolate;
+mod grid;
+mod pyramid;
+mod settings;
 
 pub use viewer_interface::*;
 
@@ -0,0 +1,219 @@
+// 选区（感兴趣区域）蒙版：由矩形、多边形、颜色或阈值段组合而成（取并集，可反选）。
+// 各处理操作只改变选区内的像素，选区外的像素恢复为操作前的值。
+// 颜色/段选区以原图为准，透明像素不会被它们选中。
+use serde::{Deserialize, Serialize};
+use threshold::{luma, segment_index, ThresholdMappingData};
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Selection {
+    // 图像坐标下的矩形
+    Rect { x: u32, y: u32, width: u32, height: u32 },
+    // 图像坐标下的多边形顶点，按奇偶规则填充，以像素中心判断是否在内
+    Polygon { points: Vec<[f64; 2]> },
+    // 与给定颜色在每个通道上相差不超过 tolerance 的像素
+    Color {
+        color: [u8; 3],
+        #[serde(default)]
+        tolerance: u8,
+    },
+    // 落在工程中阈值映射第 level 段的像素
+    Level { level: usize },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MaskData {
+    pub shapes: Vec<Selection>,
+    #[serde(default)]
+    pub invert: bool,
+}
+
+pub struct Mask {
+    width: u32,
+    height: u32,
+    bits: Vec<bool>,
+}
+
+impl Mask {
+    pub fn build(data: &MaskData, img: &image::RgbaImage, threshold: Option<&ThresholdMappingData>) -> Result<Mask, String> {
+        let (width, height) = img.dimensions();
+        let mut bits = vec![false; (width * height) as usize];
+        for shape in &data.shapes {
+            match shape {
+                Selection::Rect { x, y, width: w, height: h } => {
+                    for py in (*y).min(height)..y.saturating_add(*h).min(height) {
+                        for px in (*x).min(width)..x.saturating_add(*w).min(width) {
+                            bits[(py * width + px) as usize] = true;
+                        }
+                    }
+                }
+                Selection::Polygon { points } => fill_polygon(&mut bits, width, height, points),
+                Selection::Color { color, tolerance } => {
+                    for (px, py, p) in img.enumerate_pixels() {
+                        let close = (0..3).all(|i| (p[i] as i16 - color[i] as i16).abs() <= *tolerance as i16);
+                        if p[3] != 0 && close {
+                            bits[(py * width + px) as usize] = true;
+                        }
+                    }
+                }
+                Selection::Level { level } => {
+                    let mapping = threshold.ok_or_else(|| "Level selection needs a threshold mapping in the project".to_string())?;
+                    for (px, py, p) in img.enumerate_pixels() {
+                        if p[3] != 0 && segment_index(&mapping.stops, luma(p[0], p[1], p[2])) == *level {
+                            bits[(py * width + px) as usize] = true;
+                        }
+                    }
+                }
+            }
+        }
+        if data.invert {
+            for b in bits.iter_mut() {
+                *b = !*b;
+            }
+        }
+        Ok(Mask { width, height, bits })
+    }
+
+    // 将选区外的像素恢复为 source 中的值；尺寸不一致（如经过几何变换）时不处理
+    pub fn restore_unselected(&self, source: &image::RgbaImage, result: &mut image::RgbaImage) {
+        if source.dimensions() != (self.width, self.height) || result.dimensions() != (self.width, self.height) {
+            return;
+        }
+        for (x, y, p) in result.enumerate_pixels_mut() {
+            if !self.bits[(y * self.width + x) as usize] {
+                *p = *source.get_pixel(x, y);
+            }
+        }
+    }
+
+    // 按最近邻缩放到 width × height（与阈值预览代理图的采样方式一致）
+    pub fn resample(&self, width: u32, height: u32) -> Mask {
+        let mut bits = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            let sy = (y as u64 * self.height as u64 / height as u64) as u32;
+            for x in 0..width {
+                let sx = (x as u64 * self.width as u64 / width as u64) as u32;
+                bits.push(self.bits[(sy * self.width + sx) as usize]);
+            }
+        }
+        Mask { width, height, bits }
+    }
+
+    // 预览叠加层：选区外覆盖半透明暗色，选区内完全透明
+    pub fn overlay(&self) -> image::RgbaImage {
+        let mut out = image::RgbaImage::new(self.width, self.height);
+        for (x, y, p) in out.enumerate_pixels_mut() {
+            if !self.bits[(y * self.width + x) as usize] {
+                *p = image::Rgba([0, 0, 0, 140]);
+            }
+        }
+        out
+    }
+}
+
+// 扫描线填充多边形：在每行像素中心处求与各边的交点，成对填充
+fn fill_polygon(bits: &mut [bool], width: u32, height: u32, points: &[[f64; 2]]) {
+    if points.len() < 3 {
+        return;
+    }
+    let mut xs: Vec<f64> = Vec::new();
+    for py in 0..height {
+        let yc = py as f64 + 0.5;
+        xs.clear();
+        for i in 0..points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            if (a[1] <= yc && b[1] > yc) || (b[1] <= yc && a[1] > yc) {
+                xs.push(a[0] + (yc - a[1]) / (b[1] - a[1]) * (b[0] - a[0]));
+            }
+        }
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for pair in xs.chunks(2) {
+            if pair.len() < 2 {
+                break;
+            }
+            // 像素中心 x + 0.5 落在 [x0, x1) 内
+            let start = (pair[0] - 0.5).ceil().max(0.0) as u32;
+            let end = ((pair[1] - 0.5).ceil().max(0.0) as u32).min(width);
+            for px in start..end {
+                bits[(py * width + px) as usize] = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 逐像素的奇偶规则判断：从像素中心向右的射线与各边相交的次数
+    fn reference_polygon(width: u32, height: u32, points: &[[f64; 2]]) -> Vec<bool> {
+        let mut bits = vec![false; (width * height) as usize];
+        if points.len() < 3 {
+            return bits;
+        }
+        for py in 0..height {
+            for px in 0..width {
+                let (xc, yc) = (px as f64 + 0.5, py as f64 + 0.5);
+                let mut inside = false;
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                    if (a[1] <= yc && b[1] > yc) || (b[1] <= yc && a[1] > yc) {
+                        let x = a[0] + (yc - a[1]) / (b[1] - a[1]) * (b[0] - a[0]);
+                        if xc < x {
+                            inside = !inside;
+                        }
+                    }
+                }
+                bits[(py * width + px) as usize] = inside;
+            }
+        }
+        bits
+    }
+
+    fn filled(width: u32, height: u32, points: &[[f64; 2]]) -> Vec<bool> {
+        let mut bits = vec![false; (width * height) as usize];
+        fill_polygon(&mut bits, width, height, points);
+        bits
+    }
+
+    #[test]
+    fn square_selects_pixels_with_centers_inside() {
+        let bits = filled(4, 4, &[[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0]]);
+        let selected: Vec<usize> = (0..16).filter(|&i| bits[i]).collect();
+        assert_eq!(selected, vec![5, 6, 9, 10]);
+    }
+
+    #[test]
+    fn scanline_matches_even_odd_reference() {
+        let shapes: Vec<Vec<[f64; 2]>> = vec![
+            vec![[0.2, 0.3], [15.7, 2.1], [7.4, 11.9]],
+            // 凹多边形
+            vec![[1.0, 1.0], [14.0, 1.0], [14.0, 10.0], [8.0, 4.5], [1.0, 10.0]],
+            // 自相交的五角星：中心区域按奇偶规则不被选中
+            vec![[8.0, 0.0], [12.7, 11.5], [0.4, 4.0], [15.6, 4.0], [3.3, 11.5]],
+            // 部分在图片外
+            vec![[-5.0, -3.0], [9.5, 2.5], [20.0, 14.0], [4.5, 20.0]],
+            // 顶点恰在像素中心上
+            vec![[2.5, 2.5], [10.5, 2.5], [10.5, 8.5], [2.5, 8.5]],
+            vec![[1.0, 1.0], [5.0, 5.0]],
+        ];
+        for points in &shapes {
+            assert_eq!(filled(16, 12, points), reference_polygon(16, 12, points), "{:?}", points);
+        }
+    }
+
+    #[test]
+    fn shapes_are_united_then_inverted() {
+        let img = image::RgbaImage::from_pixel(4, 2, image::Rgba([9, 9, 9, 255]));
+        let data = MaskData {
+            shapes: vec![
+                Selection::Rect { x: 3, y: 1, width: u32::MAX, height: 9 },
+                Selection::Polygon { points: vec![[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [0.0, 1.0]] },
+            ],
+            invert: true,
+        };
+        let mask = Mask::build(&data, &img, None).unwrap();
+        assert_eq!(mask.bits, vec![false, false, true, true, true, true, true, false]);
+    }
+}
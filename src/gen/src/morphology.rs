@@ -0,0 +1,287 @@
+// 按颜色图层的形态学操作：腐蚀 / 膨胀 / 开运算 / 闭运算。
+// 每种不透明颜色视为一个图层；可只处理指定图层，也可按优先级顺序处理全部图层。
+// 透明像素视为背景：不会被改色，也不会让相邻图层被腐蚀（与图片边界外的处理相同）。
+// 按 wrap 设置，图片边缘的邻域可从对边接续（循环织造时）。
+//
+// 确定性的平局规则：
+// - 腐蚀时，被移出图层的像素改为结构元素范围内出现最多的其他不透明颜色；
+//   数量相同时按优先级列表靠前者优先，未列出的颜色排在其后并按较深者优先（与清理散点一致），
+//   最后按 RGB 值从小到大。范围内没有其他不透明颜色时像素保持不变。
+// - 处理全部图层时，按优先级从低到高依次处理，冲突时高优先级图层的结果最终生效。
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use cleanup::brightness;
+use wrap::WrapMode;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum MorphOperation {
+    Erode,
+    Dilate,
+    Open,
+    Close,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StructuringElement {
+    Square,
+    Cross,
+    Disk,
+    Custom,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MorphologyData {
+    pub operation: MorphOperation,
+    pub element: StructuringElement,
+    // 结构元素半径（square/cross/disk）
+    #[serde(default = "default_radius")]
+    pub radius: u32,
+    // 自定义结构元素：奇数行列的 0/1 矩阵，中心为原点（element 为 custom 时使用）
+    #[serde(default)]
+    pub custom: Vec<Vec<u8>>,
+    // 只处理该颜色图层；省略时处理全部图层
+    #[serde(default)]
+    pub layer: Option<[u8; 3]>,
+    // 图层优先级（靠前者优先）
+    #[serde(default)]
+    pub priority: Vec<[u8; 3]>,
+    #[serde(default)]
+    pub wrap: WrapMode,
+}
+
+fn default_radius() -> u32 {
+    1
+}
+
+// 结构元素半径上限：偏移数随半径平方增长，每个图层的每个像素都要遍历一次
+const MAX_RADIUS: u32 = 16;
+
+impl MorphologyData {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.element == StructuringElement::Custom {
+            let rows = self.custom.len();
+            let cols = self.custom.first().map_or(0, |r| r.len());
+            if rows % 2 == 0 || cols % 2 == 0 || self.custom.iter().any(|r| r.len() != cols) {
+                return Err("Custom structuring element must be a rectangular matrix with odd width and height".to_string());
+            }
+            let limit = (2 * MAX_RADIUS + 1) as usize;
+            if rows > limit || cols > limit {
+                return Err(format!("Custom structuring element must be at most {}x{}, got {}x{}", limit, limit, cols, rows));
+            }
+        } else if self.radius > MAX_RADIUS {
+            return Err(format!("Structuring element radius must be at most {}, got {}", MAX_RADIUS, self.radius));
+        }
+        Ok(())
+    }
+
+    // 结构元素的偏移列表（不含中心）
+    fn offsets(&self) -> Vec<(i32, i32)> {
+        let r = self.radius as i32;
+        let mut offsets = Vec::new();
+        match self.element {
+            StructuringElement::Custom => {
+                let cy = (self.custom.len() / 2) as i32;
+                let cx = (self.custom.first().map_or(0, |r| r.len()) / 2) as i32;
+                for (y, row) in self.custom.iter().enumerate() {
+                    for (x, &v) in row.iter().enumerate() {
+                        if v != 0 {
+                            offsets.push((x as i32 - cx, y as i32 - cy));
+                        }
+                    }
+                }
+            }
+            element => {
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let inside = match element {
+                            StructuringElement::Square => true,
+                            StructuringElement::Cross => dx == 0 || dy == 0,
+                            _ => dx * dx + dy * dy <= r * r,
+                        };
+                        if inside {
+                            offsets.push((dx, dy));
+                        }
+                    }
+                }
+            }
+        }
+        offsets.retain(|&o| o != (0, 0));
+        offsets
+    }
+
+    // 颜色的排序键：优先级列表中的位置，其次亮度（较深优先，与清理散点同为 (r+g+b)/3），最后 RGB
+    fn rank(&self, c: [u8; 3]) -> (usize, u32, [u8; 3]) {
+        let index = self.priority.iter().position(|&p| p == c).unwrap_or(self.priority.len());
+        (index, brightness(c), c)
+    }
+}
+
+fn rgb_at(img: &image::RgbaImage, x: i32, y: i32, wrap: WrapMode) -> Option<[u8; 3]> {
+    let (width, height) = img.dimensions();
+    let (x, y) = wrap.resolve(x, y, width, height)?;
+    let p = img.get_pixel(x, y);
+    if p[3] == 0 { None } else { Some([p[0], p[1], p[2]]) }
+}
+
+// 腐蚀图层：结构元素范围内存在其他不透明颜色的图层像素改为该范围内的多数颜色
+fn erode(img: &image::RgbaImage, layer: [u8; 3], offsets: &[(i32, i32)], data: &MorphologyData) -> image::RgbaImage {
+    let mut out = img.clone();
+    let mut counts: HashMap<[u8; 3], u32> = HashMap::new();
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] == 0 || [p[0], p[1], p[2]] != layer {
+            continue;
+        }
+        counts.clear();
+        for &(dx, dy) in offsets {
+            if let Some(c) = rgb_at(img, x as i32 + dx, y as i32 + dy, data.wrap) {
+                if c != layer {
+                    *counts.entry(c).or_insert(0) += 1;
+                }
+            }
+        }
+        let best = counts.iter().max_by(|a, b| a.1.cmp(b.1).then(data.rank(*b.0).cmp(&data.rank(*a.0))));
+        if let Some((&c, _)) = best {
+            out.put_pixel(x, y, image::Rgba([c[0], c[1], c[2], p[3]]));
+        }
+    }
+    out
+}
+
+// 膨胀图层：结构元素范围内存在图层像素的其他不透明像素改为图层颜色
+fn dilate(img: &image::RgbaImage, layer: [u8; 3], offsets: &[(i32, i32)], wrap: WrapMode) -> image::RgbaImage {
+    let mut out = img.clone();
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] == 0 || [p[0], p[1], p[2]] == layer {
+            continue;
+        }
+        // 结构元素关于原点对称时与腐蚀互为对偶；自定义元素按反射后的偏移检查
+        if offsets.iter().any(|&(dx, dy)| rgb_at(img, x as i32 - dx, y as i32 - dy, wrap) == Some(layer)) {
+            out.put_pixel(x, y, image::Rgba([layer[0], layer[1], layer[2], p[3]]));
+        }
+    }
+    out
+}
+
+fn apply_to_layer(img: &image::RgbaImage, layer: [u8; 3], offsets: &[(i32, i32)], data: &MorphologyData) -> image::RgbaImage {
+    match data.operation {
+        MorphOperation::Erode => erode(img, layer, offsets, data),
+        MorphOperation::Dilate => dilate(img, layer, offsets, data.wrap),
+        MorphOperation::Open => dilate(&erode(img, layer, offsets, data), layer, offsets, data.wrap),
+        MorphOperation::Close => erode(&dilate(img, layer, offsets, data.wrap), layer, offsets, data),
+    }
+}
+
+pub fn apply(img: &image::RgbaImage, data: &MorphologyData) -> image::RgbaImage {
+    let offsets = data.offsets();
+    let layers: Vec<[u8; 3]> = match data.layer {
+        Some(layer) => vec![layer],
+        None => {
+            let mut layers: Vec<[u8; 3]> = img
+                .pixels()
+                .filter(|p| p[3] != 0)
+                .map(|p| [p[0], p[1], p[2]])
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            // 按优先级从低到高处理，高优先级图层最后处理
+            layers.sort_by(|a, b| data.rank(*b).cmp(&data.rank(*a)));
+            layers
+        }
+    };
+    let mut out = img.clone();
+    for layer in layers {
+        out = apply_to_layer(&out, layer, &offsets, data);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: [u8; 3] = [10, 10, 10];
+    const B: [u8; 3] = [200, 200, 200];
+    const C: [u8; 3] = [90, 0, 0];
+
+    // 每个字符一个像素：a / b / c 为三种颜色，. 为透明
+    fn rows(rows: &[&str]) -> image::RgbaImage {
+        image::RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            let c = match rows[y as usize].as_bytes()[x as usize] {
+                b'a' => A,
+                b'b' => B,
+                b'c' => C,
+                _ => return image::Rgba([0, 0, 0, 0]),
+            };
+            image::Rgba([c[0], c[1], c[2], 255])
+        })
+    }
+
+    fn data(operation: MorphOperation, element: StructuringElement) -> MorphologyData {
+        MorphologyData { operation, element, radius: 1, custom: Vec::new(), layer: Some(A), priority: Vec::new(), wrap: WrapMode::None }
+    }
+
+    #[test]
+    fn erode_keeps_only_fully_surrounded_pixels() {
+        let img = rows(&["bbbbb", "baaab", "baaab", "baaab", "bbbbb"]);
+        let out = apply(&img, &data(MorphOperation::Erode, StructuringElement::Square));
+        assert!(out == rows(&["bbbbb", "bbbbb", "bbabb", "bbbbb", "bbbbb"]));
+    }
+
+    #[test]
+    fn dilate_grows_by_the_element() {
+        let img = rows(&["bbbbb", "bbbbb", "bbabb", "bbbbb", "bbbbb"]);
+        let square = apply(&img, &data(MorphOperation::Dilate, StructuringElement::Square));
+        assert!(square == rows(&["bbbbb", "baaab", "baaab", "baaab", "bbbbb"]));
+        let cross = apply(&img, &data(MorphOperation::Dilate, StructuringElement::Cross));
+        assert!(cross == rows(&["bbbbb", "bbabb", "baaab", "bbabb", "bbbbb"]));
+    }
+
+    #[test]
+    fn open_removes_specks_and_close_fills_holes() {
+        let specks = rows(&["bbbbbbb", "baaabbb", "baaabab", "baaabbb", "bbbbbbb"]);
+        let opened = apply(&specks, &data(MorphOperation::Open, StructuringElement::Square));
+        assert!(opened == rows(&["bbbbbbb", "baaabbb", "baaabbb", "baaabbb", "bbbbbbb"]));
+        let hole = rows(&["aaaaa", "aaaaa", "aabaa", "aaaaa", "aaaaa"]);
+        let closed = apply(&hole, &data(MorphOperation::Close, StructuringElement::Square));
+        assert!(closed == rows(&["aaaaa", "aaaaa", "aaaaa", "aaaaa", "aaaaa"]));
+    }
+
+    #[test]
+    fn custom_element_uses_its_offsets() {
+        // 只含左侧邻居的结构元素：膨胀按反射后的偏移向左扩展
+        let custom = MorphologyData { custom: vec![vec![0, 0, 0], vec![1, 0, 0], vec![0, 0, 0]], ..data(MorphOperation::Dilate, StructuringElement::Custom) };
+        let out = apply(&rows(&["bbb", "bab", "bbb"]), &custom);
+        assert!(out == rows(&["bbb", "aab", "bbb"]));
+        let even = MorphologyData { custom: vec![vec![1, 1]], ..data(MorphOperation::Dilate, StructuringElement::Custom) };
+        assert!(even.validate().is_err());
+    }
+
+    #[test]
+    fn erosion_ties_follow_priority_then_darker() {
+        let img = rows(&["bac"]);
+        let darker = apply(&img, &data(MorphOperation::Erode, StructuringElement::Square));
+        assert!(darker == rows(&["bcc"]));
+        let listed = MorphologyData { priority: vec![B], ..data(MorphOperation::Erode, StructuringElement::Square) };
+        assert!(apply(&img, &listed) == rows(&["bbc"]));
+    }
+
+    #[test]
+    fn transparent_pixels_are_left_alone() {
+        let img = rows(&[".a.", "bab"]);
+        let out = apply(&img, &data(MorphOperation::Dilate, StructuringElement::Square));
+        assert!(out == rows(&[".a.", "aaa"]));
+    }
+
+    #[test]
+    fn oversized_elements_are_rejected() {
+        let big = MorphologyData { radius: MAX_RADIUS + 1, ..data(MorphOperation::Erode, StructuringElement::Disk) };
+        assert!(big.validate().is_err());
+        assert!(MorphologyData { radius: MAX_RADIUS, ..big.clone() }.validate().is_ok());
+        let size = (2 * MAX_RADIUS + 3) as usize;
+        let custom = MorphologyData { custom: vec![vec![1; size]; size], ..data(MorphOperation::Erode, StructuringElement::Custom) };
+        assert!(custom.validate().is_err());
+    }
+}
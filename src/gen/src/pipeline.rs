@@ -0,0 +1,121 @@
+// 处理流水线：按顺序对原图执行一组步骤，每一步以上一步的结果为输入。
+// 步骤以 JSON 数组描述，例如：
+// [{ "op": "tone", "blackPoint": 20, "whitePoint": 235 }, { "op": "threshold", "stops": [85, 170], "averageMode": true },
+//  { "op": "morphology", "operation": "open", "element": "disk", "radius": 1 }, { "op": "cleanup" }]
+use cleanup::{self, CleanupData, ModeFilterData};
+use denoise::{self, DenoiseData};
+use edit::{self, EditData};
+use mask::Mask;
+use morphology::{self, MorphologyData};
+use quantize::{self, QuantizeData};
+use recolor::{self, LevelColors, MergeLevelData, ReplaceColorData};
+use regions::{self, RegionMergeData};
+use serde::Deserialize;
+use threshold::{self, ThresholdMappingData};
+use tone::{self, ToneData};
+use transform::{self, CropRect, FlipData, RotateData};
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Step {
+    Gray,
+    Tone(ToneData),
+    Denoise(DenoiseData),
+    Threshold(ThresholdMappingData),
+    Quantize(QuantizeData),
+    Cleanup(CleanupData),
+    ModeFilter(ModeFilterData),
+    MergeRegions(RegionMergeData),
+    Morphology(MorphologyData),
+    Crop(CropRect),
+    Rotate(RotateData),
+    Flip(FlipData),
+    Trim,
+    ReplaceColor(ReplaceColorData),
+    MergeLevels(MergeLevelData),
+    Edit(EditData),
+}
+
+impl Step {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Step::Tone(data) => data.validate(),
+            Step::Denoise(data) => data.validate(),
+            Step::Threshold(data) => data.validate(),
+            Step::ModeFilter(data) => data.validate(),
+            Step::Morphology(data) => data.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    // 几何变换只搬移像素，不受选区限制
+    pub fn is_geometric(&self) -> bool {
+        match self {
+            Step::Crop(_) | Step::Rotate(_) | Step::Flip(_) | Step::Trim => true,
+            _ => false,
+        }
+    }
+
+    // 改色与像素编辑步骤会报告受影响的像素数
+    pub fn reports_count(&self) -> bool {
+        match self {
+            Step::ReplaceColor(_) | Step::MergeLevels(_) | Step::Edit(_) => true,
+            _ => false,
+        }
+    }
+
+    // levels 为工程中各段的编码与颜色，合并段时使用
+    pub fn apply(&self, img: image::RgbaImage, levels: &LevelColors) -> Result<image::RgbaImage, String> {
+        Ok(match self {
+            Step::Gray => {
+                let mut img = img;
+                threshold::gray_image(&mut img);
+                img
+            }
+            Step::Tone(data) => tone::apply(&img, data),
+            Step::Denoise(data) => denoise::apply(&img, data),
+            Step::Threshold(data) => data.render(&img, &data.segment_colors()),
+            Step::Quantize(data) => quantize::quantize(&img, data)?,
+            Step::Cleanup(data) => cleanup::scattered_pixels(&img, data.wrap),
+            Step::ModeFilter(data) => cleanup::mode_filter(&img, data).0,
+            Step::MergeRegions(data) => regions::merge_small_regions(&img, data).0,
+            Step::Morphology(data) => morphology::apply(&img, data),
+            Step::Crop(rect) => transform::crop(&img, rect)?,
+            Step::Rotate(data) => transform::rotate(&img, data.degrees),
+            Step::Flip(data) => transform::flip(&img, data.direction),
+            Step::Trim => transform::trim(&img),
+            Step::ReplaceColor(data) => recolor::replace_color(&img, data).0,
+            Step::MergeLevels(data) => recolor::merge_levels(&img, data, levels)?.0,
+            Step::Edit(data) => edit::apply(&img, data),
+        })
+    }
+}
+
+// 依次执行各步骤；给定选区时，每一步之后把选区外的像素恢复为该步的输入。
+// 几何变换之后图片与选区不再对应，后续步骤不再受选区限制。
+// 返回（结果图片，每一步受影响的像素数），不报告数量的步骤为 None
+pub fn run(img: image::RgbaImage, steps: &[Step], mask: Option<&Mask>, levels: &LevelColors) -> Result<(image::RgbaImage, Vec<Option<u32>>), String> {
+    for step in steps {
+        step.validate()?;
+    }
+    let mut mask = mask;
+    let mut counts = Vec::with_capacity(steps.len());
+    let mut img = img;
+    for step in steps {
+        if step.is_geometric() {
+            mask = None;
+        }
+        if mask.is_none() && !step.reports_count() {
+            img = step.apply(img, levels)?;
+            counts.push(None);
+            continue;
+        }
+        let mut result = step.apply(img.clone(), levels)?;
+        if let Some(mask) = mask {
+            mask.restore_unselected(&img, &mut result);
+        }
+        counts.push(if step.reports_count() { Some(recolor::changed_pixels(&img, &result)) } else { None });
+        img = result;
+    }
+    Ok((img, counts))
+}
@@ -0,0 +1,61 @@
+// 阈值映射的实时预览：把原图按最近邻缩小为代理图（最长边不超过 PROXY_SIZE）并预先计算每个像素的亮度，
+// 拖动滑块时只需按 256 项查找表重新着色，不必读取与编码全分辨率图片；确认映射时仍对原图完整计算。
+use mask::Mask;
+use threshold::{self, ThresholdMappingData};
+
+pub const PROXY_SIZE: u32 = 1024;
+
+pub struct Proxy {
+    pub image: image::RgbaImage,
+    // 每个像素的亮度，透明像素为 None
+    luma: Vec<Option<u8>>,
+    // 缩放到代理图尺寸的选区；没有选区时为 None
+    mask: Option<Mask>,
+}
+
+impl Proxy {
+    pub fn new(img: &image::RgbaImage, mask: Option<&Mask>) -> Proxy {
+        let (width, height) = img.dimensions();
+        let longest = width.max(height).max(1);
+        let (pw, ph) = if longest <= PROXY_SIZE {
+            (width, height)
+        } else {
+            (
+                ((width as u64 * PROXY_SIZE as u64 / longest as u64) as u32).max(1),
+                ((height as u64 * PROXY_SIZE as u64 / longest as u64) as u32).max(1),
+            )
+        };
+        let image = image::RgbaImage::from_fn(pw, ph, |x, y| {
+            let sx = (x as u64 * width as u64 / pw as u64) as u32;
+            let sy = (y as u64 * height as u64 / ph as u64) as u32;
+            *img.get_pixel(sx, sy)
+        });
+        let luma = image
+            .pixels()
+            .map(|p| if p[3] == 0 { None } else { Some(threshold::luma(p[0], p[1], p[2])) })
+            .collect();
+        Proxy { image, luma, mask: mask.map(|m| m.resample(pw, ph)) }
+    }
+
+    // 按映射着色代理图；不抖动时直接查表，抖动时在代理图上运行相同的抖动算法
+    pub fn render(&self, mapping: &ThresholdMappingData) -> image::RgbaImage {
+        let colors = mapping.segment_colors();
+        let mut out = if mapping.dither.is_none() {
+            let lut = threshold::color_lut(&mapping.stops, &colors);
+            let (width, height) = self.image.dimensions();
+            let mut out = image::RgbaImage::new(width, height);
+            for (p, l) in out.pixels_mut().zip(self.luma.iter()) {
+                if let Some(g) = l {
+                    *p = image::Rgba(lut[*g as usize]);
+                }
+            }
+            out
+        } else {
+            mapping.render(&self.image, &colors)
+        };
+        if let Some(mask) = &self.mask {
+            mask.restore_unselected(&self.image, &mut out);
+        }
+        out
+    }
+}
@@ -0,0 +1,121 @@
+// 工程文件：与原图同目录保存为 `<原图路径>.project.json`，记录与该图片相关的设计配置
+// （最近一次阈值映射、每段的纱线颜色、选区、经纬密度等），重新打开同一图片时自动恢复。
+use serde::{Deserialize, Serialize};
+use grid::Density;
+use mask::MaskData;
+use recolor::LevelColors;
+use threshold::ThresholdMappingData;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    // 最近一次确认的阈值映射，决定图片被分为哪些段（level）
+    #[serde(default)]
+    pub threshold: Option<ThresholdMappingData>,
+    // 每段对应的纱线颜色，按 level 索引查找；未配置的段沿用阈值映射的输出
+    #[serde(default)]
+    pub color_table: Vec<LevelColor>,
+    // 选区：各处理操作只改变选区内的像素
+    #[serde(default)]
+    pub mask: Option<MaskData>,
+    // 经纬密度，用于网格的厘米刻度
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub density: Option<Density>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelColor {
+    pub level: usize,
+    pub color: [u8; 3],
+    // 纱线名称/编号，仅用于展示与导出
+    #[serde(default)]
+    pub name: String,
+    // 该段使用的织物组织（如 "satin5"），未指定时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structure: Option<String>,
+}
+
+// 颜色表导出条目：灰度编码与颜色的对应关系
+#[derive(Serialize)]
+pub struct ColorTableEntry {
+    pub level: usize,
+    pub code: [u8; 4],
+    pub color: [u8; 4],
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structure: Option<String>,
+}
+
+pub fn project_path(image_path: &str) -> String {
+    format!("{}{}", image_path, ".project.json")
+}
+
+impl Project {
+    // 读取图片对应的工程文件；不存在或解析失败时返回空工程
+    pub fn load(image_path: &str) -> Project {
+        if image_path.is_empty() {
+            return Project::default();
+        }
+        match std::fs::read_to_string(project_path(image_path)) {
+            Ok(text) => match serde_json::from_str(&text) {
+                Ok(project) => project,
+                Err(e) => {
+                    eprintln!("Error parsing project file: {}", e);
+                    Project::default()
+                }
+            },
+            Err(_) => Project::default(),
+        }
+    }
+
+    pub fn save(&self, image_path: &str) {
+        if image_path.is_empty() {
+            return;
+        }
+        match serde_json::to_string_pretty(self) {
+            Ok(text) => {
+                if let Err(e) = std::fs::write(project_path(image_path), text) {
+                    eprintln!("Error saving project file: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Error serializing project: {}", e),
+        }
+    }
+
+    // 阈值映射的各段颜色经颜色表覆盖后的结果
+    pub fn level_colors(&self, mapping: &ThresholdMappingData) -> Vec<[u8; 4]> {
+        let mut colors = mapping.segment_colors();
+        for entry in &self.color_table {
+            if let Some(c) = colors.get_mut(entry.level) {
+                *c = [entry.color[0], entry.color[1], entry.color[2], 255];
+            }
+        }
+        colors
+    }
+
+    // 各段的灰度编码与纱线颜色；没有阈值映射时为空
+    pub fn levels(&self) -> LevelColors {
+        match &self.threshold {
+            Some(mapping) => LevelColors { codes: mapping.segment_colors(), colors: self.level_colors(mapping) },
+            None => LevelColors { codes: Vec::new(), colors: Vec::new() },
+        }
+    }
+
+    // 生成“灰度编码 -> 颜色”对照表，配合灰度编码图一起导出
+    pub fn color_table_entries(&self, mapping: &ThresholdMappingData) -> Vec<ColorTableEntry> {
+        let codes = mapping.segment_colors();
+        let colors = self.level_colors(mapping);
+        (0..mapping.level_count())
+            .map(|level| {
+                let entry = self.color_table.iter().find(|e| e.level == level);
+                ColorTableEntry {
+                    level,
+                    code: codes[level],
+                    color: colors[level],
+                    name: entry.map(|e| e.name.clone()).unwrap_or_default(),
+                    structure: entry.and_then(|e| e.structure.clone()),
+                }
+            })
+            .collect()
+    }
+}
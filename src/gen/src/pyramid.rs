@@ -0,0 +1,201 @@
+// 分块多分辨率显示：超大图片不再整张交给 QML 的 Image，而是建立逐层减半的金字塔，
+// 按当前缩放倍数选择分辨率层，只切出视口内可见的 TILE_SIZE × TILE_SIZE 分块，
+// 使显示开销取决于窗口大小而不是图片大小。缩小时按最近邻取样，保持设计图颜色不被混合。
+use serde::{Deserialize, Serialize};
+
+pub const TILE_SIZE: u32 = 256;
+// 像素数超过该值时使用分块显示，较小的图片仍整张显示
+pub const TILED_MIN_PIXELS: u64 = 4096 * 4096;
+
+// 视口：图像坐标下的可见区域与缩放倍数（屏幕像素 / 图像像素）
+#[derive(Deserialize)]
+pub struct TileViewport {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub zoom: f64,
+}
+
+impl TileViewport {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.zoom > 0.0) {
+            return Err(format!("Zoom must be positive, got {}", self.zoom));
+        }
+        Ok(())
+    }
+}
+
+// 分块在图像坐标下的位置与大小，以及分块图片的路径
+#[derive(Serialize)]
+pub struct TileInfo {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub path: String,
+}
+
+#[derive(Serialize)]
+pub struct TileLayout {
+    // false 时图片较小，QML 直接显示整张图片，tiles 为空
+    pub tiled: bool,
+    pub width: u32,
+    pub height: u32,
+    pub level: usize,
+    pub tiles: Vec<TileInfo>,
+}
+
+pub struct Pyramid {
+    // levels[0] 为原始分辨率，之后每层宽高减半（向上取整），按需生成
+    levels: Vec<image::RgbaImage>,
+}
+
+// 某层中第 row 行、第 column 列的分块
+pub struct TileRef {
+    pub level: usize,
+    pub column: u32,
+    pub row: u32,
+}
+
+impl Pyramid {
+    pub fn new(img: image::RgbaImage) -> Pyramid {
+        Pyramid { levels: vec![img] }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.levels[0].dimensions()
+    }
+
+    // 最粗的一层：宽高都不超过一个分块
+    fn max_level(&self) -> usize {
+        let (width, height) = self.dimensions();
+        let mut level = 0;
+        while (width.max(height) >> level) > TILE_SIZE {
+            level += 1;
+        }
+        level
+    }
+
+    // 每层的分辨率为上一层的一半；选择分辨率不低于屏幕的最粗一层
+    pub fn level_for_zoom(&self, zoom: f64) -> usize {
+        if zoom >= 1.0 {
+            return 0;
+        }
+        ((1.0 / zoom).log2().floor() as usize).min(self.max_level())
+    }
+
+    fn level(&mut self, level: usize) -> &image::RgbaImage {
+        while self.levels.len() <= level {
+            let next = {
+                let prev = &self.levels[self.levels.len() - 1];
+                let (width, height) = prev.dimensions();
+                image::RgbaImage::from_fn((width + 1) / 2, (height + 1) / 2, |x, y| *prev.get_pixel(x * 2, y * 2))
+            };
+            self.levels.push(next);
+        }
+        &self.levels[level]
+    }
+
+    // 视口内可见的分块
+    pub fn visible_tiles(&self, viewport: &TileViewport) -> Vec<TileRef> {
+        let level = self.level_for_zoom(viewport.zoom);
+        let (width, height) = self.dimensions();
+        // 该层一个分块覆盖的原图像素数
+        let span = (TILE_SIZE as u64) << level;
+        let columns = ((width as u64 + span - 1) / span) as i64;
+        let rows = ((height as u64 + span - 1) / span) as i64;
+        let first_column = (viewport.x / span as f64).floor().max(0.0) as i64;
+        let first_row = (viewport.y / span as f64).floor().max(0.0) as i64;
+        let last_column = (((viewport.x + viewport.width) / span as f64).ceil() as i64).min(columns);
+        let last_row = (((viewport.y + viewport.height) / span as f64).ceil() as i64).min(rows);
+        let mut tiles = Vec::new();
+        for row in first_row..last_row {
+            for column in first_column..last_column {
+                tiles.push(TileRef { level, column: column as u32, row: row as u32 });
+            }
+        }
+        tiles
+    }
+
+    // 分块在图像坐标下的位置与大小（最后一行/列的分块可能较小）
+    pub fn tile_rect(&self, tile: &TileRef) -> (u32, u32, u32, u32) {
+        let (width, height) = self.dimensions();
+        let span = TILE_SIZE << tile.level;
+        let (x, y) = (tile.column * span, tile.row * span);
+        (x, y, span.min(width - x), span.min(height - y))
+    }
+
+    pub fn render_tile(&mut self, tile: &TileRef) -> image::RgbaImage {
+        let img = self.level(tile.level);
+        let (width, height) = img.dimensions();
+        let (x, y) = (tile.column * TILE_SIZE, tile.row * TILE_SIZE);
+        image::imageops::crop_imm(img, x, y, TILE_SIZE.min(width - x), TILE_SIZE.min(height - y)).to_image()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1000x600：第 1 层 500x300，第 2 层 250x150 已不超过一个分块
+    fn pyramid() -> Pyramid {
+        Pyramid::new(image::RgbaImage::from_fn(1000, 600, |x, y| image::Rgba([(x % 256) as u8, (y % 256) as u8, 0, 255])))
+    }
+
+    fn viewport(x: f64, y: f64, width: f64, height: f64, zoom: f64) -> TileViewport {
+        TileViewport { x, y, width, height, zoom }
+    }
+
+    #[test]
+    fn zoom_selects_level() {
+        let p = pyramid();
+        assert_eq!(p.level_for_zoom(4.0), 0);
+        assert_eq!(p.level_for_zoom(1.0), 0);
+        assert_eq!(p.level_for_zoom(0.75), 0);
+        assert_eq!(p.level_for_zoom(0.5), 1);
+        assert_eq!(p.level_for_zoom(0.3), 1);
+        assert_eq!(p.level_for_zoom(0.25), 2);
+        // 不超过最粗的一层
+        assert_eq!(p.level_for_zoom(0.01), 2);
+        assert!(viewport(0.0, 0.0, 1.0, 1.0, 0.0).validate().is_err());
+    }
+
+    #[test]
+    fn visible_tiles_cover_only_the_viewport() {
+        let p = pyramid();
+        assert_eq!(p.visible_tiles(&viewport(0.0, 0.0, 1000.0, 600.0, 1.0)).len(), 12);
+        let tiles = p.visible_tiles(&viewport(300.0, 0.0, 200.0, 100.0, 1.0));
+        assert_eq!(tiles.iter().map(|t| (t.level, t.column, t.row)).collect::<Vec<_>>(), vec![(0, 1, 0)]);
+        // 超出图片或在图片左上方的视口不会产生越界分块
+        assert!(p.visible_tiles(&viewport(5000.0, 0.0, 500.0, 500.0, 1.0)).is_empty());
+        let tiles = p.visible_tiles(&viewport(-800.0, -800.0, 1000.0, 1000.0, 1.0));
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(p.visible_tiles(&viewport(0.0, 0.0, 1000.0, 600.0, 0.1)).len(), 1);
+    }
+
+    #[test]
+    fn edge_tiles_are_clamped_to_the_image() {
+        let mut p = pyramid();
+        let corner = TileRef { level: 0, column: 3, row: 2 };
+        assert_eq!(p.tile_rect(&corner), (768, 512, 232, 88));
+        assert_eq!(p.render_tile(&corner).dimensions(), (232, 88));
+
+        let coarse = TileRef { level: 1, column: 1, row: 1 };
+        assert_eq!(p.tile_rect(&coarse), (512, 512, 488, 88));
+        assert_eq!(p.render_tile(&coarse).dimensions(), (244, 44));
+
+        let whole = TileRef { level: 2, column: 0, row: 0 };
+        assert_eq!(p.tile_rect(&whole), (0, 0, 1000, 600));
+        assert_eq!(p.render_tile(&whole).dimensions(), (250, 150));
+    }
+
+    #[test]
+    fn coarser_levels_use_nearest_samples() {
+        let mut p = pyramid();
+        let tile = p.render_tile(&TileRef { level: 1, column: 0, row: 0 });
+        assert_eq!(tile.get_pixel(10, 20).0, [20, 40, 0, 255]);
+        let tile = p.render_tile(&TileRef { level: 2, column: 0, row: 0 });
+        assert_eq!(tile.get_pixel(100, 70).0, [(400 % 256) as u8, (280 % 256) as u8, 0, 255]);
+    }
+}
@@ -0,0 +1,325 @@
+// 全彩量化：将 RGBA 图片缩减为 N 种颜色（CIELAB 空间下的中位切分 / k-means），
+// 或按最近 ΔE（CIE76，即 Lab 空间欧氏距离）映射到用户给定的纱线调色板。
+// 透明规则与其他操作一致：alpha 为 0 的像素输出 (0,0,0,0)，其余像素 alpha 设为 255。
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuantizeData {
+    // 目标颜色数（未给出 palette 时必填）
+    #[serde(default)]
+    pub colors: Option<usize>,
+    // 用户调色板：给出时直接按最近 ΔE 映射，忽略 colors/method
+    #[serde(default)]
+    pub palette: Option<Vec<[u8; 3]>>,
+    #[serde(default)]
+    pub method: QuantizeMethod,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuantizeMethod {
+    MedianCut,
+    Kmeans,
+}
+
+impl Default for QuantizeMethod {
+    fn default() -> Self {
+        QuantizeMethod::Kmeans
+    }
+}
+
+// k-means 最大迭代次数；中心移动小于该阈值（ΔE）时提前结束
+const KMEANS_MAX_ITERATIONS: usize = 20;
+const KMEANS_EPSILON: f32 = 0.05;
+
+// sRGB(D65) -> CIELAB
+pub fn rgb_to_lab(c: [u8; 3]) -> [f32; 3] {
+    fn linear(v: u8) -> f32 {
+        let v = v as f32 / 255.0;
+        if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+    }
+    fn f(t: f32) -> f32 {
+        if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 }
+    }
+    let (r, g, b) = (linear(c[0]), linear(c[1]), linear(c[2]));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+// CIE76 色差的平方，仅用于比较大小
+fn delta_e2(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let (dl, da, db) = (a[0] - b[0], a[1] - b[1], a[2] - b[2]);
+    dl * dl + da * da + db * db
+}
+
+fn nearest(lab: &[f32; 3], centers: &[[f32; 3]]) -> usize {
+    let mut best = 0;
+    let mut best_d = f32::MAX;
+    for (i, c) in centers.iter().enumerate() {
+        let d = delta_e2(lab, c);
+        if d < best_d {
+            best_d = d;
+            best = i;
+        }
+    }
+    best
+}
+
+// 图片中出现的不透明颜色及其像素数，量化在直方图上进行以减少计算量
+struct ColorSample {
+    rgb: [u8; 3],
+    lab: [f32; 3],
+    count: u32,
+}
+
+fn collect_samples(img: &image::RgbaImage) -> Vec<ColorSample> {
+    let mut histogram: HashMap<[u8; 3], u32> = HashMap::new();
+    for p in img.pixels() {
+        if p[3] != 0 {
+            *histogram.entry([p[0], p[1], p[2]]).or_insert(0) += 1;
+        }
+    }
+    let mut samples: Vec<ColorSample> = histogram
+        .into_iter()
+        .map(|(rgb, count)| ColorSample { rgb, lab: rgb_to_lab(rgb), count })
+        .collect();
+    // 固定顺序，保证结果与 HashMap 遍历顺序无关
+    samples.sort_by_key(|s| s.rgb);
+    samples
+}
+
+// 各簇成员按像素数加权的平均 RGB，作为输出颜色
+fn cluster_colors(samples: &[ColorSample], assignment: &[usize], k: usize) -> Vec<[u8; 3]> {
+    let mut sums = vec![[0u64; 4]; k];
+    for (s, &c) in samples.iter().zip(assignment) {
+        let n = s.count as u64;
+        sums[c][0] += s.rgb[0] as u64 * n;
+        sums[c][1] += s.rgb[1] as u64 * n;
+        sums[c][2] += s.rgb[2] as u64 * n;
+        sums[c][3] += n;
+    }
+    sums.iter()
+        .map(|s| {
+            if s[3] == 0 {
+                [0, 0, 0]
+            } else {
+                [((s[0] + s[3] / 2) / s[3]) as u8, ((s[1] + s[3] / 2) / s[3]) as u8, ((s[2] + s[3] / 2) / s[3]) as u8]
+            }
+        })
+        .collect()
+}
+
+// 在 Lab 空间做中位切分：每次选择像素数最多且跨度最大的盒子，沿最长轴按像素中位数切开
+fn median_cut(samples: &[ColorSample], k: usize) -> Vec<usize> {
+    let mut boxes: Vec<Vec<usize>> = vec![(0..samples.len()).collect()];
+    while boxes.len() < k {
+        // 选择可切分的盒子中 “像素数 × 最大跨度” 最大者
+        let mut best: Option<(usize, usize, f32)> = None;
+        for (bi, b) in boxes.iter().enumerate() {
+            if b.len() < 2 {
+                continue;
+            }
+            let pixels: u64 = b.iter().map(|&i| samples[i].count as u64).sum();
+            for axis in 0..3 {
+                let (lo, hi) = b.iter().fold((f32::MAX, f32::MIN), |(lo, hi), &i| {
+                    (lo.min(samples[i].lab[axis]), hi.max(samples[i].lab[axis]))
+                });
+                let score = (hi - lo) * pixels as f32;
+                if best.map_or(true, |(_, _, s)| score > s) {
+                    best = Some((bi, axis, score));
+                }
+            }
+        }
+        let (bi, axis, _) = match best {
+            Some(b) => b,
+            None => break, // 颜色数不足 k，无法继续切分
+        };
+        let mut b = boxes.swap_remove(bi);
+        b.sort_by(|&x, &y| samples[x].lab[axis].partial_cmp(&samples[y].lab[axis]).unwrap());
+        let half: u64 = b.iter().map(|&i| samples[i].count as u64).sum::<u64>() / 2;
+        let mut acc = 0u64;
+        let mut split = 1;
+        for (pos, &i) in b.iter().enumerate() {
+            acc += samples[i].count as u64;
+            if acc >= half {
+                split = (pos + 1).max(1).min(b.len() - 1);
+                break;
+            }
+        }
+        let upper = b.split_off(split);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+    let mut assignment = vec![0; samples.len()];
+    for (bi, b) in boxes.iter().enumerate() {
+        for &i in b {
+            assignment[i] = bi;
+        }
+    }
+    assignment
+}
+
+// 各簇的 Lab 加权中心；空簇为 None
+fn lab_centers(samples: &[ColorSample], assignment: &[usize], k: usize) -> Vec<Option<[f32; 3]>> {
+    let mut sums = vec![[0f64; 4]; k];
+    for (s, &c) in samples.iter().zip(assignment) {
+        let n = s.count as f64;
+        sums[c][0] += s.lab[0] as f64 * n;
+        sums[c][1] += s.lab[1] as f64 * n;
+        sums[c][2] += s.lab[2] as f64 * n;
+        sums[c][3] += n;
+    }
+    sums.iter()
+        .map(|s| if s[3] == 0.0 { None } else { Some([(s[0] / s[3]) as f32, (s[1] / s[3]) as f32, (s[2] / s[3]) as f32]) })
+        .collect()
+}
+
+// 以中位切分结果为初始中心的加权 k-means，初始化确定，结果可复现
+fn kmeans(samples: &[ColorSample], k: usize) -> Vec<usize> {
+    let mut assignment = median_cut(samples, k);
+    let k = assignment.iter().max().map_or(0, |&m| m + 1);
+    let mut centers: Vec<[f32; 3]> = lab_centers(samples, &assignment, k).into_iter().map(|c| c.unwrap_or([0.0; 3])).collect();
+    for _ in 0..KMEANS_MAX_ITERATIONS {
+        for (s, a) in samples.iter().zip(assignment.iter_mut()) {
+            *a = nearest(&s.lab, &centers);
+        }
+        // 空簇保留原中心，避免颜色数意外减少
+        let mut moved = 0f32;
+        for (c, n) in centers.iter_mut().zip(lab_centers(samples, &assignment, k)) {
+            if let Some(n) = n {
+                moved = moved.max(delta_e2(c, &n).sqrt());
+                *c = n;
+            }
+        }
+        if moved < KMEANS_EPSILON {
+            break;
+        }
+    }
+    assignment
+}
+
+fn apply_lookup(img: &image::RgbaImage, lookup: &HashMap<[u8; 3], [u8; 3]>) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let mut out = image::RgbaImage::new(width, height);
+    for (x, y, p) in img.enumerate_pixels() {
+        let mapped = if p[3] == 0 {
+            [0, 0, 0, 0]
+        } else {
+            let c = lookup[&[p[0], p[1], p[2]]];
+            [c[0], c[1], c[2], 255]
+        };
+        out.put_pixel(x, y, image::Rgba(mapped));
+    }
+    out
+}
+
+pub fn quantize(img: &image::RgbaImage, data: &QuantizeData) -> Result<image::RgbaImage, String> {
+    let samples = collect_samples(img);
+    let lookup: HashMap<[u8; 3], [u8; 3]> = if let Some(palette) = &data.palette {
+        if palette.is_empty() {
+            return Err("Palette is empty".to_string());
+        }
+        let palette_lab: Vec<[f32; 3]> = palette.iter().map(|&c| rgb_to_lab(c)).collect();
+        samples.iter().map(|s| (s.rgb, palette[nearest(&s.lab, &palette_lab)])).collect()
+    } else {
+        let k = match data.colors {
+            Some(k) if k > 0 => k,
+            _ => return Err("Quantization needs a positive color count or a palette".to_string()),
+        };
+        let assignment = match data.method {
+            QuantizeMethod::MedianCut => median_cut(&samples, k),
+            QuantizeMethod::Kmeans => kmeans(&samples, k),
+        };
+        let cluster_count = assignment.iter().max().map_or(0, |&m| m + 1);
+        let colors = cluster_colors(&samples, &assignment, cluster_count);
+        samples.iter().zip(&assignment).map(|(s, &c)| (s.rgb, colors[c])).collect()
+    };
+    Ok(apply_lookup(img, &lookup))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // 伪随机彩色图片，约 1/8 像素透明
+    fn sample_image(width: u32, height: u32) -> image::RgbaImage {
+        let mut state = 12345u32;
+        image::RgbaImage::from_fn(width, height, |_, _| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            let v = state >> 8;
+            let alpha = if v % 8 == 0 { 0 } else { 255 };
+            image::Rgba([(v >> 3) as u8, (v >> 11) as u8, (v >> 19) as u8 / 2 + 64, alpha])
+        })
+    }
+
+    fn opaque_colors(img: &image::RgbaImage) -> HashSet<[u8; 3]> {
+        img.pixels().filter(|p| p[3] != 0).map(|p| [p[0], p[1], p[2]]).collect()
+    }
+
+    fn assert_transparent_kept(img: &image::RgbaImage, out: &image::RgbaImage) {
+        for (p, q) in img.pixels().zip(out.pixels()) {
+            if p[3] == 0 {
+                assert_eq!(q.0, [0, 0, 0, 0]);
+            } else {
+                assert_eq!(q[3], 255);
+            }
+        }
+    }
+
+    #[test]
+    fn reduces_to_at_most_n_colors_deterministically() {
+        let img = sample_image(40, 30);
+        for &method in &[QuantizeMethod::MedianCut, QuantizeMethod::Kmeans] {
+            for &n in &[1, 2, 5, 16] {
+                let data = QuantizeData { colors: Some(n), palette: None, method };
+                let out = quantize(&img, &data).unwrap();
+                let colors = opaque_colors(&out);
+                assert!(!colors.is_empty() && colors.len() <= n, "{} colors for n = {}", colors.len(), n);
+                assert!(quantize(&img, &data).unwrap() == out);
+                assert_transparent_kept(&img, &out);
+            }
+        }
+    }
+
+    #[test]
+    fn few_colors_are_kept_exactly() {
+        let img = image::RgbaImage::from_fn(4, 1, |x, _| image::Rgba(if x % 2 == 0 { [250, 10, 10, 255] } else { [10, 10, 250, 128] }));
+        for &method in &[QuantizeMethod::MedianCut, QuantizeMethod::Kmeans] {
+            let out = quantize(&img, &QuantizeData { colors: Some(8), palette: None, method }).unwrap();
+            assert_eq!(out.get_pixel(0, 0).0, [250, 10, 10, 255]);
+            assert_eq!(out.get_pixel(1, 0).0, [10, 10, 250, 255]);
+        }
+    }
+
+    #[test]
+    fn palette_maps_to_nearest_delta_e() {
+        let img = sample_image(32, 32);
+        let palette = vec![[0, 0, 0], [255, 255, 255], [200, 30, 30], [30, 160, 60], [40, 60, 200], [128, 128, 128]];
+        let out = quantize(&img, &QuantizeData { colors: None, palette: Some(palette.clone()), method: QuantizeMethod::Kmeans }).unwrap();
+        assert_transparent_kept(&img, &out);
+        for (p, q) in img.pixels().zip(out.pixels()) {
+            if p[3] == 0 {
+                continue;
+            }
+            let lab = rgb_to_lab([p[0], p[1], p[2]]);
+            let best = palette.iter().map(|&c| delta_e2(&lab, &rgb_to_lab(c))).fold(f32::MAX, f32::min);
+            let chosen = delta_e2(&lab, &rgb_to_lab([q[0], q[1], q[2]]));
+            assert!(palette.contains(&[q[0], q[1], q[2]]));
+            assert_eq!(chosen, best);
+        }
+    }
+
+    #[test]
+    fn invalid_requests_are_rejected() {
+        let img = sample_image(4, 4);
+        assert!(quantize(&img, &QuantizeData { colors: None, palette: Some(Vec::new()), method: QuantizeMethod::Kmeans }).is_err());
+        assert!(quantize(&img, &QuantizeData { colors: Some(0), palette: None, method: QuantizeMethod::MedianCut }).is_err());
+        assert!(quantize(&img, &QuantizeData { colors: None, palette: None, method: QuantizeMethod::MedianCut }).is_err());
+    }
+}
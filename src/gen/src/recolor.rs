@@ -0,0 +1,118 @@
+// 映射之后的全局改色：把一种颜色（或容差范围内的颜色）替换为另一种颜色，或把段 A 合并到段 B。
+// 只处理不透明像素，按 RGB 比较，改色时保留像素原有 alpha；两种操作都返回受影响的像素数。
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone)]
+pub struct ReplaceColorData {
+    pub from: [u8; 3],
+    pub to: [u8; 3],
+    // 每个通道允许的最大差值，0 表示精确匹配
+    #[serde(default)]
+    pub tolerance: u8,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MergeLevelData {
+    // 被合并的段
+    pub from: usize,
+    // 合并到的段
+    pub into: usize,
+}
+
+// 各段在图中可能出现的颜色：阈值映射的输出（灰度编码）与颜色表中的纱线颜色
+pub struct LevelColors {
+    pub codes: Vec<[u8; 4]>,
+    pub colors: Vec<[u8; 4]>,
+}
+
+impl LevelColors {
+    // 按颜色判断像素所属的段时，一种颜色只能属于一个段：某段的编码或纱线颜色与另一段的相同
+    // （如颜色表把纱线颜色设成另一段的灰度编码）时无法区分，直接拒绝
+    pub fn check_distinct(&self) -> Result<(), String> {
+        let mut owners: Vec<([u8; 3], usize)> = Vec::new();
+        for (level, (&code, &color)) in self.codes.iter().zip(&self.colors).enumerate() {
+            for c in [rgb(code), rgb(color)].iter() {
+                match owners.iter().find(|(owned, _)| owned == c) {
+                    Some(&(_, owner)) if owner != level => {
+                        return Err(format!(
+                            "Levels {} and {} both use color {:?}; change the color table so each level has its own colors",
+                            owner, level, c
+                        ));
+                    }
+                    Some(_) => {}
+                    None => owners.push((*c, level)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rgb(c: [u8; 4]) -> [u8; 3] {
+    [c[0], c[1], c[2]]
+}
+
+// 将 to 返回 Some 的不透明像素改为该颜色，返回颜色实际改变的像素数
+fn recolor<F: Fn([u8; 3]) -> Option<[u8; 3]>>(img: &image::RgbaImage, to: F) -> (image::RgbaImage, u32) {
+    let mut out = img.clone();
+    let mut affected = 0;
+    for p in out.pixels_mut() {
+        if p[3] == 0 {
+            continue;
+        }
+        if let Some(c) = to([p[0], p[1], p[2]]) {
+            if c != [p[0], p[1], p[2]] {
+                p[0] = c[0];
+                p[1] = c[1];
+                p[2] = c[2];
+                affected += 1;
+            }
+        }
+    }
+    (out, affected)
+}
+
+// 两张同尺寸图片中发生变化的像素数（两个透明像素视为相同）；应用选区之后用它重新统计受影响的像素
+pub fn changed_pixels(before: &image::RgbaImage, after: &image::RgbaImage) -> u32 {
+    before
+        .pixels()
+        .zip(after.pixels())
+        .filter(|(a, b)| a != b && !(a[3] == 0 && b[3] == 0))
+        .count() as u32
+}
+
+pub fn replace_color(img: &image::RgbaImage, data: &ReplaceColorData) -> (image::RgbaImage, u32) {
+    recolor(img, |c| {
+        let close = (0..3).all(|i| (c[i] as i16 - data.from[i] as i16).abs() <= data.tolerance as i16);
+        if close { Some(data.to) } else { None }
+    })
+}
+
+impl MergeLevelData {
+    pub fn validate(&self, levels: &LevelColors) -> Result<(), String> {
+        let count = levels.codes.len();
+        if count == 0 {
+            return Err("Merging levels needs a threshold mapping in the project".to_string());
+        }
+        if self.from >= count || self.into >= count {
+            return Err(format!("Level index out of range: {} levels", count));
+        }
+        levels.check_distinct()
+    }
+}
+
+// 段 from 的编码像素改为段 into 的编码，段 from 的纱线颜色像素改为段 into 的纱线颜色
+pub fn merge_levels(img: &image::RgbaImage, data: &MergeLevelData, levels: &LevelColors) -> Result<(image::RgbaImage, u32), String> {
+    data.validate(levels)?;
+    let (from_code, into_code) = (rgb(levels.codes[data.from]), rgb(levels.codes[data.into]));
+    let (from_color, into_color) = (rgb(levels.colors[data.from]), rgb(levels.colors[data.into]));
+    Ok(recolor(img, |c| {
+        if c == from_code {
+            Some(into_code)
+        } else if c == from_color {
+            Some(into_color)
+        } else {
+            None
+        }
+    }))
+}
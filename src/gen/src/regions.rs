@@ -0,0 +1,323 @@
+// 连通区域：按颜色标记连通区域（4/8 邻接），并将面积过小的区域并入与其共享边界最长的相邻区域。
+// 与清理散点一致：只考虑不透明像素（alpha > 0），颜色按 RGB 比较，改色时保留像素原有 alpha；
+// 邻居按 wrap 解析，循环织造时跨越接缝的同色像素属于同一区域。
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use wrap::WrapMode;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+// JSON 中以数字 4 / 8 给出
+impl<'de> Deserialize<'de> for Connectivity {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        match u8::deserialize(d)? {
+            4 => Ok(Connectivity::Four),
+            8 => Ok(Connectivity::Eight),
+            n => Err(serde::de::Error::custom(format!("connectivity must be 4 or 8, got {}", n))),
+        }
+    }
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Connectivity::Eight
+    }
+}
+
+impl Connectivity {
+    // 向后（已扫描方向）的邻居偏移；逐像素扫描时每对相邻像素只被统计一次
+    fn forward_offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Connectivity::Four => &[(1, 0), (0, 1)],
+            Connectivity::Eight => &[(1, 0), (-1, 1), (0, 1), (1, 1)],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionMergeData {
+    // 面积（像素数）小于该值的区域会被合并
+    pub min_area: u32,
+    #[serde(default)]
+    pub connectivity: Connectivity,
+    #[serde(default)]
+    pub wrap: WrapMode,
+}
+
+// 透明像素的标记值
+pub const NO_REGION: u32 = u32::MAX;
+
+pub struct Region {
+    pub color: [u8; 3],
+    pub area: u32,
+}
+
+// 连通区域标记结果：labels[y * width + x] 为区域编号，透明像素为 NO_REGION
+pub struct RegionMap {
+    pub width: u32,
+    pub height: u32,
+    pub labels: Vec<u32>,
+    pub regions: Vec<Region>,
+}
+
+fn rgb_at(img: &image::RgbaImage, x: u32, y: u32) -> Option<[u8; 3]> {
+    let p = img.get_pixel(x, y);
+    if p[3] == 0 { None } else { Some([p[0], p[1], p[2]]) }
+}
+
+// 以扫描线顺序对每个未标记的不透明像素做种子填充；区域编号按首个像素的扫描顺序递增
+pub fn label_regions(img: &image::RgbaImage, connectivity: Connectivity, wrap: WrapMode) -> RegionMap {
+    let (width, height) = img.dimensions();
+    let mut labels = vec![NO_REGION; (width * height) as usize];
+    let mut regions = Vec::new();
+    let mut queue = Vec::new();
+    let neighbours: &[(i32, i32)] = match connectivity {
+        Connectivity::Four => &[(1, 0), (-1, 0), (0, 1), (0, -1)],
+        Connectivity::Eight => &[(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)],
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let idx = (y * width + x) as usize;
+            if labels[idx] != NO_REGION {
+                continue;
+            }
+            let color = match rgb_at(img, x, y) {
+                Some(c) => c,
+                None => continue,
+            };
+            let id = regions.len() as u32;
+            let mut area = 0;
+            labels[idx] = id;
+            queue.push((x, y));
+            while let Some((cx, cy)) = queue.pop() {
+                area += 1;
+                for &(dx, dy) in neighbours {
+                    let (nx, ny) = match wrap.resolve(cx as i32 + dx, cy as i32 + dy, width, height) {
+                        Some(p) => p,
+                        None => continue,
+                    };
+                    let nidx = (ny * width + nx) as usize;
+                    if labels[nidx] == NO_REGION && rgb_at(img, nx, ny) == Some(color) {
+                        labels[nidx] = id;
+                        queue.push((nx, ny));
+                    }
+                }
+            }
+            regions.push(Region { color, area });
+        }
+    }
+    RegionMap { width, height, labels, regions }
+}
+
+fn find(parent: &mut [u32], mut id: u32) -> u32 {
+    while parent[id as usize] != id {
+        let next = parent[parent[id as usize] as usize];
+        parent[id as usize] = next;
+        id = next;
+    }
+    id
+}
+
+// 将面积小于 min_area 的区域逐个并入共享边界最长的相邻区域，返回（结果图片，被合并的小区域数量）。
+// 处理顺序：面积从小到大、编号从小到大；边界长度相同时选择面积更大的邻居，再相同时选择编号更小者。
+// 合并后若与同色区域相接，则它们连成同一区域。四周只有透明像素的孤立区域保持不变。
+pub fn merge_small_regions(img: &image::RgbaImage, data: &RegionMergeData) -> (image::RgbaImage, u32) {
+    let map = label_regions(img, data.connectivity, data.wrap);
+    let (width, height) = (map.width, map.height);
+    let count = map.regions.len();
+    let mut colors: Vec<[u8; 3]> = map.regions.iter().map(|r| r.color).collect();
+    let mut areas: Vec<u32> = map.regions.iter().map(|r| r.area).collect();
+
+    // 统计相邻区域之间的边界长度（相邻像素对的数量）
+    let mut borders: Vec<HashMap<u32, u32>> = vec![HashMap::new(); count];
+    for y in 0..height {
+        for x in 0..width {
+            let a = map.labels[(y * width + x) as usize];
+            if a == NO_REGION {
+                continue;
+            }
+            for &(dx, dy) in data.connectivity.forward_offsets() {
+                let (nx, ny) = match data.wrap.resolve(x as i32 + dx, y as i32 + dy, width, height) {
+                    Some(p) => p,
+                    None => continue,
+                };
+                let b = map.labels[(ny * width + nx) as usize];
+                if b != NO_REGION && b != a {
+                    *borders[a as usize].entry(b).or_insert(0) += 1;
+                    *borders[b as usize].entry(a).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    let mut parent: Vec<u32> = (0..count as u32).collect();
+    let mut heap: BinaryHeap<Reverse<(u32, u32)>> = (0..count as u32)
+        .filter(|&id| areas[id as usize] < data.min_area)
+        .map(|id| Reverse((areas[id as usize], id)))
+        .collect();
+    let mut merged = 0;
+
+    // 将 src 的面积与边界并入 dst（二者均为根）
+    fn absorb(dst: u32, src: u32, parent: &mut [u32], areas: &mut [u32], borders: &mut [HashMap<u32, u32>]) {
+        parent[src as usize] = dst;
+        areas[dst as usize] += areas[src as usize];
+        let src_borders = std::mem::take(&mut borders[src as usize]);
+        for (n, len) in src_borders {
+            borders[n as usize].remove(&src);
+            if n == dst {
+                continue;
+            }
+            *borders[dst as usize].entry(n).or_insert(0) += len;
+            *borders[n as usize].entry(dst).or_insert(0) += len;
+        }
+        borders[dst as usize].remove(&src);
+    }
+
+    while let Some(Reverse((area, id))) = heap.pop() {
+        // 已被合并或面积已变化的条目是过期的
+        if find(&mut parent, id) != id || areas[id as usize] != area || area >= data.min_area {
+            continue;
+        }
+        let target = borders[id as usize]
+            .iter()
+            .map(|(&n, &len)| (n, len))
+            .max_by(|a, b| a.1.cmp(&b.1).then(areas[a.0 as usize].cmp(&areas[b.0 as usize])).then(b.0.cmp(&a.0)));
+        let target = match target {
+            Some((n, _)) => n,
+            None => continue,
+        };
+        absorb(target, id, &mut parent, &mut areas, &mut borders);
+        merged += 1;
+
+        // 与目标同色的相邻区域此时已连通，一并合并
+        let same_color: Vec<u32> = borders[target as usize]
+            .keys()
+            .cloned()
+            .filter(|&n| colors[n as usize] == colors[target as usize])
+            .collect();
+        for n in same_color {
+            absorb(target, n, &mut parent, &mut areas, &mut borders);
+        }
+        if areas[target as usize] < data.min_area {
+            heap.push(Reverse((areas[target as usize], target)));
+        }
+    }
+
+    // 按合并结果重新着色，保留原 alpha
+    for id in 0..count as u32 {
+        let root = find(&mut parent, id);
+        colors[id as usize] = colors[root as usize];
+    }
+    let mut out = img.clone();
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        let label = map.labels[(y * width + x) as usize];
+        if label != NO_REGION {
+            let c = colors[label as usize];
+            p[0] = c[0];
+            p[1] = c[1];
+            p[2] = c[2];
+        }
+    }
+    (out, merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: [u8; 4] = [10, 10, 10, 255];
+    const B: [u8; 4] = [200, 200, 200, 255];
+
+    const C: [u8; 4] = [90, 0, 0, 255];
+
+    // 每个字符一个像素：a / b / c 为三种颜色，. 为透明
+    fn rows(rows: &[&str]) -> image::RgbaImage {
+        image::RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            image::Rgba(match rows[y as usize].as_bytes()[x as usize] {
+                b'a' => A,
+                b'b' => B,
+                b'c' => C,
+                _ => [0, 0, 0, 0],
+            })
+        })
+    }
+
+    fn merge(img: &image::RgbaImage, min_area: u32, connectivity: Connectivity) -> (image::RgbaImage, u32) {
+        merge_small_regions(img, &RegionMergeData { min_area, connectivity, wrap: WrapMode::None })
+    }
+
+    #[test]
+    fn labels_follow_connectivity_and_scan_order() {
+        let img = rows(&["ab.", "ba.", "..a"]);
+        let four = label_regions(&img, Connectivity::Four, WrapMode::None);
+        assert_eq!(four.regions.len(), 5);
+        assert_eq!(&four.labels[..3], &[0, 1, NO_REGION]);
+        assert_eq!(four.labels[8], 4);
+        let eight = label_regions(&img, Connectivity::Eight, WrapMode::None);
+        // 对角相连的 a 连成一个区域，两个 b 也对角相连
+        assert_eq!(eight.regions.len(), 2);
+        assert_eq!((eight.regions[0].color, eight.regions[0].area), ([10, 10, 10], 3));
+        assert_eq!((eight.regions[1].color, eight.regions[1].area), ([200, 200, 200], 2));
+    }
+
+    #[test]
+    fn small_region_joins_the_neighbour_with_the_longest_border() {
+        // 中间的 c 与 a 共享 2 条边、与 b 共享 1 条边
+        let img = rows(&["aab", "acb", "aab"]);
+        let (out, merged) = merge(&img, 2, Connectivity::Four);
+        assert_eq!((out.get_pixel(1, 1).0, merged), (A, 1));
+    }
+
+    #[test]
+    fn equal_borders_prefer_the_larger_neighbour() {
+        let img = rows(&["aaacbb"]);
+        let (out, merged) = merge(&img, 2, Connectivity::Four);
+        assert_eq!((out.get_pixel(3, 0).0, merged), (A, 1));
+    }
+
+    #[test]
+    fn merging_joins_same_colored_neighbours_and_keeps_alpha() {
+        // c 并入左侧的 a 后两侧的 a 连通，面积达到阈值；右侧的 a 不再单独计为被合并的小区域
+        let mut img = rows(&["aca", "a.a"]);
+        img.put_pixel(1, 0, image::Rgba([90, 0, 0, 128]));
+        let (out, merged) = merge(&img, 3, Connectivity::Four);
+        assert_eq!(out.get_pixel(1, 0).0, [10, 10, 10, 128]);
+        assert_eq!(out.get_pixel(1, 1).0, [0, 0, 0, 0]);
+        assert_eq!(merged, 1);
+        assert_eq!(label_regions(&out, Connectivity::Four, WrapMode::None).regions.len(), 1);
+    }
+
+    #[test]
+    fn isolated_regions_are_left_alone() {
+        let img = rows(&["a.b", "..."]);
+        let (out, merged) = merge(&img, 5, Connectivity::Eight);
+        assert!(out == img);
+        assert_eq!(merged, 0);
+    }
+
+    #[test]
+    fn regions_join_across_the_seam_when_wrapping() {
+        let img = rows(&["abba", "abba"]);
+        assert_eq!(label_regions(&img, Connectivity::Four, WrapMode::None).regions.len(), 3);
+        let map = label_regions(&img, Connectivity::Four, WrapMode::Horizontal);
+        assert_eq!(map.regions.len(), 2);
+        assert_eq!(map.labels[0], map.labels[3]);
+    }
+
+    #[test]
+    fn small_regions_at_the_seam_merge_into_the_wrapped_neighbour() {
+        // 左边缘的单个 a 在循环时与右侧的 a 区域相接，面积达到阈值，不再被合并
+        let img = rows(&["bbbaa", "abbaa", "bbbaa"]);
+        let data = |wrap| RegionMergeData { min_area: 2, connectivity: Connectivity::Four, wrap };
+        let (out, merged) = merge_small_regions(&img, &data(WrapMode::None));
+        assert_eq!((out.get_pixel(0, 1).0, merged), (B, 1));
+        let (out, merged) = merge_small_regions(&img, &data(WrapMode::Horizontal));
+        assert_eq!((out.get_pixel(0, 1).0, merged), (A, 0));
+    }
+}
@@ -0,0 +1,225 @@
+// 循环（回位）预览与接缝检查：提花图案按循环织造，单元的右边接左边、下边接上边（或半落/半砖错位）。
+// 平铺预览把当前结果按循环方式排成 columns x rows 个单元；接缝检查逐行/逐列比较相接边缘的像素，
+// 报告不一致的位置，并可在预览中以红色标出。
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RepeatMode {
+    // 直接平铺
+    Straight,
+    // 半落：每隔一列向下错开半个单元高度
+    HalfDrop,
+    // 半砖：每隔一行向右错开半个单元宽度
+    HalfBrick,
+    // 镜像：奇数列左右镜像，奇数行上下镜像
+    Mirror,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RepeatData {
+    pub mode: RepeatMode,
+    #[serde(default = "default_count")]
+    pub columns: u32,
+    #[serde(default = "default_count")]
+    pub rows: u32,
+    // 在预览中以红色标出不一致的接缝像素
+    #[serde(default = "default_highlight")]
+    pub highlight_seams: bool,
+}
+
+fn default_count() -> u32 {
+    3
+}
+
+fn default_highlight() -> bool {
+    true
+}
+
+// 一条接缝的检查结果：positions 为不一致的行号（左右接缝）或列号（上下接缝），以单元内坐标计
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeamReport {
+    pub mismatched: u32,
+    pub total: u32,
+    pub positions: Vec<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeamAnalysis {
+    // 单元右边缘与右侧相邻单元左边缘
+    pub left_right: SeamReport,
+    // 单元下边缘与下方相邻单元上边缘
+    pub top_bottom: SeamReport,
+}
+
+const HIGHLIGHT: image::Rgba<u8> = image::Rgba([255, 0, 0, 255]);
+
+// 平铺预览输出的像素上限。超出时先减少单元数（保留至少 2 x 2 以便看到接缝），
+// 仍超出时按最近邻以整数倍缩小单元
+pub const MAX_PREVIEW_PIXELS: u64 = 4096 * 4096;
+
+impl RepeatData {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.columns == 0 || self.rows == 0 || self.columns > 16 || self.rows > 16 {
+            return Err("Repeat columns and rows must be within 1..=16".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 两个像素是否一致：都透明时视为一致，否则比较 RGBA
+fn same(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> bool {
+    (a[3] == 0 && b[3] == 0) || a == b
+}
+
+// 输出中第 (col, row) 个单元内的位置 (x, y) 对应的原图坐标
+fn source_pixel(mode: RepeatMode, width: u32, height: u32, col: u32, row: u32, x: u32, y: u32) -> (u32, u32) {
+    match mode {
+        RepeatMode::Straight => (x, y),
+        RepeatMode::HalfDrop => (x, (y + height - (col % 2) * (height / 2)) % height),
+        RepeatMode::HalfBrick => ((x + width - (row % 2) * (width / 2)) % width, y),
+        RepeatMode::Mirror => (
+            if col % 2 == 1 { width - 1 - x } else { x },
+            if row % 2 == 1 { height - 1 - y } else { y },
+        ),
+    }
+}
+
+// 接缝检查：左右接缝上，单元第 y 行的右边缘像素与右侧单元在同一输出行的左边缘像素相接；上下接缝同理
+pub fn analyze(img: &image::RgbaImage, mode: RepeatMode) -> SeamAnalysis {
+    let (width, height) = img.dimensions();
+    let mut left_right = SeamReport { mismatched: 0, total: height, positions: Vec::new() };
+    let mut top_bottom = SeamReport { mismatched: 0, total: width, positions: Vec::new() };
+    if width == 0 || height == 0 {
+        return SeamAnalysis { left_right, top_bottom };
+    }
+    for y in 0..height {
+        let (nx, ny) = source_pixel(mode, width, height, 1, 0, 0, y);
+        let (sx, sy) = source_pixel(mode, width, height, 0, 0, width - 1, y);
+        if !same(img.get_pixel(sx, sy), img.get_pixel(nx, ny)) {
+            left_right.mismatched += 1;
+            left_right.positions.push(y);
+        }
+    }
+    for x in 0..width {
+        let (nx, ny) = source_pixel(mode, width, height, 0, 1, x, 0);
+        let (sx, sy) = source_pixel(mode, width, height, 0, 0, x, height - 1);
+        if !same(img.get_pixel(sx, sy), img.get_pixel(nx, ny)) {
+            top_bottom.mismatched += 1;
+            top_bottom.positions.push(x);
+        }
+    }
+    SeamAnalysis { left_right, top_bottom }
+}
+
+// 在像素上限内的单元数与缩小倍数
+fn fit_budget(width: u32, height: u32, columns: u32, rows: u32) -> (u32, u32, u32) {
+    let cell = width as u64 * height as u64;
+    let (mut columns, mut rows) = (columns, rows);
+    while cell * columns as u64 * rows as u64 > MAX_PREVIEW_PIXELS && (columns > 2 || rows > 2) {
+        if columns >= rows {
+            columns -= 1;
+        } else {
+            rows -= 1;
+        }
+    }
+    let mut step = 1u32;
+    loop {
+        let scaled = ((width + step - 1) / step) as u64 * ((height + step - 1) / step) as u64;
+        if scaled * columns as u64 * rows as u64 <= MAX_PREVIEW_PIXELS {
+            return (columns, rows, step);
+        }
+        step += 1;
+    }
+}
+
+pub fn render(img: &image::RgbaImage, data: &RepeatData) -> image::RgbaImage {
+    let (columns, rows, step) = fit_budget(img.width(), img.height(), data.columns, data.rows);
+    if step == 1 {
+        return render_cells(img, data.mode, columns, rows, data.highlight_seams);
+    }
+    let cell = image::RgbaImage::from_fn((img.width() + step - 1) / step, (img.height() + step - 1) / step, |x, y| {
+        *img.get_pixel(x * step, y * step)
+    });
+    render_cells(&cell, data.mode, columns, rows, data.highlight_seams)
+}
+
+fn render_cells(img: &image::RgbaImage, mode: RepeatMode, columns: u32, rows: u32, highlight_seams: bool) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    // fit_budget 保证输出不超过 MAX_PREVIEW_PIXELS，乘法不会溢出
+    let out_width = width.checked_mul(columns).expect("repeat preview width within budget");
+    let out_height = height.checked_mul(rows).expect("repeat preview height within budget");
+    let mut out = image::RgbaImage::new(out_width, out_height);
+    for row in 0..rows {
+        for col in 0..columns {
+            for y in 0..height {
+                for x in 0..width {
+                    let (sx, sy) = source_pixel(mode, width, height, col, row, x, y);
+                    out.put_pixel(col * width + x, row * height + y, *img.get_pixel(sx, sy));
+                }
+            }
+        }
+    }
+    if !highlight_seams || width == 0 || height == 0 {
+        return out;
+    }
+    // 相邻的两个输出像素若在原图中并不相邻，说明它们跨越了接缝（半落/半砖时接缝可能在单元中间）；
+    // 跨越接缝且不一致的两侧像素标红
+    let source = |gx: u32, gy: u32| source_pixel(mode, width, height, gx / width, gy / height, gx % width, gy % height);
+    let tiled = out.clone();
+    let (out_w, out_h) = out.dimensions();
+    for gy in 0..out_h {
+        for gx in 0..out_w {
+            let (sx, sy) = source(gx, gy);
+            if gx + 1 < out_w {
+                let (nx, ny) = source(gx + 1, gy);
+                let inside = sy == ny && (sx as i64 - nx as i64).abs() == 1;
+                if !inside && !same(tiled.get_pixel(gx, gy), tiled.get_pixel(gx + 1, gy)) {
+                    out.put_pixel(gx, gy, HIGHLIGHT);
+                    out.put_pixel(gx + 1, gy, HIGHLIGHT);
+                }
+            }
+            if gy + 1 < out_h {
+                let (nx, ny) = source(gx, gy + 1);
+                let inside = sx == nx && (sy as i64 - ny as i64).abs() == 1;
+                if !inside && !same(tiled.get_pixel(gx, gy), tiled.get_pixel(gx, gy + 1)) {
+                    out.put_pixel(gx, gy, HIGHLIGHT);
+                    out.put_pixel(gx, gy + 1, HIGHLIGHT);
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_keeps_small_previews_unchanged() {
+        assert_eq!(fit_budget(400, 300, 3, 3), (3, 3, 1));
+    }
+
+    #[test]
+    fn budget_reduces_count_then_scales_large_designs() {
+        assert_eq!(fit_budget(2000, 2000, 3, 3), (2, 2, 1));
+        let (columns, rows, step) = fit_budget(20000, 15000, 16, 16);
+        assert_eq!((columns, rows), (2, 2));
+        let scaled = ((20000 + step - 1) / step) as u64 * ((15000 + step - 1) / step) as u64;
+        assert!(scaled * 4 <= MAX_PREVIEW_PIXELS);
+        assert!(step > 1);
+    }
+
+    #[test]
+    fn render_stays_within_budget() {
+        let img = image::RgbaImage::from_pixel(3000, 3000, image::Rgba([1, 2, 3, 255]));
+        let data = RepeatData { mode: RepeatMode::HalfDrop, columns: 16, rows: 16, highlight_seams: true };
+        let out = render(&img, &data);
+        assert!(out.width() as u64 * out.height() as u64 <= MAX_PREVIEW_PIXELS);
+        assert_eq!(out.dimensions(), (3000, 3000));
+    }
+}
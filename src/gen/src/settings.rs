@@ -0,0 +1,211 @@
+// 应用设置：与具体图片无关、跨启动保留的状态（最近打开的文件、最近一次阈值映射与模式、
+// 最近的导出目录与导出方式、透明像素与灰度预览的默认选项、窗口大小与缩放倍数），保存在平台配置目录下的
+// `picture-process/settings.json`（如 Linux 的 `~/.config`、Windows 的 `%APPDATA%`）。
+// 与图片相关的设计配置仍保存在工程文件中（见 project.rs）。
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use threshold::ThresholdMappingData;
+
+// 最近文件列表最多保留的条数
+pub const MAX_RECENT_FILES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    // 最近打开的图片，最近的在前
+    #[serde(default)]
+    pub recent_files: Vec<String>,
+    // 最近一次确认的阈值映射（阈值、平均/分段模式与抖动选项，含“仅不透明”的透明像素处理方式），
+    // 阈值窗口打开时以此为初值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_threshold: Option<ThresholdMappingData>,
+    // 最近一次导出所在的目录
+    #[serde(default)]
+    pub last_export_dir: String,
+    // 最近一次导出的方式：true 为灰度编码图 + 颜色表，false 为着色后的图片
+    #[serde(default)]
+    pub export_gray_codes: bool,
+    #[serde(default)]
+    pub defaults: ProcessingDefaults,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowState>,
+}
+
+// 透明像素处理与灰度预览的默认选项
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessingDefaults {
+    // 抖动误差只扩散给不透明像素；没有最近一次阈值映射时作为阈值窗口“仅不透明”的初值
+    #[serde(default = "default_true")]
+    pub opaque_only: bool,
+    // 灰度预览保留半透明像素原有的 alpha；为 false 时 alpha 非零的像素一律设为 255
+    #[serde(default)]
+    pub gray_keep_alpha: bool,
+    // 打开图片后自动生成灰度预览
+    #[serde(default)]
+    pub gray_on_open: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ProcessingDefaults {
+    fn default() -> Self {
+        ProcessingDefaults { opaque_only: true, gray_keep_alpha: false, gray_on_open: false }
+    }
+}
+
+// 主窗口大小与缩放倍数
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct WindowState {
+    pub width: u32,
+    pub height: u32,
+    pub zoom: f64,
+}
+
+impl WindowState {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("Window size must be positive, got {}x{}", self.width, self.height));
+        }
+        if !(self.zoom > 0.0) || !self.zoom.is_finite() {
+            return Err(format!("Zoom must be positive, got {}", self.zoom));
+        }
+        Ok(())
+    }
+}
+
+pub fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("picture-process").join("settings.json"))
+}
+
+impl Settings {
+    // 读取设置；不存在或解析失败时返回默认设置。已不存在的最近文件不再列出
+    pub fn load() -> Settings {
+        match settings_path() {
+            Some(path) => Settings::load_from(&path),
+            None => Settings::default(),
+        }
+    }
+
+    fn load_from(path: &Path) -> Settings {
+        let mut settings: Settings = match std::fs::read_to_string(path) {
+            Ok(text) => match serde_json::from_str(&text) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("Error parsing settings file: {}", e);
+                    Settings::default()
+                }
+            },
+            Err(_) => Settings::default(),
+        };
+        settings.recent_files.retain(|f| std::path::Path::new(f).exists());
+        settings
+    }
+
+    pub fn save(&self) {
+        match settings_path() {
+            Some(path) => self.save_to(&path),
+            None => eprintln!("No config directory available for settings"),
+        }
+    }
+
+    fn save_to(&self, path: &Path) {
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                eprintln!("Error creating settings directory: {}", e);
+                return;
+            }
+        }
+        match serde_json::to_string_pretty(self) {
+            Ok(text) => {
+                if let Err(e) = std::fs::write(path, text) {
+                    eprintln!("Error saving settings file: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Error serializing settings: {}", e),
+        }
+    }
+
+    // 将文件移到最近列表的最前面
+    pub fn add_recent_file(&mut self, path: &str) {
+        self.recent_files.retain(|f| f != path);
+        self.recent_files.insert(0, path.to_string());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试使用自己的临时目录，避免并行测试互相覆盖
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("picture-process-settings-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = temp_dir("round-trip");
+        let image = dir.join("design.png");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&image, b"").unwrap();
+        let mut settings = Settings::default();
+        settings.add_recent_file(image.to_str().unwrap());
+        settings.last_export_dir = "/tmp/exports".to_string();
+        settings.export_gray_codes = true;
+        settings.defaults = ProcessingDefaults { opaque_only: false, gray_keep_alpha: true, gray_on_open: true };
+        settings.window = Some(WindowState { width: 1200, height: 800, zoom: 1.5 });
+        let path = dir.join("nested").join("settings.json");
+        settings.save_to(&path);
+
+        let loaded = Settings::load_from(&path);
+        assert_eq!(loaded.recent_files, settings.recent_files);
+        assert_eq!(loaded.last_export_dir, "/tmp/exports");
+        assert!(loaded.export_gray_codes);
+        assert!(loaded.defaults == settings.defaults);
+        assert!(loaded.window == settings.window);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_or_corrupt_files_give_defaults() {
+        let dir = temp_dir("defaults");
+        let missing = Settings::load_from(&dir.join("settings.json"));
+        assert!(missing.recent_files.is_empty() && missing.window.is_none() && missing.last_threshold.is_none());
+        assert!(missing.defaults == ProcessingDefaults::default());
+        assert!(missing.defaults.opaque_only);
+
+        std::fs::create_dir_all(&dir).unwrap();
+        let corrupt = dir.join("corrupt.json");
+        std::fs::write(&corrupt, "{ not json").unwrap();
+        assert!(Settings::load_from(&corrupt).defaults == ProcessingDefaults::default());
+
+        // 旧版本的设置文件没有 defaults，缺少的字段取默认值；已不存在的最近文件被去掉
+        let old = dir.join("old.json");
+        std::fs::write(&old, r#"{ "recentFiles": ["/no/such/file.png"], "defaults": { "grayOnOpen": true } }"#).unwrap();
+        let loaded = Settings::load_from(&old);
+        assert!(loaded.recent_files.is_empty());
+        assert!(loaded.defaults.opaque_only && loaded.defaults.gray_on_open && !loaded.defaults.gray_keep_alpha);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recent_files_are_deduplicated_and_capped() {
+        let mut settings = Settings::default();
+        for i in 0..MAX_RECENT_FILES + 3 {
+            settings.add_recent_file(&format!("/images/{}.png", i));
+        }
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+        assert_eq!(settings.recent_files[0], format!("/images/{}.png", MAX_RECENT_FILES + 2));
+        assert!(!settings.recent_files.contains(&"/images/0.png".to_string()));
+
+        settings.add_recent_file("/images/5.png");
+        assert_eq!(settings.recent_files[0], "/images/5.png");
+        assert_eq!(settings.recent_files.iter().filter(|f| *f == "/images/5.png").count(), 1);
+        assert_eq!(settings.recent_files.len(), MAX_RECENT_FILES);
+    }
+}
@@ -0,0 +1,66 @@
+// 颜色统计：列出处理结果中出现的每种颜色的像素数、占不透明面积的百分比、连通区域数（8 邻接）与外接矩形。
+// 与连通区域一致：只统计不透明像素，颜色按 RGB 比较。
+use regions::{self, Connectivity, NO_REGION};
+use std::collections::HashMap;
+use wrap::WrapMode;
+
+pub struct ColorStats {
+    pub color: [u8; 3],
+    pub pixels: u64,
+    pub percentage: f64,
+    pub regions: u32,
+    // 外接矩形：左上角与宽高
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// 按像素数从多到少排序，相同时按颜色排序，保证顺序稳定
+pub fn color_stats(img: &image::RgbaImage) -> Vec<ColorStats> {
+    let map = regions::label_regions(img, Connectivity::Eight, WrapMode::None);
+    let mut index: HashMap<[u8; 3], usize> = HashMap::new();
+    let mut stats: Vec<ColorStats> = Vec::new();
+    // 每种颜色的外接矩形右下角（含）
+    let mut max: Vec<(u32, u32)> = Vec::new();
+    for region in &map.regions {
+        let i = *index.entry(region.color).or_insert_with(|| {
+            stats.push(ColorStats {
+                color: region.color,
+                pixels: 0,
+                percentage: 0.0,
+                regions: 0,
+                x: u32::MAX,
+                y: u32::MAX,
+                width: 0,
+                height: 0,
+            });
+            max.push((0, 0));
+            stats.len() - 1
+        });
+        stats[i].pixels += region.area as u64;
+        stats[i].regions += 1;
+    }
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let label = map.labels[(y * map.width + x) as usize];
+            if label == NO_REGION {
+                continue;
+            }
+            let i = index[&map.regions[label as usize].color];
+            let s = &mut stats[i];
+            s.x = s.x.min(x);
+            s.y = s.y.min(y);
+            max[i].0 = max[i].0.max(x);
+            max[i].1 = max[i].1.max(y);
+        }
+    }
+    let total: u64 = stats.iter().map(|s| s.pixels).sum();
+    for (s, &(mx, my)) in stats.iter_mut().zip(max.iter()) {
+        s.width = mx - s.x + 1;
+        s.height = my - s.y + 1;
+        s.percentage = s.pixels as f64 * 100.0 / total as f64;
+    }
+    stats.sort_by(|a, b| b.pixels.cmp(&a.pixels).then(a.color.cmp(&b.color)));
+    stats
+}
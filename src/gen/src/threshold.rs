@@ -0,0 +1,258 @@
+// 阈值映射：按 stops 将亮度划分为若干段（level），并计算每段的输出颜色。
+// 供 apply_threshold_mapping 与颜色映射共用，保证两者的分段结果完全一致。
+use serde::{Deserialize, Serialize};
+use dither::{self, DitherOptions};
+use rayon::prelude::*;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThresholdMappingData {
+    pub stops: Vec<u8>,
+    #[serde(rename = "averageMode")]
+    pub average_mode: bool,
+    // 可选：逐段指定输出值，长度必须为 stops.len() + 1；给定时忽略 averageMode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<SegmentOutput>>,
+    // 可选：抖动方式，默认不抖动（硬阈值）
+    #[serde(default, skip_serializing_if = "DitherOptions::is_none")]
+    pub dither: DitherOptions,
+}
+
+// 单段输出：灰度值（如 128）或 RGBA 颜色（如 [255, 0, 0] / [255, 0, 0, 255]）
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(untagged)]
+pub enum SegmentOutput {
+    Gray(u8),
+    Rgb([u8; 3]),
+    Rgba([u8; 4]),
+}
+
+impl SegmentOutput {
+    pub fn to_rgba(self) -> [u8; 4] {
+        match self {
+            SegmentOutput::Gray(v) => [v, v, v, 255],
+            SegmentOutput::Rgb([r, g, b]) => [r, g, b, 255],
+            SegmentOutput::Rgba(c) => c,
+        }
+    }
+}
+
+impl ThresholdMappingData {
+    // 段数 = 阈值数 + 1
+    pub fn level_count(&self) -> usize {
+        self.stops.len() + 1
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.stops.is_empty() {
+            return Err("No thresholds provided".to_string());
+        }
+        // 段索引与逐段输出都依赖阈值的顺序，重复的阈值会产生空段
+        if let Some(pair) = self.stops.windows(2).find(|pair| pair[0] >= pair[1]) {
+            return Err(format!("Thresholds must be strictly ascending, got {} before {}", pair[0], pair[1]));
+        }
+        // 逐段输出必须与段数一一对应
+        if let Some(outputs) = &self.outputs {
+            if outputs.len() != self.level_count() {
+                return Err(format!("Expected {} segment outputs for {} thresholds, got {}", self.level_count(), self.stops.len(), outputs.len()));
+            }
+        }
+        self.dither.validate()
+    }
+
+    // 按本映射（含抖动选项）将图片分段，并用 colors[段索引] 着色
+    pub fn render(&self, img: &image::RgbaImage, colors: &[[u8; 4]]) -> image::RgbaImage {
+        dither::dither_levels(img, &self.stops, colors, &self.dither)
+    }
+
+    // 预先计算每段的输出颜色
+    pub fn segment_colors(&self) -> Vec<[u8; 4]> {
+        (0..self.level_count())
+            .map(|i| match &self.outputs {
+                Some(outputs) => outputs[i].to_rgba(),
+                None => {
+                    let v = default_segment_gray(&self.stops, i, self.average_mode);
+                    [v, v, v, 255] // alpha修改：alpha不为0的像素alpha设为255
+                }
+            })
+            .collect()
+    }
+}
+
+// ITU-R BT.601 近似加权，整数计算避免浮点（与灰色预览一致）
+pub fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+}
+
+// 灰色预览：将 RGB 设置为相同亮度值，alpha 为 0 的像素设为 (0,0,0,0)，其余 alpha 设为 255
+pub fn gray_image(rgba: &mut image::RgbaImage) {
+    for p in rgba.pixels_mut() {
+        let r = p[0] as u32;
+        let g = p[1] as u32;
+        let b = p[2] as u32;
+        let a = p[3];
+        
+        // ITU-R BT.601 近似加权，整数计算避免浮点
+        let luma = ((299 * r + 587 * g + 114 * b) / 1000) as u8;
+        
+        // 新的alpha处理逻辑：
+        // - alpha为0的像素设为(0,0,0,0)
+        // - alpha不为0的像素alpha设为255
+        if a == 0 {
+            p[0] = 0;
+            p[1] = 0;
+            p[2] = 0;
+            p[3] = 0;
+        } else {
+            p[0] = luma;
+            p[1] = luma;
+            p[2] = luma;
+            p[3] = 255;
+        }
+    }
+}
+
+// 计算灰度值所属的段索引：第一个满足 gray <= stop 的位置，超过所有阈值时为 stops.len()
+pub fn segment_index(stops: &[u8], gray: u8) -> usize {
+    stops.iter().position(|&s| gray <= s).unwrap_or(stops.len())
+}
+
+// 按映射模式计算某段的默认输出灰度
+pub fn default_segment_gray(stops: &[u8], index: usize, is_average_mode: bool) -> u8 {
+    if is_average_mode {
+        // 平均模式：该段上下界的中点，最后一段的上界为 255
+        let lower = if index == 0 { 0 } else { stops[index - 1] };
+        let upper = if index < stops.len() { stops[index] } else { 255 };
+        ((lower as u16 + upper as u16) / 2) as u8
+    } else {
+        // 分段模式：均匀分布，最亮段为255，最暗段为0
+        let segment_count = stops.len() + 1; // 段数 = 阈值数 + 1
+        if segment_count == 1 {
+            128 // 只有一段时使用中值
+        } else if index == 0 {
+            0 // 最暗段映射为纯黑色
+        } else if index == segment_count - 1 {
+            255 // 最亮段映射为纯白色
+        } else {
+            // 中间段均匀分布
+            ((255 * index) / (segment_count - 1)) as u8
+        }
+    }
+}
+
+// 亮度 0..=255 到输出颜色的查找表：lut[g] = colors[segment_index(stops, g)]
+pub fn color_lut(stops: &[u8], colors: &[[u8; 4]]) -> [[u8; 4]; 256] {
+    let mut lut = [[0; 4]; 256];
+    for (g, c) in lut.iter_mut().enumerate() {
+        *c = colors[segment_index(stops, g as u8)];
+    }
+    lut
+}
+
+// 将图片按 stops 分段，并用 colors[段索引] 着色；透明像素保持为(0,0,0,0)。
+// 分段只取决于 8 位亮度，先算好查找表，再按行并行处理
+pub fn map_levels(img: &image::RgbaImage, stops: &[u8], colors: &[[u8; 4]]) -> image::RgbaImage {
+    let (width, height) = img.dimensions();
+    let lut = color_lut(stops, colors);
+    let mut mapped_img = image::RgbaImage::new(width, height);
+    let row_len = (width * 4) as usize;
+    if row_len == 0 {
+        return mapped_img;
+    }
+    mapped_img
+        .par_chunks_mut(row_len)
+        .zip(img.as_raw().par_chunks(row_len))
+        .for_each(|(out_row, in_row)| {
+            for (out, p) in out_row.chunks_exact_mut(4).zip(in_row.chunks_exact(4)) {
+                if p[3] != 0 {
+                    out.copy_from_slice(&lut[luma(p[0], p[1], p[2]) as usize]);
+                }
+            }
+        });
+    mapped_img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 查找表之前的逐像素实现，作为对照
+    fn reference_map_levels(img: &image::RgbaImage, stops: &[u8], colors: &[[u8; 4]]) -> image::RgbaImage {
+        let (width, height) = img.dimensions();
+        let mut mapped_img = image::RgbaImage::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = img.get_pixel(x, y);
+                let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+                let mapped = if a == 0 { [0, 0, 0, 0] } else { colors[segment_index(stops, luma(r, g, b))] };
+                mapped_img.put_pixel(x, y, image::Rgba(mapped));
+            }
+        }
+        mapped_img
+    }
+
+    // 伪随机图片：约八分之一的像素透明（RGB 不为 0），其余 alpha 各异
+    fn sample_image(width: u32, height: u32) -> image::RgbaImage {
+        let mut seed: u32 = 12345;
+        image::RgbaImage::from_fn(width, height, |_, _| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let v = seed.to_be_bytes();
+            let alpha = if v[3] % 8 == 0 { 0 } else { v[3] | 1 };
+            image::Rgba([v[0], v[1], v[2], alpha])
+        })
+    }
+
+    fn mapping(stops: Vec<u8>, average_mode: bool, outputs: Option<Vec<SegmentOutput>>) -> ThresholdMappingData {
+        ThresholdMappingData { stops, average_mode, outputs, dither: DitherOptions::default() }
+    }
+
+    fn assert_identical(data: &ThresholdMappingData) {
+        // 奇数宽度，检验按行分块的边界
+        let img = sample_image(257, 61);
+        let colors = data.segment_colors();
+        let expected = reference_map_levels(&img, &data.stops, &colors);
+        assert!(map_levels(&img, &data.stops, &colors) == expected);
+        assert!(data.render(&img, &colors) == expected);
+    }
+
+    #[test]
+    fn average_mode_matches_reference() {
+        assert_identical(&mapping(vec![64, 128, 192], true, None));
+        assert_identical(&mapping(vec![0, 255], true, None));
+    }
+
+    #[test]
+    fn segmented_mode_matches_reference() {
+        assert_identical(&mapping(vec![30, 31, 100, 200, 254], false, None));
+        assert_identical(&mapping(vec![128], false, None));
+    }
+
+    #[test]
+    fn outputs_override_matches_reference() {
+        let outputs = vec![SegmentOutput::Gray(7), SegmentOutput::Rgb([255, 0, 0]), SegmentOutput::Rgba([0, 0, 255, 128])];
+        assert_identical(&mapping(vec![90, 170], true, Some(outputs)));
+    }
+
+    #[test]
+    fn transparent_pixels_become_zero() {
+        let img = sample_image(33, 9);
+        let data = mapping(vec![100], true, None);
+        let mapped = map_levels(&img, &data.stops, &data.segment_colors());
+        for (p, q) in img.pixels().zip(mapped.pixels()) {
+            if p[3] == 0 {
+                assert_eq!(q.0, [0, 0, 0, 0]);
+            } else {
+                assert_eq!(q[3], 255);
+            }
+        }
+    }
+
+    #[test]
+    fn stops_must_be_strictly_ascending() {
+        let mapping = |stops: Vec<u8>| ThresholdMappingData { stops, average_mode: true, outputs: None, dither: DitherOptions::default() };
+        assert!(mapping(vec![40, 128, 200]).validate().is_ok());
+        assert!(mapping(vec![128]).validate().is_ok());
+        assert!(mapping(vec![128, 40]).validate().is_err());
+        assert!(mapping(vec![40, 128, 128]).validate().is_err());
+        assert!(mapping(Vec::new()).validate().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// 色调调整：在阈值映射之前拉开扫描图的亮度分布。
+// 对与灰色预览相同的亮度值（BT.601 整数加权）依次应用：
+// 输入黑/白场 -> gamma -> 亮度/对比度 -> 自定义曲线，全部预先编译成一张 256 项查找表。
+// 输出为灰度图，透明规则同灰色预览（alpha 为 0 的像素设为 (0,0,0,0)，其余 alpha 设为 255）。
+use serde::Deserialize;
+use threshold::luma;
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ToneData {
+    // 输入黑场/白场：不高于黑场的亮度映射为 0，不低于白场的映射为 255
+    #[serde(default)]
+    pub black_point: u8,
+    #[serde(default = "default_white_point")]
+    pub white_point: u8,
+    // gamma > 1 提亮中间调，< 1 压暗中间调
+    #[serde(default = "default_gamma")]
+    pub gamma: f64,
+    // 亮度偏移，范围 -255..=255
+    #[serde(default)]
+    pub brightness: i32,
+    // 对比度百分比，范围 -100..=100，以 128 为中心拉伸或压缩
+    #[serde(default)]
+    pub contrast: f64,
+    // 曲线控制点 [输入, 输出]，按输入排序后做单调三次插值；为空时不调整
+    #[serde(default)]
+    pub curve: Vec<[u8; 2]>,
+}
+
+fn default_white_point() -> u8 {
+    255
+}
+
+fn default_gamma() -> f64 {
+    1.0
+}
+
+impl ToneData {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.black_point >= self.white_point {
+            return Err("Tone black point must be lower than the white point".to_string());
+        }
+        if !(self.gamma > 0.0) {
+            return Err("Tone gamma must be greater than 0".to_string());
+        }
+        if self.brightness < -255 || self.brightness > 255 {
+            return Err("Tone brightness must be within -255..=255".to_string());
+        }
+        if !(self.contrast >= -100.0 && self.contrast <= 100.0) {
+            return Err("Tone contrast must be within -100..=100".to_string());
+        }
+        if self.curve.len() == 1 {
+            return Err("Tone curve needs at least two control points".to_string());
+        }
+        let mut inputs: Vec<u8> = self.curve.iter().map(|p| p[0]).collect();
+        inputs.sort();
+        if inputs.windows(2).any(|w| w[0] == w[1]) {
+            return Err("Tone curve control points must have distinct inputs".to_string());
+        }
+        Ok(())
+    }
+
+    // 编译查找表：lut[输入亮度] = 输出亮度
+    pub fn lut(&self) -> [u8; 256] {
+        let curve = curve_lut(&self.curve);
+        let black = self.black_point as f64;
+        let range = self.white_point as f64 - black;
+        // 对比度系数：-100 时压成全灰，0 不变，100 时斜率为 4
+        let contrast = ((100.0 + self.contrast) / 100.0).powi(2);
+        let mut lut = [0u8; 256];
+        for (v, out) in lut.iter_mut().enumerate() {
+            let level = ((v as f64 - black) / range).max(0.0).min(1.0);
+            let mut x = 255.0 * level.powf(1.0 / self.gamma);
+            x = (x - 128.0) * contrast + 128.0 + self.brightness as f64;
+            let x = x.round().max(0.0).min(255.0) as u8;
+            *out = curve[x as usize];
+        }
+        lut
+    }
+}
+
+// 曲线查找表：控制点之间用 Fritsch–Carlson 单调三次插值，首尾之外保持端点输出
+fn curve_lut(points: &[[u8; 2]]) -> [u8; 256] {
+    let mut lut = [0u8; 256];
+    if points.len() < 2 {
+        for (v, out) in lut.iter_mut().enumerate() {
+            *out = v as u8;
+        }
+        return lut;
+    }
+    let mut points = points.to_vec();
+    points.sort_by_key(|p| p[0]);
+    let xs: Vec<f64> = points.iter().map(|p| p[0] as f64).collect();
+    let ys: Vec<f64> = points.iter().map(|p| p[1] as f64).collect();
+    let n = xs.len();
+    let slopes: Vec<f64> = (0..n - 1).map(|i| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i])).collect();
+    let mut tangents = vec![0.0; n];
+    tangents[0] = slopes[0];
+    tangents[n - 1] = slopes[n - 2];
+    for i in 1..n - 1 {
+        tangents[i] = if slopes[i - 1] * slopes[i] <= 0.0 { 0.0 } else { (slopes[i - 1] + slopes[i]) / 2.0 };
+    }
+    // 限制切线，保证相邻控制点之间单调、不过冲
+    for i in 0..n - 1 {
+        if slopes[i] == 0.0 {
+            tangents[i] = 0.0;
+            tangents[i + 1] = 0.0;
+            continue;
+        }
+        let a = tangents[i] / slopes[i];
+        let b = tangents[i + 1] / slopes[i];
+        let s = a * a + b * b;
+        if s > 9.0 {
+            let t = 3.0 / s.sqrt();
+            tangents[i] = t * a * slopes[i];
+            tangents[i + 1] = t * b * slopes[i];
+        }
+    }
+    for (v, out) in lut.iter_mut().enumerate() {
+        let x = v as f64;
+        let y = if x <= xs[0] {
+            ys[0]
+        } else if x >= xs[n - 1] {
+            ys[n - 1]
+        } else {
+            let i = xs.windows(2).position(|w| x < w[1]).unwrap_or(n - 2);
+            let h = xs[i + 1] - xs[i];
+            let t = (x - xs[i]) / h;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * ys[i]
+                + (t3 - 2.0 * t2 + t) * h * tangents[i]
+                + (-2.0 * t3 + 3.0 * t2) * ys[i + 1]
+                + (t3 - t2) * h * tangents[i + 1]
+        };
+        *out = y.round().max(0.0).min(255.0) as u8;
+    }
+    lut
+}
+
+pub fn apply(img: &image::RgbaImage, data: &ToneData) -> image::RgbaImage {
+    let lut = data.lut();
+    let mut out = img.clone();
+    for p in out.pixels_mut() {
+        if p[3] == 0 {
+            *p = image::Rgba([0, 0, 0, 0]);
+        } else {
+            let v = lut[luma(p[0], p[1], p[2]) as usize];
+            *p = image::Rgba([v, v, v, 255]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone() -> ToneData {
+        ToneData { black_point: 0, white_point: 255, gamma: 1.0, brightness: 0, contrast: 0.0, curve: Vec::new() }
+    }
+
+    fn identity() -> Vec<u8> {
+        (0..=255).collect()
+    }
+
+    #[test]
+    fn default_settings_and_straight_curves_are_identity() {
+        assert_eq!(tone().lut().to_vec(), identity());
+        let straight = ToneData { curve: vec![[0, 0], [255, 255]], ..tone() };
+        assert_eq!(straight.lut().to_vec(), identity());
+        let collinear = ToneData { curve: vec![[255, 255], [0, 0], [100, 100]], ..tone() };
+        assert_eq!(collinear.lut().to_vec(), identity());
+    }
+
+    #[test]
+    fn outputs_are_clamped_to_the_byte_range() {
+        assert!(ToneData { brightness: 255, ..tone() }.lut().iter().all(|&v| v == 255));
+        assert!(ToneData { brightness: -255, ..tone() }.lut().iter().all(|&v| v == 0));
+        let contrast = ToneData { contrast: 100.0, ..tone() }.lut();
+        assert_eq!((contrast[0], contrast[40], contrast[128], contrast[220], contrast[255]), (0, 0, 128, 255, 255));
+        let points = ToneData { black_point: 50, white_point: 200, ..tone() }.lut();
+        assert!(points[..=50].iter().all(|&v| v == 0));
+        assert!(points[200..].iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn curve_passes_through_points_without_overshoot() {
+        let points = vec![[0, 0], [64, 200], [128, 210], [255, 255]];
+        let lut = ToneData { curve: points.clone(), ..tone() }.lut();
+        for p in &points {
+            assert_eq!(lut[p[0] as usize], p[1]);
+        }
+        assert!(lut.windows(2).all(|w| w[0] <= w[1]));
+        // 首尾控制点之外保持端点输出
+        let inner = ToneData { curve: vec![[50, 20], [200, 230]], ..tone() }.lut();
+        assert_eq!((inner[0], inner[50], inner[200], inner[255]), (20, 20, 230, 230));
+    }
+
+    #[test]
+    fn alpha_follows_the_gray_preview_rule() {
+        let mut img = image::RgbaImage::from_pixel(3, 1, image::Rgba([100, 100, 100, 255]));
+        img.put_pixel(1, 0, image::Rgba([30, 60, 90, 0]));
+        img.put_pixel(2, 0, image::Rgba([100, 100, 100, 255]));
+        let out = apply(&img, &ToneData { brightness: 20, ..tone() });
+        assert_eq!(out.get_pixel(0, 0).0, [120, 120, 120, 255]);
+        assert_eq!(out.get_pixel(1, 0).0, [0, 0, 0, 0]);
+        assert_eq!(out.get_pixel(2, 0).0[3], 255);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        assert!(ToneData { black_point: 200, white_point: 200, ..tone() }.validate().is_err());
+        assert!(ToneData { gamma: 0.0, ..tone() }.validate().is_err());
+        assert!(ToneData { curve: vec![[10, 10]], ..tone() }.validate().is_err());
+        assert!(ToneData { curve: vec![[10, 10], [10, 40]], ..tone() }.validate().is_err());
+    }
+}
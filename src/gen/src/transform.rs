@@ -0,0 +1,170 @@
+// 几何变换：裁剪、无损旋转 90/180/270 度、水平/垂直翻转，以及裁掉透明边框。
+// 这些变换只搬移像素、不改变颜色；除翻转外都会改变图片尺寸，因此选区对它们不起作用。
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Copy)]
+pub enum Rotation {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+// JSON 中以顺时针角度 90 / 180 / 270 给出
+impl<'de> Deserialize<'de> for Rotation {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        match u32::deserialize(d)? {
+            90 => Ok(Rotation::Rotate90),
+            180 => Ok(Rotation::Rotate180),
+            270 => Ok(Rotation::Rotate270),
+            n => Err(serde::de::Error::custom(format!("rotation must be 90, 180 or 270 degrees, got {}", n))),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct RotateData {
+    pub degrees: Rotation,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum FlipDirection {
+    // 左右镜像
+    Horizontal,
+    // 上下镜像
+    Vertical,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct FlipData {
+    pub direction: FlipDirection,
+}
+
+impl CropRect {
+    pub fn validate(&self, width: u32, height: u32) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err("Crop rectangle must not be empty".to_string());
+        }
+        if self.x.saturating_add(self.width) > width || self.y.saturating_add(self.height) > height {
+            return Err(format!(
+                "Crop rectangle {}x{}+{}+{} exceeds the image size {}x{}",
+                self.width, self.height, self.x, self.y, width, height
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn crop(img: &image::RgbaImage, rect: &CropRect) -> Result<image::RgbaImage, String> {
+    let (width, height) = img.dimensions();
+    rect.validate(width, height)?;
+    Ok(image::imageops::crop_imm(img, rect.x, rect.y, rect.width, rect.height).to_image())
+}
+
+pub fn rotate(img: &image::RgbaImage, rotation: Rotation) -> image::RgbaImage {
+    match rotation {
+        Rotation::Rotate90 => image::imageops::rotate90(img),
+        Rotation::Rotate180 => image::imageops::rotate180(img),
+        Rotation::Rotate270 => image::imageops::rotate270(img),
+    }
+}
+
+pub fn flip(img: &image::RgbaImage, direction: FlipDirection) -> image::RgbaImage {
+    match direction {
+        FlipDirection::Horizontal => image::imageops::flip_horizontal(img),
+        FlipDirection::Vertical => image::imageops::flip_vertical(img),
+    }
+}
+
+// 不透明像素（alpha > 0）的外接矩形；整张图都透明时返回 None
+pub fn opaque_bounds(img: &image::RgbaImage) -> Option<CropRect> {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] == 0 {
+            continue;
+        }
+        bounds = Some(match bounds {
+            None => (x, y, x, y),
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+        });
+    }
+    bounds.map(|(x0, y0, x1, y1)| CropRect { x: x0, y: y0, width: x1 - x0 + 1, height: y1 - y0 + 1 })
+}
+
+// 裁掉四周完全透明的边框；整张图都透明时保持不变
+pub fn trim(img: &image::RgbaImage) -> image::RgbaImage {
+    match opaque_bounds(img) {
+        Some(rect) => image::imageops::crop_imm(img, rect.x, rect.y, rect.width, rect.height).to_image(),
+        None => img.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3x2 的图片，每个像素颜色都不同，便于检查像素搬移的位置
+    fn sample() -> image::RgbaImage {
+        image::RgbaImage::from_fn(3, 2, |x, y| image::Rgba([x as u8, y as u8, 0, 255]))
+    }
+
+    #[test]
+    fn quarter_rotations_swap_dimensions() {
+        let img = sample();
+        let cw = rotate(&img, Rotation::Rotate90);
+        assert_eq!(cw.dimensions(), (2, 3));
+        // 顺时针旋转后，原左下角到了左上角
+        assert_eq!(cw.get_pixel(0, 0).0, [0, 1, 0, 255]);
+        let ccw = rotate(&img, Rotation::Rotate270);
+        assert_eq!(ccw.dimensions(), (2, 3));
+        assert_eq!(ccw.get_pixel(0, 0).0, [2, 0, 0, 255]);
+        let half = rotate(&img, Rotation::Rotate180);
+        assert_eq!(half.dimensions(), (3, 2));
+        assert_eq!(half.get_pixel(0, 0).0, [2, 1, 0, 255]);
+    }
+
+    #[test]
+    fn flips_mirror_pixels() {
+        let img = sample();
+        assert_eq!(flip(&img, FlipDirection::Horizontal).get_pixel(0, 1).0, [2, 1, 0, 255]);
+        assert_eq!(flip(&img, FlipDirection::Vertical).get_pixel(0, 0).0, [0, 1, 0, 255]);
+    }
+
+    #[test]
+    fn crop_must_stay_inside_the_image() {
+        let img = sample();
+        let inside = crop(&img, &CropRect { x: 1, y: 1, width: 2, height: 1 }).unwrap();
+        assert_eq!(inside.dimensions(), (2, 1));
+        assert_eq!(inside.get_pixel(0, 0).0, [1, 1, 0, 255]);
+        assert!(crop(&img, &CropRect { x: 2, y: 0, width: 2, height: 1 }).is_err());
+        assert!(crop(&img, &CropRect { x: 0, y: 1, width: 1, height: 2 }).is_err());
+        assert!(crop(&img, &CropRect { x: u32::MAX, y: 0, width: 1, height: 1 }).is_err());
+        assert!(crop(&img, &CropRect { x: 0, y: 0, width: 0, height: 1 }).is_err());
+    }
+
+    #[test]
+    fn trim_removes_transparent_borders() {
+        let mut img = image::RgbaImage::new(5, 4);
+        img.put_pixel(1, 1, image::Rgba([10, 20, 30, 255]));
+        img.put_pixel(3, 2, image::Rgba([40, 50, 60, 1]));
+        let trimmed = trim(&img);
+        assert_eq!(trimmed.dimensions(), (3, 2));
+        assert_eq!(trimmed.get_pixel(0, 0).0, [10, 20, 30, 255]);
+        assert_eq!(trimmed.get_pixel(2, 1).0, [40, 50, 60, 1]);
+    }
+
+    #[test]
+    fn trim_keeps_fully_transparent_image() {
+        let img = image::RgbaImage::from_pixel(4, 3, image::Rgba([1, 2, 3, 0]));
+        assert!(opaque_bounds(&img).is_none());
+        assert_eq!(trim(&img), img);
+    }
+}
@@ -9,13 +9,88 @@
 use crate::viewer_interface::*;
 use notify::Watcher;
 use serde::{Deserialize, Serialize};
-use std::cell::{RefCell, UnsafeCell};
+use std::cell::{Cell, RefCell, UnsafeCell};
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::SystemTime;
+use threshold::{self, ThresholdMappingData};
+use project::{self, LevelColor, Project};
+use quantize::{self, QuantizeData};
+use regions::{self, RegionMergeData};
+use cleanup::{self, ModeFilterData};
+use pipeline::{self, Step};
+use mask::{Mask, MaskData};
+use tone::{self, ToneData};
+use denoise::{self, DenoiseData};
+use repeat::{self, RepeatData};
+use wrap::WrapMode;
+use recolor::{self, MergeLevelData, ReplaceColorData};
+use edit::EditData;
+use inspect;
+use stats;
+use preview::Proxy;
+use compare::{self, CompareData, CompareSource};
+use isolate::{self, IsolateData};
+use grid::{self, Density, GridRequest};
+use pyramid::{self, Pyramid, TileInfo, TileLayout, TileViewport};
+use settings::{ProcessingDefaults, Settings, WindowState};
 
-#[derive(Deserialize)]
-struct ThresholdMappingData {
-    stops: Vec<u8>,
-    #[serde(rename = "averageMode")]
-    average_mode: bool,
+// 像素编辑会话：以开始编辑时的处理结果为底图，记录编辑步骤以便撤销/重做。
+// 只有当待保存的结果仍是本会话的临时文件时会话才有效，其他处理操作会使其失效
+struct EditSession {
+    temp_path: String,
+    base: image::RgbaImage,
+    current: image::RgbaImage,
+    steps: Vec<Step>,
+    redo: Vec<Step>,
+}
+
+// 阈值预览的代理图，按原图路径与修改时间判断是否过期；选区改变时丢弃
+struct PreviewProxy {
+    path: String,
+    modified: Option<SystemTime>,
+    proxy: Proxy,
+}
+
+// 超大图片的分块金字塔，按显示图片路径与修改时间判断是否过期；
+// written 记录已写入分块目录的分块，generation 写入分块文件名，避免 QML 沿用过期分块的缓存
+struct TilePyramid {
+    path: String,
+    modified: Option<SystemTime>,
+    generation: u32,
+    pyramid: Pyramid,
+    written: HashSet<(usize, u32, u32)>,
+}
+
+// 应用设置对应的属性值（JSON 文本），属性 getter 返回其引用；设置变化后只对变化的属性发出信号
+#[derive(Clone, PartialEq)]
+struct SettingsView {
+    recent_files: String,
+    last_threshold: String,
+    last_export_dir: String,
+    export_gray_codes: bool,
+    defaults: String,
+    window_state: String,
+}
+
+impl SettingsView {
+    fn new(settings: &Settings) -> SettingsView {
+        SettingsView {
+            recent_files: serde_json::to_string(&settings.recent_files).unwrap_or_default(),
+            last_threshold: settings.last_threshold.as_ref().and_then(|t| serde_json::to_string(t).ok()).unwrap_or_default(),
+            last_export_dir: settings.last_export_dir.clone(),
+            export_gray_codes: settings.export_gray_codes,
+            defaults: serde_json::to_string(&settings.defaults).unwrap_or_default(),
+            window_state: settings.window.as_ref().and_then(|w| serde_json::to_string(w).ok()).unwrap_or_default(),
+        }
+    }
+}
+
+// 解码后的图片缓存（像素检查时避免反复读取文件），按路径与修改时间判断是否过期
+struct CachedImage {
+    path: String,
+    modified: Option<SystemTime>,
+    image: Rc<image::RgbaImage>,
 }
 
 pub struct ImageViewer {
@@ -25,19 +100,271 @@ pub struct ImageViewer {
     image_path: UnsafeCell<String>,
     display_path: UnsafeCell<String>,
     pending_path: UnsafeCell<Option<String>>,
+    // 上一步处理结果的路径（对比“上一步”时使用），没有时为 None
+    previous_step: UnsafeCell<Option<String>>,
     // watcher 放在 RefCell 中，避免与字符串借用相互影响
     watcher: RefCell<Option<notify::RecommendedWatcher>>,
+    // 当前图片对应的工程配置（阈值映射、颜色表），随 image_path 加载
+    project: RefCell<Project>,
+    // 选区预览叠加层图片路径（无选区时为空）
+    mask_overlay_path: UnsafeCell<String>,
+    edit_session: RefCell<Option<EditSession>>,
+    image_cache: RefCell<Vec<CachedImage>>,
+    // 阈值实时预览图片路径（未在预览时为空）
+    preview_path: UnsafeCell<String>,
+    preview_proxy: RefCell<Option<PreviewProxy>>,
+    tile_pyramid: RefCell<Option<TilePyramid>>,
+    tile_generation: Cell<u32>,
+    // 应用设置（跨启动保留，与具体图片无关）
+    settings: RefCell<Settings>,
+    settings_view: UnsafeCell<SettingsView>,
+}
+
+impl ImageViewer {
+    // 根据工程中的选区构建蒙版；颜色/段选区以 original（原图）为准
+    fn selection_mask(&self, original: &image::RgbaImage) -> Option<Mask> {
+        let project = self.project.borrow();
+        let data = project.mask.as_ref()?;
+        match Mask::build(data, original, project.threshold.as_ref()) {
+            Ok(mask) => Some(mask),
+            Err(e) => {
+                eprintln!("Error building selection mask: {}", e);
+                None
+            }
+        }
+    }
+    // 将 result 中选区外的像素恢复为 original；没有选区时不做处理
+    fn restore_unselected(&self, original: &image::RgbaImage, result: &mut image::RgbaImage) {
+        if let Some(mask) = self.selection_mask(original) {
+            mask.restore_unselected(original, result);
+        }
+    }
+    // 当前处理结果（待保存的临时文件）的路径，没有时为原图
+    fn current_result_path(&self) -> String {
+        unsafe { (*self.pending_path.get()).clone().unwrap_or_else(|| (*self.image_path.get()).clone()) }
+    }
+    // 映射之后的改色：作用于当前处理结果，选区外保持不变；保存为 .recolor.tmp.png，返回受影响的像素数
+    fn recolor_current<F>(&self, recolor_fn: F) -> u32
+    where
+        F: Fn(&image::RgbaImage) -> Result<image::RgbaImage, String>,
+    {
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for recolor");
+            return 0;
+        }
+        let img = match image::open(self.current_result_path()) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading image for recolor: {}", e);
+                return 0;
+            }
+        };
+        let mut recolored_img = match recolor_fn(&img) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 0;
+            }
+        };
+        // 选区按原图构建
+        if let Ok(original) = image::open(&original_path) {
+            if let Some(mask) = self.selection_mask(&original.to_rgba8()) {
+                mask.restore_unselected(&img, &mut recolored_img);
+            }
+        }
+        let affected = recolor::changed_pixels(&img, &recolored_img);
+        
+        let temp_path = format!("{}{}", original_path, ".recolor.tmp.png");
+        if let Err(e) = self.save_step(&recolored_img, &temp_path) {
+            eprintln!("Error saving recolored image: {}", e);
+            return 0;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+        affected
+    }
+    // 按原图构建选区蒙版（用于作用在当前处理结果上的操作）
+    fn original_selection_mask(&self) -> Option<Mask> {
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        match image::open(&original_path) {
+            Ok(original) => self.selection_mask(&original.to_rgba8()),
+            Err(_) => None,
+        }
+    }
+    // 在编辑会话的底图上依次执行 steps（选区外保持不变），返回（结果，受影响的像素总数）
+    fn run_edit_steps(&self, img: image::RgbaImage, steps: &[Step]) -> Result<(image::RgbaImage, u32), String> {
+        let mask = self.original_selection_mask();
+        let levels = self.project.borrow().levels();
+        let (img, counts) = pipeline::run(img, steps, mask.as_ref(), &levels)?;
+        Ok((img, counts.iter().map(|c| c.unwrap_or(0)).sum()))
+    }
+    // 保存编辑会话的当前结果为待保存的临时文件并刷新显示
+    fn store_edit_result(&self, session: &EditSession) {
+        if let Err(e) = self.save_step(&session.current, &session.temp_path) {
+            eprintln!("Error saving edited image: {}", e);
+            return;
+        }
+        unsafe {
+            *self.display_path.get() = session.temp_path.clone();
+            *self.pending_path.get() = Some(session.temp_path.clone());
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+    }
+    // 当前有效的编辑会话（待保存结果仍为会话的临时文件）
+    fn edit_session_valid(&self) -> bool {
+        let pending = unsafe { (*self.pending_path.get()).clone() };
+        match &*self.edit_session.borrow() {
+            Some(session) => pending.as_ref() == Some(&session.temp_path),
+            None => false,
+        }
+    }
+    // 读取图片（带缓存）；只保留最近使用的两张（原图与当前结果）
+    fn cached_image(&self, path: &str) -> Option<Rc<image::RgbaImage>> {
+        if path.is_empty() {
+            return None;
+        }
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut cache = self.image_cache.borrow_mut();
+        if let Some(pos) = cache.iter().position(|c| c.path == path) {
+            if cache[pos].modified == modified {
+                let entry = cache.remove(pos);
+                let image = entry.image.clone();
+                cache.insert(0, entry);
+                return Some(image);
+            }
+            cache.remove(pos);
+        }
+        let image = Rc::new(image::open(path).ok()?.to_rgba8());
+        cache.insert(0, CachedImage { path: path.to_string(), modified, image: image.clone() });
+        cache.truncate(2);
+        Some(image)
+    }
+    // 写入新的处理结果并登记上一步：上一步即此前的待保存结果，只记录其路径；
+    // 仅当新结果要覆盖同名临时文件（同类处理连续执行）时，先把旧文件改名为快照，不复制图片
+    fn save_step(&self, img: &image::RgbaImage, result_path: &str) -> image::ImageResult<()> {
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        let snapshot = format!("{}{}", original_path, ".previous.tmp.png");
+        let pending = unsafe { (*self.pending_path.get()).clone() };
+        let previous = match pending {
+            Some(ref path) if path == result_path => match std::fs::rename(path, &snapshot) {
+                Ok(()) => Some(snapshot.clone()),
+                Err(e) => {
+                    eprintln!("Error recording processing step: {}", e);
+                    None
+                }
+            },
+            other => other,
+        };
+        if let Err(e) = img.save(result_path) {
+            // 写入失败时恢复原来的待保存结果
+            if previous.as_ref() == Some(&snapshot) {
+                let _ = std::fs::rename(&snapshot, result_path);
+            }
+            return Err(e);
+        }
+        unsafe {
+            *self.previous_step.get() = previous;
+        }
+        Ok(())
+    }
+    // 上一步处理结果的路径，没有时为原图
+    fn previous_result_path(&self) -> String {
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        let has_result = unsafe { (*self.pending_path.get()).is_some() };
+        match unsafe { (*self.previous_step.get()).clone() } {
+            Some(previous) if has_result && std::path::Path::new(&previous).exists() => previous,
+            _ => original_path,
+        }
+    }
+    // 结束阈值实时预览
+    fn clear_preview(&self) {
+        unsafe {
+            let preview_path = &mut *self.preview_path.get();
+            if !preview_path.is_empty() {
+                preview_path.clear();
+                (&mut *self.emit.get()).preview_path_changed();
+            }
+        }
+    }
+    // 修改应用设置并写入配置目录，只对变化的属性发出信号
+    fn update_settings<F: FnOnce(&mut Settings)>(&self, update: F) {
+        let view = {
+            let mut settings = self.settings.borrow_mut();
+            update(&mut settings);
+            settings.save();
+            SettingsView::new(&settings)
+        };
+        let old = unsafe { std::mem::replace(&mut *self.settings_view.get(), view.clone()) };
+        if old == view {
+            return;
+        }
+        unsafe {
+            let emit = &mut *self.emit.get();
+            if old.recent_files != view.recent_files { emit.recent_files_changed(); }
+            if old.last_threshold != view.last_threshold { emit.last_threshold_changed(); }
+            if old.last_export_dir != view.last_export_dir { emit.last_export_dir_changed(); }
+            if old.export_gray_codes != view.export_gray_codes { emit.export_gray_codes_changed(); }
+            if old.defaults != view.defaults { emit.defaults_changed(); }
+            if old.window_state != view.window_state { emit.window_state_changed(); }
+        }
+    }
+    // 重新生成选区预览叠加层，并通知 QML 刷新
+    fn refresh_mask_overlay(&self) {
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        let overlay_path = format!("{}{}", original_path, ".mask.tmp.png");
+        let has_mask = self.project.borrow().mask.is_some();
+        let mut written = false;
+        if has_mask && !original_path.is_empty() {
+            if let Ok(img) = image::open(&original_path) {
+                if let Some(mask) = self.selection_mask(&img.to_rgba8()) {
+                    match mask.overlay().save(&overlay_path) {
+                        Ok(_) => written = true,
+                        Err(e) => eprintln!("Error saving selection overlay: {}", e),
+                    }
+                }
+            }
+        }
+        if !written && !original_path.is_empty() {
+            let _ = std::fs::remove_file(&overlay_path);
+        }
+        unsafe {
+            *self.mask_overlay_path.get() = if written { overlay_path } else { String::new() };
+            (&mut *self.emit.get()).mask_overlay_path_changed();
+        }
+    }
 }
 
 
 impl ImageViewerTrait for ImageViewer {
     fn new(emit: ImageViewerEmitter) -> ImageViewer {
+        let settings = Settings::load();
         ImageViewer {
             emit: UnsafeCell::new(emit),
             image_path: UnsafeCell::new(String::new()),
             display_path: UnsafeCell::new(String::new()),
             pending_path: UnsafeCell::new(None),
+            previous_step: UnsafeCell::new(None),
             watcher: RefCell::new(None),
+            project: RefCell::new(Project::default()),
+            mask_overlay_path: UnsafeCell::new(String::new()),
+            edit_session: RefCell::new(None),
+            image_cache: RefCell::new(Vec::new()),
+            preview_path: UnsafeCell::new(String::new()),
+            preview_proxy: RefCell::new(None),
+            tile_pyramid: RefCell::new(None),
+            tile_generation: Cell::new(0),
+            settings_view: UnsafeCell::new(SettingsView::new(&settings)),
+            settings: RefCell::new(settings),
         }
     }
     fn emit(&mut self) -> &mut ImageViewerEmitter {
@@ -51,51 +378,112 @@ impl ImageViewerTrait for ImageViewer {
         if !disp.is_empty() { disp } else { unsafe { &*self.image_path.get() } }
     }
     fn has_pending(&self) -> bool { unsafe { (*self.pending_path.get()).is_some() } }
+    fn mask_overlay_path(&self) -> &str {
+        unsafe { &*self.mask_overlay_path.get() }
+    }
+    fn preview_path(&self) -> &str {
+        unsafe { &*self.preview_path.get() }
+    }
+    fn result_path(&self) -> &str {
+        // 当前处理结果：有待保存的结果时为临时文件，否则为原图（不受平铺预览影响）
+        unsafe {
+            match &*self.pending_path.get() {
+                Some(p) => p,
+                None => &*self.image_path.get(),
+            }
+        }
+    }
+    fn recent_files(&self) -> &str {
+        unsafe { &(*self.settings_view.get()).recent_files }
+    }
+    fn last_threshold(&self) -> &str {
+        unsafe { &(*self.settings_view.get()).last_threshold }
+    }
+    fn last_export_dir(&self) -> &str {
+        unsafe { &(*self.settings_view.get()).last_export_dir }
+    }
+    fn export_gray_codes(&self) -> bool {
+        unsafe { (*self.settings_view.get()).export_gray_codes }
+    }
+    fn defaults(&self) -> &str {
+        unsafe { &(*self.settings_view.get()).defaults }
+    }
+    fn set_defaults(&self, defaults_json: String) -> () {
+        // 默认选项JSON：{ "opaqueOnly", "grayKeepAlpha", "grayOnOpen" }，缺少的字段取默认值
+        let defaults: ProcessingDefaults = match serde_json::from_str(&defaults_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing defaults JSON: {}", e);
+                return;
+            }
+        };
+        self.update_settings(|s| s.defaults = defaults);
+    }
+    fn window_state(&self) -> &str {
+        unsafe { &(*self.settings_view.get()).window_state }
+    }
+    fn set_window_state(&self, window_json: String) -> () {
+        // 窗口状态JSON：{ "width", "height", "zoom" }，关闭主窗口时保存
+        let state: WindowState = match serde_json::from_str(&window_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing window state JSON: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = state.validate() {
+            eprintln!("{}", e);
+            return;
+        }
+        self.update_settings(|s| s.window = Some(state));
+    }
+    fn clear_recent_files(&self) -> () {
+        self.update_settings(|s| s.recent_files.clear());
+    }
     fn set_image_path(&self, path: String) -> () {
-        // 设置原图路径，通知 QML 更新
+        // 设置原图路径并加载对应工程，通知 QML 更新
+        *self.project.borrow_mut() = Project::load(&path);
+        if !path.is_empty() {
+            self.update_settings(|s| s.add_recent_file(&path));
+        }
         unsafe { *self.image_path.get() = path; }
-        unsafe { (&mut *self.emit.get()).image_path_changed(); }
+        unsafe {
+            (&mut *self.emit.get()).image_path_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+        self.clear_preview();
+        self.refresh_mask_overlay();
+        // 按默认选项在打开图片后直接生成灰度预览（含启动时恢复的图片）
+        let gray_on_open = self.settings.borrow().defaults.gray_on_open;
+        if gray_on_open && !self.image_path().is_empty() {
+            self.gray_preview();
+        }
     }
     fn gray_preview(&self) -> () {
         let path = unsafe { (*self.image_path.get()).clone() };
         if path.is_empty() { return; }
         // 读取并转换为 RGBA8，保留 alpha；将 RGB 设置为相同亮度值
         let img = match image::open(&path) { Ok(i) => i, Err(_) => return };
-        let mut rgba: image::RgbaImage = img.to_rgba8();
-        for p in rgba.pixels_mut() {
-            let r = p[0] as u32;
-            let g = p[1] as u32;
-            let b = p[2] as u32;
-            let a = p[3];
-            
-            // ITU-R BT.601 近似加权，整数计算避免浮点
-            let luma = ((299 * r + 587 * g + 114 * b) / 1000) as u8;
-            
-            // 新的alpha处理逻辑：
-            // - alpha为0的像素设为(0,0,0,0)
-            // - alpha不为0的像素alpha设为255
-            if a == 0 {
-                p[0] = 0;
-                p[1] = 0;
-                p[2] = 0;
-                p[3] = 0;
-            } else {
-                p[0] = luma;
-                p[1] = luma;
-                p[2] = luma;
-                p[3] = 255;
-            }
-        }
-        let dyn_img = image::DynamicImage::ImageRgba8(rgba);
+        let original = img.to_rgba8();
+        let mut rgba = original.clone();
+        threshold::gray_image(&mut rgba);
+        if self.settings.borrow().defaults.gray_keep_alpha {
+            // 保留半透明像素原有的 alpha（alpha 为 0 的像素仍为 (0,0,0,0)）
+            for (p, o) in rgba.pixels_mut().zip(original.pixels()) {
+                p[3] = o[3];
+            }
+        }
+        self.restore_unselected(&original, &mut rgba);
         // 写入临时文件，不覆盖原图
         let tmp_path = format!("{}{}", &path, ".gray.tmp.png");
-        let _ = dyn_img.save(&tmp_path);
+        let _ = self.save_step(&rgba, &tmp_path);
         // 标记待保存并刷新显示
         unsafe {
             *self.pending_path.get() = Some(tmp_path);
             *self.display_path.get() = (*self.pending_path.get()).clone().unwrap_or_default();
             (&mut *self.emit.get()).display_path_changed();
             (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
         }
     }
     fn save_processed(&self) -> () {
@@ -105,12 +493,16 @@ impl ImageViewerTrait for ImageViewer {
         if let Some(tmp) = tmp_opt {
             let _ = std::fs::copy(&tmp, &img_path);
             let _ = std::fs::remove_file(&tmp);
+            // 保存后原图即当前结果，不再保留上一步
+            let _ = std::fs::remove_file(format!("{}{}", img_path, ".previous.tmp.png"));
             unsafe {
                 *self.pending_path.get() = None;
+                *self.previous_step.get() = None;
                 (*self.display_path.get()).clear();
                 (&mut *self.emit.get()).image_path_changed();
                 (&mut *self.emit.get()).display_path_changed();
                 (&mut *self.emit.get()).has_pending_changed();
+                (&mut *self.emit.get()).result_path_changed();
             }
         }
     }
@@ -125,6 +517,7 @@ impl ImageViewerTrait for ImageViewer {
         unsafe {
             (&mut *self.emit.get()).display_path_changed();
             (&mut *self.emit.get()).image_path_changed();
+            (&mut *self.emit.get()).result_path_changed();
         }
     }
     fn apply_threshold_mapping(&self, thresholds_json: String) -> () {
@@ -137,11 +530,8 @@ impl ImageViewerTrait for ImageViewer {
             }
         };
         
-        let thresholds = mapping_data.stops;
-        let is_average_mode = mapping_data.average_mode;
-        
-        if thresholds.is_empty() {
-            eprintln!("No thresholds provided");
+        if let Err(e) = mapping_data.validate() {
+            eprintln!("{}", e);
             return;
         }
         
@@ -162,73 +552,25 @@ impl ImageViewerTrait for ImageViewer {
                 }
             };
             
-            let (width, height) = img.dimensions();
-            let mut mapped_img = image::RgbaImage::new(width, height);
+            // 确认映射后以全分辨率结果替换实时预览
+            self.clear_preview();
             
             // 应用阈值映射和alpha修改逻辑
-            for y in 0..height {
-                for x in 0..width {
-                    let pixel = img.get_pixel(x, y);
-                    let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
-                    
-                    // 计算灰度值
-                    let gray = ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8;
-                    
-                    // 应用alpha修改逻辑和阈值映射
-                    let (mapped_gray, mapped_alpha) = if a == 0 {
-                        (0, 0) // 透明像素保持为(0,0,0,0)
-                    } else {
-                        let segment_gray = if is_average_mode {
-                            // 平均模式：计算该段的平均灰度值
-                            let mut result = 0;
-                            for (i, &threshold) in thresholds.iter().enumerate() {
-                                if gray <= threshold {
-                                    let prev_threshold = if i == 0 { 0 } else { thresholds[i-1] };
-                                    result = ((prev_threshold as u16 + threshold as u16) / 2) as u8;
-                                    break;
-                                }
-                            }
-                            // 如果灰度值大于所有阈值，使用最后一段
-                            if gray > *thresholds.last().unwrap() {
-                                let last_threshold = *thresholds.last().unwrap();
-                                result = ((last_threshold as u16 + 255) / 2) as u8;
-                            }
-                            result
-                        } else {
-                            // 分段模式：均匀分布，最亮段为255，最暗段为0
-                            let segment_count = thresholds.len() + 1; // 段数 = 阈值数 + 1
-                            let mut segment_index = 0;
-                            
-                            for (i, &threshold) in thresholds.iter().enumerate() {
-                                if gray <= threshold {
-                                    segment_index = i;
-                                    break;
-                                }
-                                segment_index = i + 1;
-                            }
-                            
-                            if segment_count == 1 {
-                                128 // 只有一段时使用中值
-                            } else if segment_index == 0 {
-                                0 // 最暗段映射为纯黑色
-                            } else if segment_index == segment_count - 1 {
-                                255 // 最亮段映射为纯白色
-                            } else {
-                                // 中间段均匀分布
-                                ((255 * segment_index) / (segment_count - 1)) as u8
-                            }
-                        };
-                        (segment_gray, 255) // alpha修改：alpha不为0的像素alpha设为255
-                    };
-                    
-                    mapped_img.put_pixel(x, y, image::Rgba([mapped_gray, mapped_gray, mapped_gray, mapped_alpha]));
-                }
+            let mut mapped_img = mapping_data.render(&img, &mapping_data.segment_colors());
+            self.restore_unselected(&img, &mut mapped_img);
+            
+            // 记录到工程，颜色映射与导出基于同一分段
+            {
+                let mut project = self.project.borrow_mut();
+                project.threshold = Some(mapping_data.clone());
+                project.save(original_path);
             }
+            // 下次打开阈值窗口时以本次映射为初值
+            self.update_settings(|s| s.last_threshold = Some(mapping_data));
             
             // 保存映射后的图片到临时文件
             let temp_path = format!("{}{}", original_path, ".threshold.tmp.png");
-            let dyn_img = image::DynamicImage::ImageRgba8(mapped_img);
-            if let Err(e) = dyn_img.save(&temp_path) {
+            if let Err(e) = self.save_step(&mapped_img, &temp_path) {
                 eprintln!("Error saving threshold mapped image: {}", e);
                 return;
             }
@@ -239,9 +581,75 @@ impl ImageViewerTrait for ImageViewer {
                 *self.pending_path.get() = Some(temp_path);
                 (&mut *self.emit.get()).display_path_changed();
                 (&mut *self.emit.get()).has_pending_changed();
+                (&mut *self.emit.get()).result_path_changed();
+            }
+    }
+    fn preview_threshold_mapping(&self, thresholds_json: String) -> () {
+        // 拖动阈值滑块时的快速预览：JSON 格式同 apply_threshold_mapping，作用于原图的缩小代理图，
+        // 只改变显示，不改变待保存的结果，也不写入工程；传入空字符串结束预览
+        if thresholds_json.trim().is_empty() {
+            self.clear_preview();
+            return;
+        }
+        let mapping_data: ThresholdMappingData = match serde_json::from_str(&thresholds_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing threshold mapping JSON: {}", e);
+                return;
             }
+        };
+        if let Err(e) = mapping_data.validate() {
+            eprintln!("{}", e);
+            return;
+        }
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for threshold preview");
+            return;
+        }
+        
+        // 代理图只在原图或选区变化后重新生成
+        let modified = std::fs::metadata(&original_path).and_then(|m| m.modified()).ok();
+        let stale = match &*self.preview_proxy.borrow() {
+            Some(cached) => cached.path != original_path || cached.modified != modified,
+            None => true,
+        };
+        if stale {
+            let img = match image::open(&original_path) {
+                Ok(i) => i.to_rgba8(),
+                Err(e) => {
+                    eprintln!("Error loading original image for threshold preview: {}", e);
+                    return;
+                }
+            };
+            let proxy = Proxy::new(&img, self.selection_mask(&img).as_ref());
+            *self.preview_proxy.borrow_mut() = Some(PreviewProxy { path: original_path.clone(), modified, proxy });
+        }
+        let preview_img = match &*self.preview_proxy.borrow() {
+            Some(cached) => cached.proxy.render(&mapping_data),
+            None => return,
+        };
+        
+        let temp_path = format!("{}{}", original_path, ".preview.tmp.png");
+        let dyn_img = image::DynamicImage::ImageRgba8(preview_img);
+        if let Err(e) = dyn_img.save(&temp_path) {
+            eprintln!("Error saving threshold preview: {}", e);
+            return;
+        }
+        unsafe {
+            *self.preview_path.get() = temp_path;
+            (&mut *self.emit.get()).preview_path_changed();
+        }
     }
-    fn cleanup_scattered_pixels(&self) -> () {
+    fn cleanup_scattered_pixels(&self, wrap_mode: String) -> () {
+        // wrap_mode：none / horizontal / vertical / both / halfDrop，空字符串视为 none
+        let wrap = match WrapMode::parse(&wrap_mode) {
+            Ok(wrap) => wrap,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
         let original_path = unsafe { &*self.image_path.get() };
             
             if original_path.is_empty() {
@@ -258,91 +666,12 @@ impl ImageViewerTrait for ImageViewer {
                 }
             };
             
-            let (width, height) = img.dimensions();
-            let mut cleaned_img = img.clone();
-            
-            // 从左上角开始遍历所有非透明像素
-            for y in 0..height {
-                for x in 0..width {
-                    let pixel = img.get_pixel(x, y);
-                    let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
-                    
-                    // 跳过透明像素
-                    if a == 0 {
-                        continue;
-                    }
-                    
-                    // 检查周围8个像素的颜色
-                    let mut neighbor_colors = std::collections::HashMap::new();
-                    let mut neighbor_count = 0;
-                    
-                    for dy in -1..=1 {
-                        for dx in -1..=1 {
-                            if dx == 0 && dy == 0 {
-                                continue; // 跳过中心像素
-                            }
-                            
-                            let nx = x as i32 + dx;
-                            let ny = y as i32 + dy;
-                            
-                            // 边界检查
-                            if nx < 0 || nx >= width as i32 || ny < 0 || ny >= height as i32 {
-                                continue;
-                            }
-                            
-                            let neighbor_pixel = img.get_pixel(nx as u32, ny as u32);
-                            let (nr, ng, nb, na) = (neighbor_pixel[0], neighbor_pixel[1], neighbor_pixel[2], neighbor_pixel[3]);
-                            
-                            // 只考虑非透明像素
-                            if na > 0 {
-                                let color_key = (nr, ng, nb);
-                                *neighbor_colors.entry(color_key).or_insert(0) += 1;
-                                neighbor_count += 1;
-                            }
-                        }
-                    }
-                    
-                    // 如果周围有非透明像素且颜色不同，则进行清理
-                    if neighbor_count > 0 && !neighbor_colors.contains_key(&(r, g, b)) {
-                        // 找到数量最多的颜色
-                        let mut max_count = 0;
-                        let mut most_common_colors = Vec::new();
-                        
-                        for (&color, &count) in &neighbor_colors {
-                            if count > max_count {
-                                max_count = count;
-                                most_common_colors.clear();
-                                most_common_colors.push(color);
-                            } else if count == max_count {
-                                most_common_colors.push(color);
-                            }
-                        }
-                        
-                        // 如果有多个颜色数量相同，选择较深的颜色
-                        if most_common_colors.len() > 1 {
-                            let mut darkest_color = most_common_colors[0];
-                            let mut darkest_brightness = (darkest_color.0 as u32 + darkest_color.1 as u32 + darkest_color.2 as u32) / 3;
-                            
-                            for &color in &most_common_colors[1..] {
-                                let brightness = (color.0 as u32 + color.1 as u32 + color.2 as u32) / 3;
-                                if brightness < darkest_brightness {
-                                    darkest_color = color;
-                                    darkest_brightness = brightness;
-                                }
-                            }
-                            
-                            cleaned_img.put_pixel(x, y, image::Rgba([darkest_color.0, darkest_color.1, darkest_color.2, a]));
-                        } else if let Some(&new_color) = most_common_colors.first() {
-                            cleaned_img.put_pixel(x, y, image::Rgba([new_color.0, new_color.1, new_color.2, a]));
-                        }
-                    }
-                }
-            }
+            let mut cleaned_img = cleanup::scattered_pixels(&img, wrap);
+            self.restore_unselected(&img, &mut cleaned_img);
             
             // 保存清理后的图片到临时文件
             let temp_path = format!("{}{}", original_path, ".cleanup.tmp.png");
-            let dyn_img = image::DynamicImage::ImageRgba8(cleaned_img);
-            if let Err(e) = dyn_img.save(&temp_path) {
+            if let Err(e) = self.save_step(&cleaned_img, &temp_path) {
                 eprintln!("Error saving cleaned image: {}", e);
                 return;
             }
@@ -353,61 +682,1091 @@ impl ImageViewerTrait for ImageViewer {
                 *self.pending_path.get() = Some(temp_path);
                 (&mut *self.emit.get()).display_path_changed();
                 (&mut *self.emit.get()).has_pending_changed();
+                (&mut *self.emit.get()).result_path_changed();
             }
     }
-    fn cleanup_temp_files(&self) -> () {
-        // 清理当前登记的临时文件
-        let pending_snapshot = unsafe { (*self.pending_path.get()).clone() };
-        if let Some(temp_path) = pending_snapshot {
-            let _ = std::fs::remove_file(&temp_path);
-            println!("Cleaned up temporary file: {}", temp_path);
+    fn quantize_colors(&self, quantize_json: String) -> () {
+        // 解析量化参数JSON：{ "colors": 6, "method": "kmeans" | "medianCut" } 或 { "palette": [[r, g, b], ...] }
+        let quantize_data: QuantizeData = match serde_json::from_str(&quantize_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing quantize JSON: {}", e);
+                return;
+            }
+        };
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for quantization");
+            return;
         }
-        // 扫描并清理同名基底的其他临时变体
-        let image_path_snapshot = unsafe { (*self.image_path.get()).clone() };
-        if !image_path_snapshot.is_empty() {
-            let base_path = std::path::Path::new(&image_path_snapshot);
-            if let Some(parent) = base_path.parent() {
-                if let Some(file_stem) = base_path.file_stem() {
-                    let temp_patterns = [
-                        format!("{}.gray.tmp.png", file_stem.to_string_lossy()),
-                        format!("{}.threshold.tmp.png", file_stem.to_string_lossy()),
-                        format!("{}.cleanup.tmp.png", file_stem.to_string_lossy()),
-                    ];
-                    for pattern in &temp_patterns {
-                        let temp_path = parent.join(pattern);
-                        if temp_path.exists() { let _ = std::fs::remove_file(&temp_path); }
-                    }
-                }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for quantization: {}", e);
+                return;
+            }
+        };
+        
+        let mut quantized_img = match quantize::quantize(&img, &quantize_data) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
             }
+        };
+        self.restore_unselected(&img, &mut quantized_img);
+        
+        // 保存量化后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".quantize.tmp.png");
+        if let Err(e) = self.save_step(&quantized_img, &temp_path) {
+            eprintln!("Error saving quantized image: {}", e);
+            return;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
         }
     }
-    fn start_watcher(&self, path: String) -> () {
-        // 启动文件监听，用于感知目标 PNG 文件变化，节流 60ms
-        let (tx, rx) = std::sync::mpsc::channel();
-        let result = notify::recommended_watcher(move |res| { let _ = tx.send(res); });
-        if let Ok(mut w) = result {
-            use notify::{RecursiveMode, EventKind};
-            let _ = w.watch(std::path::Path::new(&path), RecursiveMode::NonRecursive);
-            // 记录 watcher 并克隆一个发射器用于子线程触发 UI 刷新
-            *self.watcher.borrow_mut() = Some(w);
-            let mut emit_clone = unsafe { (&mut *self.emit.get()).clone() };
-            std::thread::spawn(move || {
-                let mut last = std::time::Instant::now();
-                while let Ok(res) = rx.recv() {
-                    if let Ok(event) = res {
-                        match event.kind {
-                            EventKind::Modify(_) | EventKind::Create(_) => {
-                                if last.elapsed() >= std::time::Duration::from_millis(60) {
-                                    emit_clone.image_path_changed();
-                                    last = std::time::Instant::now();
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else { break; }
-                }
-            });
+    fn adjust_tone(&self, tone_json: String) -> () {
+        // 解析色调参数JSON：{ "blackPoint": 20, "whitePoint": 235, "gamma": 1.2, "brightness": 0, "contrast": 10, "curve": [[0, 0], [128, 150], [255, 255]] }
+        let tone_data: ToneData = match serde_json::from_str(&tone_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing tone JSON: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = tone_data.validate() {
+            eprintln!("{}", e);
+            return;
         }
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for tone adjustment");
+            return;
+        }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for tone adjustment: {}", e);
+                return;
+            }
+        };
+        
+        let mut adjusted_img = tone::apply(&img, &tone_data);
+        self.restore_unselected(&img, &mut adjusted_img);
+        
+        // 保存调整后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".tone.tmp.png");
+        if let Err(e) = self.save_step(&adjusted_img, &temp_path) {
+            eprintln!("Error saving tone adjusted image: {}", e);
+            return;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+    }
+    fn denoise(&self, denoise_json: String) -> () {
+        // 解析降噪参数JSON：{ "filter": "median", "radius": 1 } / { "filter": "gaussian", "sigma": 1.0 } / { "filter": "bilateral", "sigma": 2.0, "range": 30.0 }
+        let denoise_data: DenoiseData = match serde_json::from_str(&denoise_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing denoise JSON: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = denoise_data.validate() {
+            eprintln!("{}", e);
+            return;
+        }
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for denoising");
+            return;
+        }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for denoising: {}", e);
+                return;
+            }
+        };
+        
+        let mut denoised_img = denoise::apply(&img, &denoise_data);
+        self.restore_unselected(&img, &mut denoised_img);
+        
+        // 保存降噪后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".denoise.tmp.png");
+        if let Err(e) = self.save_step(&denoised_img, &temp_path) {
+            eprintln!("Error saving denoised image: {}", e);
+            return;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+    }
+    fn apply_transform(&self, transform_json: String) -> () {
+        // 解析几何变换JSON（与流水线步骤相同）：{ "op": "crop", "x": 0, "y": 0, "width": 100, "height": 80 } /
+        // { "op": "rotate", "degrees": 90 } / { "op": "flip", "direction": "horizontal" } / { "op": "trim" }
+        let step: Step = match serde_json::from_str(&transform_json) {
+            Ok(step) => step,
+            Err(e) => {
+                eprintln!("Error parsing transform JSON: {}", e);
+                return;
+            }
+        };
+        if !step.is_geometric() {
+            eprintln!("Not a geometric transform: {}", transform_json);
+            return;
+        }
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for transform");
+            return;
+        }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for transform: {}", e);
+                return;
+            }
+        };
+        
+        let transformed_img = match step.apply(img, &self.project.borrow().levels()) {
+            Ok(i) => i,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        
+        // 保存变换后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".transform.tmp.png");
+        if let Err(e) = self.save_step(&transformed_img, &temp_path) {
+            eprintln!("Error saving transformed image: {}", e);
+            return;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+    }
+    fn tile_preview(&self, repeat_json: String) -> () {
+        // 循环平铺预览：{ "mode": "straight" | "halfDrop" | "halfBrick" | "mirror", "columns": 3, "rows": 3, "highlightSeams": true }
+        // 只改变显示，不改变待保存的结果；传入空字符串退出预览
+        if repeat_json.trim().is_empty() {
+            unsafe {
+                *self.display_path.get() = (*self.pending_path.get()).clone().unwrap_or_default();
+                (&mut *self.emit.get()).display_path_changed();
+            }
+            return;
+        }
+        let repeat_data: RepeatData = match serde_json::from_str(&repeat_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing repeat JSON: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = repeat_data.validate() {
+            eprintln!("{}", e);
+            return;
+        }
+        
+        // 以当前处理结果（映射之后）为循环单元
+        let source_path = self.current_result_path();
+        if source_path.is_empty() {
+            eprintln!("No image loaded for tile preview");
+            return;
+        }
+        let img = match image::open(&source_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading image for tile preview: {}", e);
+                return;
+            }
+        };
+        
+        let tiled_img = repeat::render(&img, &repeat_data);
+        let original_path = unsafe { &*self.image_path.get() };
+        let temp_path = format!("{}{}", original_path, ".tile.tmp.png");
+        let dyn_img = image::DynamicImage::ImageRgba8(tiled_img);
+        if let Err(e) = dyn_img.save(&temp_path) {
+            eprintln!("Error saving tile preview: {}", e);
+            return;
+        }
+        
+        unsafe {
+            *self.display_path.get() = temp_path;
+            (&mut *self.emit.get()).display_path_changed();
+        }
+    }
+    fn compare(&self, compare_json: String) -> String {
+        // 前后对比：{ "before": "original" | "previous", "mode": "sideBySide" | "split" | "difference", "split": 0.5, "highlight": [255, 0, 0, 255] }
+        // 与当前处理结果对比，只改变显示，不改变待保存的结果；返回 { "changed", "width", "height" }，传入空字符串退出对比
+        if compare_json.trim().is_empty() {
+            unsafe {
+                *self.display_path.get() = (*self.pending_path.get()).clone().unwrap_or_default();
+                (&mut *self.emit.get()).display_path_changed();
+            }
+            return String::new();
+        }
+        let compare_data: CompareData = match serde_json::from_str(&compare_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing compare JSON: {}", e);
+                return String::new();
+            }
+        };
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        if original_path.is_empty() {
+            eprintln!("No image loaded for comparison");
+            return String::new();
+        }
+        let before_path = match compare_data.before {
+            CompareSource::Original => original_path.clone(),
+            CompareSource::Previous => self.previous_result_path(),
+        };
+        let (before, after) = match (self.cached_image(&before_path), self.cached_image(&self.current_result_path())) {
+            (Some(before), Some(after)) => (before, after),
+            _ => {
+                eprintln!("Error loading images for comparison");
+                return String::new();
+            }
+        };
+        let (compare_img, mut report) = match compare::render(&before, &after, &compare_data) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}", e);
+                return String::new();
+            }
+        };
+        let compare_img = match compare_img {
+            Some(img) => img,
+            None => {
+                // 分割模式：显示当前结果，由 QML 叠放对比对象，拖动分割线时不再调用
+                unsafe {
+                    *self.display_path.get() = (*self.pending_path.get()).clone().unwrap_or_default();
+                    (&mut *self.emit.get()).display_path_changed();
+                }
+                report.before_path = Some(before_path);
+                return serde_json::to_string(&report).unwrap_or_default();
+            }
+        };
+        
+        let temp_path = format!("{}{}", original_path, ".compare.tmp.png");
+        let dyn_img = image::DynamicImage::ImageRgba8(compare_img);
+        if let Err(e) = dyn_img.save(&temp_path) {
+            eprintln!("Error saving comparison image: {}", e);
+            return String::new();
+        }
+        unsafe {
+            *self.display_path.get() = temp_path;
+            (&mut *self.emit.get()).display_path_changed();
+        }
+        serde_json::to_string(&report).unwrap_or_default()
+    }
+    fn analyze_seams(&self, repeat_json: String) -> String {
+        // 检查当前处理结果按指定循环方式相接时的接缝，返回 { "leftRight": {...}, "topBottom": {...} }
+        let repeat_data: RepeatData = match serde_json::from_str(&repeat_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing repeat JSON: {}", e);
+                return String::new();
+            }
+        };
+        let source_path = self.current_result_path();
+        let img = match image::open(&source_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading image for seam analysis: {}", e);
+                return String::new();
+            }
+        };
+        serde_json::to_string(&repeat::analyze(&img, repeat_data.mode)).unwrap_or_default()
+    }
+    fn replace_color(&self, replace_json: String) -> u32 {
+        // 解析改色JSON：{ "from": [r, g, b], "to": [r, g, b], "tolerance": 0 }
+        let replace_data: ReplaceColorData = match serde_json::from_str(&replace_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing replace color JSON: {}", e);
+                return 0;
+            }
+        };
+        self.recolor_current(|img| Ok(recolor::replace_color(img, &replace_data).0))
+    }
+    fn merge_levels(&self, merge_json: String) -> u32 {
+        // 解析合并段JSON：{ "from": 2, "into": 1 }
+        let merge_data: MergeLevelData = match serde_json::from_str(&merge_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing merge levels JSON: {}", e);
+                return 0;
+            }
+        };
+        let levels = self.project.borrow().levels();
+        self.recolor_current(|img| Ok(recolor::merge_levels(img, &merge_data, &levels)?.0))
+    }
+    fn isolate_level(&self, isolate_json: String) -> u32 {
+        // 单段查看：{ "level": 2, "others": "dim" | "hide" }，作用于当前处理结果，返回该段的像素数
+        // 只改变显示，不改变待保存的结果；传入空字符串退出
+        if isolate_json.trim().is_empty() {
+            unsafe {
+                *self.display_path.get() = (*self.pending_path.get()).clone().unwrap_or_default();
+                (&mut *self.emit.get()).display_path_changed();
+            }
+            return 0;
+        }
+        let isolate_data: IsolateData = match serde_json::from_str(&isolate_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing isolate level JSON: {}", e);
+                return 0;
+            }
+        };
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        if original_path.is_empty() {
+            eprintln!("No image loaded for level isolation");
+            return 0;
+        }
+        let img = match self.cached_image(&self.current_result_path()) {
+            Some(img) => img,
+            None => {
+                eprintln!("Error loading image for level isolation");
+                return 0;
+            }
+        };
+        let levels = self.project.borrow().levels();
+        let (isolated_img, count) = match isolate::isolate(&img, &isolate_data, &levels) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 0;
+            }
+        };
+        
+        let temp_path = format!("{}{}", original_path, ".isolate.tmp.png");
+        let dyn_img = image::DynamicImage::ImageRgba8(isolated_img);
+        if let Err(e) = dyn_img.save(&temp_path) {
+            eprintln!("Error saving isolated level image: {}", e);
+            return 0;
+        }
+        unsafe {
+            *self.display_path.get() = temp_path;
+            (&mut *self.emit.get()).display_path_changed();
+        }
+        count
+    }
+    fn edit_pixels(&self, edit_json: String) -> u32 {
+        // 解析编辑JSON（图像坐标）：{ "tool": "pixel", "x": 1, "y": 2, "color": [r, g, b, a] } /
+        // { "tool": "line", "x0", "y0", "x1", "y1", "color" } / { "tool": "rect", "x", "y", "width", "height", "color" } /
+        // { "tool": "fill", "x", "y", "color", "connectivity": 4 | 8 }；返回改变的像素数
+        let edit_data: EditData = match serde_json::from_str(&edit_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing edit JSON: {}", e);
+                return 0;
+            }
+        };
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for editing");
+            return 0;
+        }
+        
+        // 会话失效时，以当前处理结果为底图开始新的编辑会话
+        if !self.edit_session_valid() {
+            let base = match image::open(self.current_result_path()) {
+                Ok(i) => i.to_rgba8(),
+                Err(e) => {
+                    eprintln!("Error loading image for editing: {}", e);
+                    return 0;
+                }
+            };
+            *self.edit_session.borrow_mut() = Some(EditSession {
+                temp_path: format!("{}{}", original_path, ".edit.tmp.png"),
+                current: base.clone(),
+                base,
+                steps: Vec::new(),
+                redo: Vec::new(),
+            });
+        }
+        
+        let step = Step::Edit(edit_data);
+        let current = self.edit_session.borrow().as_ref().map(|s| s.current.clone()).unwrap();
+        let (edited_img, affected) = match self.run_edit_steps(current, std::slice::from_ref(&step)) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}", e);
+                return 0;
+            }
+        };
+        let mut session = self.edit_session.borrow_mut();
+        let session = session.as_mut().unwrap();
+        session.current = edited_img;
+        session.steps.push(step);
+        session.redo.clear();
+        self.store_edit_result(session);
+        affected
+    }
+    fn undo_edit(&self) -> bool {
+        // 撤销最近一次编辑：从底图重放其余编辑步骤
+        if !self.edit_session_valid() {
+            return false;
+        }
+        let mut session = self.edit_session.borrow_mut();
+        let session = session.as_mut().unwrap();
+        let step = match session.steps.pop() {
+            Some(step) => step,
+            None => return false,
+        };
+        session.redo.push(step);
+        match self.run_edit_steps(session.base.clone(), &session.steps) {
+            Ok((img, _)) => session.current = img,
+            Err(e) => {
+                eprintln!("{}", e);
+                return false;
+            }
+        }
+        self.store_edit_result(session);
+        true
+    }
+    fn redo_edit(&self) -> bool {
+        // 重做最近一次撤销的编辑
+        if !self.edit_session_valid() {
+            return false;
+        }
+        let mut session = self.edit_session.borrow_mut();
+        let session = session.as_mut().unwrap();
+        let step = match session.redo.pop() {
+            Some(step) => step,
+            None => return false,
+        };
+        match self.run_edit_steps(session.current.clone(), std::slice::from_ref(&step)) {
+            Ok((img, _)) => session.current = img,
+            Err(e) => {
+                eprintln!("{}", e);
+                session.redo.push(step);
+                return false;
+            }
+        }
+        session.steps.push(step);
+        self.store_edit_result(session);
+        true
+    }
+    fn inspect(&self, x: i32, y: i32) -> String {
+        // 查询图像坐标 (x, y) 处的像素信息，返回 JSON；坐标在图片外或没有图片时返回空字符串
+        let original_path = unsafe { (*self.image_path.get()).clone() };
+        let original = match self.cached_image(&original_path) {
+            Some(img) => img,
+            None => return String::new(),
+        };
+        let result_path = self.current_result_path();
+        let processed = if result_path == original_path { Some(original.clone()) } else { self.cached_image(&result_path) };
+        match inspect::inspect(&original, processed.as_ref().map(|p| &**p), &self.project.borrow(), x, y) {
+            Some(info) => serde_json::to_string(&info).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+    fn set_color_table(&self, color_table_json: String) -> () {
+        // 解析颜色表JSON：[{ "level": 0, "color": [r, g, b], "name": "...", "structure": "..." }, ...]
+        let color_table: Vec<LevelColor> = match serde_json::from_str(&color_table_json) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("Error parsing color table JSON: {}", e);
+                return;
+            }
+        };
+        let image_path = unsafe { &*self.image_path.get() };
+        let mut project = self.project.borrow_mut();
+        project.color_table = color_table;
+        project.save(image_path);
+    }
+    fn get_color_table(&self) -> String {
+        serde_json::to_string(&self.project.borrow().color_table).unwrap_or_default()
+    }
+    fn set_density(&self, density_json: String) -> () {
+        // 解析经纬密度JSON：{ "warp": 40.0, "weft": 32.0 }（每厘米根数）；空字符串清除
+        let density: Option<Density> = if density_json.trim().is_empty() {
+            None
+        } else {
+            match serde_json::from_str::<Density>(&density_json) {
+                Ok(d) => {
+                    if let Err(e) = d.validate() {
+                        eprintln!("{}", e);
+                        return;
+                    }
+                    Some(d)
+                }
+                Err(e) => {
+                    eprintln!("Error parsing density JSON: {}", e);
+                    return;
+                }
+            }
+        };
+        let image_path = unsafe { &*self.image_path.get() };
+        let mut project = self.project.borrow_mut();
+        project.density = density;
+        project.save(image_path);
+    }
+    fn get_density(&self) -> String {
+        match &self.project.borrow().density {
+            Some(density) => serde_json::to_string(density).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+    fn grid_geometry(&self, grid_json: String) -> String {
+        // 视口JSON：{ "x", "y", "width", "height"（图像坐标）, "zoom", "major": 10 }
+        // 返回 { "lines": [{ "vertical", "position", "major" }], "ticks": [{ "vertical", "position", "label" }], "tickStep" }
+        let request: GridRequest = match serde_json::from_str(&grid_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing grid JSON: {}", e);
+                return String::new();
+            }
+        };
+        if let Err(e) = request.validate() {
+            eprintln!("{}", e);
+            return String::new();
+        }
+        // 只读取文件头获取尺寸
+        let (width, height) = match image::image_dimensions(self.current_result_path()) {
+            Ok(size) => size,
+            Err(_) => return String::new(),
+        };
+        let geometry = grid::geometry(&request, width, height, self.project.borrow().density.as_ref());
+        serde_json::to_string(&geometry).unwrap_or_default()
+    }
+    fn visible_tiles(&self, viewport_json: String) -> String {
+        // 视口JSON：{ "x", "y", "width", "height"（图像坐标）, "zoom" }
+        // 返回 { "tiled", "width", "height", "level", "tiles": [{ "x", "y", "width", "height"（图像坐标）, "path" }] }
+        // 图片较小时 tiled 为 false，QML 直接显示整张图片
+        let viewport: TileViewport = match serde_json::from_str(&viewport_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing tile viewport JSON: {}", e);
+                return String::new();
+            }
+        };
+        if let Err(e) = viewport.validate() {
+            eprintln!("{}", e);
+            return String::new();
+        }
+        let source_path = self.display_path().to_string();
+        if source_path.is_empty() {
+            return String::new();
+        }
+        // 只读取文件头获取尺寸，小图不解码
+        let (width, height) = match image::image_dimensions(&source_path) {
+            Ok(size) => size,
+            Err(_) => return String::new(),
+        };
+        if (width as u64) * (height as u64) < pyramid::TILED_MIN_PIXELS {
+            *self.tile_pyramid.borrow_mut() = None;
+            let layout = TileLayout { tiled: false, width, height, level: 0, tiles: Vec::new() };
+            return serde_json::to_string(&layout).unwrap_or_default();
+        }
+
+        // 分块目录以原图命名，显示图片变化后清空重建
+        let tile_dir = format!("{}{}", unsafe { &*self.image_path.get() }, ".tiles.tmp");
+        let modified = std::fs::metadata(&source_path).and_then(|m| m.modified()).ok();
+        let stale = match &*self.tile_pyramid.borrow() {
+            Some(cached) => cached.path != source_path || cached.modified != modified,
+            None => true,
+        };
+        if stale {
+            let img = match image::open(&source_path) {
+                Ok(i) => i.to_rgba8(),
+                Err(e) => {
+                    eprintln!("Error loading image for tiled display: {}", e);
+                    return String::new();
+                }
+            };
+            let _ = std::fs::remove_dir_all(&tile_dir);
+            if let Err(e) = std::fs::create_dir_all(&tile_dir) {
+                eprintln!("Error creating tile directory: {}", e);
+                return String::new();
+            }
+            let generation = self.tile_generation.get().wrapping_add(1);
+            self.tile_generation.set(generation);
+            *self.tile_pyramid.borrow_mut() = Some(TilePyramid {
+                path: source_path.clone(),
+                modified,
+                generation,
+                pyramid: Pyramid::new(img),
+                written: HashSet::new(),
+            });
+        }
+
+        let mut cache = self.tile_pyramid.borrow_mut();
+        let cached = match cache.as_mut() {
+            Some(cached) => cached,
+            None => return String::new(),
+        };
+        let visible = cached.pyramid.visible_tiles(&viewport);
+        let level = visible.first().map(|t| t.level).unwrap_or_else(|| cached.pyramid.level_for_zoom(viewport.zoom));
+        let mut tiles = Vec::with_capacity(visible.len());
+        for tile in &visible {
+            let path = std::path::Path::new(&tile_dir)
+                .join(format!("{}_{}_{}_{}.png", cached.generation, tile.level, tile.column, tile.row))
+                .to_string_lossy()
+                .into_owned();
+            // 分块只在第一次可见时写入，之后直接复用
+            if cached.written.insert((tile.level, tile.column, tile.row)) {
+                let tile_img = cached.pyramid.render_tile(tile);
+                if let Err(e) = image::DynamicImage::ImageRgba8(tile_img).save(&path) {
+                    eprintln!("Error saving tile: {}", e);
+                    cached.written.remove(&(tile.level, tile.column, tile.row));
+                    continue;
+                }
+            }
+            let (x, y, tile_width, tile_height) = cached.pyramid.tile_rect(tile);
+            tiles.push(TileInfo { x, y, width: tile_width, height: tile_height, path });
+        }
+        let layout = TileLayout { tiled: true, width, height, level, tiles };
+        serde_json::to_string(&layout).unwrap_or_default()
+    }
+    fn apply_color_mapping(&self) -> () {
+        // 在阈值映射的分段基础上，用颜色表中的纱线颜色替换各段输出
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for color mapping");
+            return;
+        }
+        let colored_img = {
+            let project = self.project.borrow();
+            let mapping = match &project.threshold {
+                Some(m) => m,
+                None => {
+                    eprintln!("No threshold mapping in project; apply threshold mapping first");
+                    return;
+                }
+            };
+            let img = match image::open(original_path) {
+                Ok(i) => i.to_rgba8(),
+                Err(e) => {
+                    eprintln!("Error loading original image for color mapping: {}", e);
+                    return;
+                }
+            };
+            let mut colored_img = mapping.render(&img, &project.level_colors(mapping));
+            self.restore_unselected(&img, &mut colored_img);
+            colored_img
+        };
+        
+        // 保存着色后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".color.tmp.png");
+        if let Err(e) = self.save_step(&colored_img, &temp_path) {
+            eprintln!("Error saving color mapped image: {}", e);
+            return;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+    }
+    fn export_color_mapping(&self, output_path: String, gray_codes: bool) -> () {
+        // 导出颜色映射结果：
+        // - gray_codes 为 false：导出着色后的图片
+        // - gray_codes 为 true：导出阈值映射的灰度编码图，并在同名 `.colors.json` 中写出编码与颜色的对照表
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() || output_path.is_empty() {
+            eprintln!("No image or output path for color mapping export");
+            return;
+        }
+        let project = self.project.borrow();
+        let mapping = match &project.threshold {
+            Some(m) => m,
+            None => {
+                eprintln!("No threshold mapping in project; apply threshold mapping first");
+                return;
+            }
+        };
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for color mapping export: {}", e);
+                return;
+            }
+        };
+        let colors = if gray_codes { mapping.segment_colors() } else { project.level_colors(mapping) };
+        let out_img = image::DynamicImage::ImageRgba8(mapping.render(&img, &colors));
+        if let Err(e) = out_img.save(&output_path) {
+            eprintln!("Error exporting color mapped image: {}", e);
+            return;
+        }
+        if gray_codes {
+            let table_path = std::path::Path::new(&output_path).with_extension("colors.json");
+            match serde_json::to_string_pretty(&project.color_table_entries(mapping)) {
+                Ok(text) => {
+                    if let Err(e) = std::fs::write(&table_path, text) {
+                        eprintln!("Error exporting color table: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Error serializing color table: {}", e),
+            }
+        }
+        let export_dir = std::path::Path::new(&output_path).parent().map(|d| d.to_string_lossy().into_owned()).unwrap_or_default();
+        self.update_settings(|s| {
+            s.last_export_dir = export_dir;
+            s.export_gray_codes = gray_codes;
+        });
+    }
+    fn mode_filter(&self, mode_filter_json: String) -> String {
+        // 解析参数JSON：{ "radius": 1, "minAgreement": 0.5, "iterations": 2, "untilStable": false,
+        //               "tieBreak": { "policy": "darker" | "lighter" | "original" | "priority", "colors": [[r, g, b], ...] } }
+        // 返回每次迭代改变的像素数（JSON 数组）
+        let mode_data: ModeFilterData = match serde_json::from_str(&mode_filter_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing mode filter JSON: {}", e);
+                return String::new();
+            }
+        };
+        if let Err(e) = mode_data.validate() {
+            eprintln!("{}", e);
+            return String::new();
+        }
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for mode filter");
+            return String::new();
+        }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for mode filter: {}", e);
+                return String::new();
+            }
+        };
+        
+        let (mut filtered_img, changes) = cleanup::mode_filter(&img, &mode_data);
+        self.restore_unselected(&img, &mut filtered_img);
+        
+        // 保存滤波后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".mode.tmp.png");
+        if let Err(e) = self.save_step(&filtered_img, &temp_path) {
+            eprintln!("Error saving mode filtered image: {}", e);
+            return String::new();
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+        serde_json::to_string(&changes).unwrap_or_default()
+    }
+    fn merge_small_regions(&self, regions_json: String) -> u32 {
+        // 解析参数JSON：{ "minArea": 4, "connectivity": 4 | 8 }
+        let merge_data: RegionMergeData = match serde_json::from_str(&regions_json) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error parsing region merge JSON: {}", e);
+                return 0;
+            }
+        };
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for region merge");
+            return 0;
+        }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for region merge: {}", e);
+                return 0;
+            }
+        };
+        
+        let (mut merged_img, merged) = regions::merge_small_regions(&img, &merge_data);
+        self.restore_unselected(&img, &mut merged_img);
+        
+        // 保存合并后的图片到临时文件
+        let temp_path = format!("{}{}", original_path, ".regions.tmp.png");
+        if let Err(e) = self.save_step(&merged_img, &temp_path) {
+            eprintln!("Error saving region merged image: {}", e);
+            return 0;
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+        merged
+    }
+    fn run_pipeline(&self, pipeline_json: String) -> String {
+        // 解析流水线JSON：步骤数组，按顺序作用于原图；返回每一步受影响的像素数（JSON 数组，不报告的步骤为 null）
+        let steps: Vec<Step> = match serde_json::from_str(&pipeline_json) {
+            Ok(steps) => steps,
+            Err(e) => {
+                eprintln!("Error parsing pipeline JSON: {}", e);
+                return String::new();
+            }
+        };
+        
+        let original_path = unsafe { &*self.image_path.get() };
+        if original_path.is_empty() {
+            eprintln!("No original image loaded for pipeline");
+            return String::new();
+        }
+        
+        // 从原图加载图片
+        let img = match image::open(original_path) {
+            Ok(i) => i.to_rgba8(),
+            Err(e) => {
+                eprintln!("Error loading original image for pipeline: {}", e);
+                return String::new();
+            }
+        };
+        
+        // 每一步都只改变选区内的像素
+        let mask = self.selection_mask(&img);
+        let levels = self.project.borrow().levels();
+        let (processed_img, counts) = match pipeline::run(img, &steps, mask.as_ref(), &levels) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Error running pipeline: {}", e);
+                return String::new();
+            }
+        };
+        
+        // 保存流水线结果到临时文件
+        let temp_path = format!("{}{}", original_path, ".pipeline.tmp.png");
+        if let Err(e) = self.save_step(&processed_img, &temp_path) {
+            eprintln!("Error saving pipeline image: {}", e);
+            return String::new();
+        }
+        
+        // 更新显示路径和待保存状态
+        unsafe {
+            *self.display_path.get() = temp_path.clone();
+            *self.pending_path.get() = Some(temp_path);
+            (&mut *self.emit.get()).display_path_changed();
+            (&mut *self.emit.get()).has_pending_changed();
+            (&mut *self.emit.get()).result_path_changed();
+        }
+        serde_json::to_string(&counts).unwrap_or_default()
+    }
+    fn set_selection_mask(&self, mask_json: String) -> () {
+        // 解析选区JSON：{ "shapes": [{ "type": "rect", ... }, ...], "invert": false }；空字符串或 null 清除选区
+        let mask_data: Option<MaskData> = if mask_json.trim().is_empty() {
+            None
+        } else {
+            match serde_json::from_str(&mask_json) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("Error parsing selection mask JSON: {}", e);
+                    return;
+                }
+            }
+        };
+        {
+            let image_path = unsafe { &*self.image_path.get() };
+            let mut project = self.project.borrow_mut();
+            project.mask = mask_data;
+            project.save(image_path);
+        }
+        *self.preview_proxy.borrow_mut() = None;
+        self.refresh_mask_overlay();
+    }
+    fn get_selection_mask(&self) -> String {
+        match &self.project.borrow().mask {
+            Some(mask) => serde_json::to_string(mask).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+    fn cleanup_temp_files(&self) -> () {
+        // 清理当前登记的临时文件
+        let pending_snapshot = unsafe { (*self.pending_path.get()).clone() };
+        if let Some(temp_path) = pending_snapshot {
+            let _ = std::fs::remove_file(&temp_path);
+            println!("Cleaned up temporary file: {}", temp_path);
+        }
+        // 清理其他临时变体：临时文件按 `<原图完整路径><后缀>` 命名（如 foo.png.gray.tmp.png），
+        // 与写入时使用同一组后缀；`.tiles.tmp` 为分块显示的分块目录
+        let image_path_snapshot = unsafe { (*self.image_path.get()).clone() };
+        if !image_path_snapshot.is_empty() {
+            let temp_suffixes = [
+                ".gray.tmp.png",
+                ".threshold.tmp.png",
+                ".cleanup.tmp.png",
+                ".color.tmp.png",
+                ".quantize.tmp.png",
+                ".regions.tmp.png",
+                ".mode.tmp.png",
+                ".pipeline.tmp.png",
+                ".mask.tmp.png",
+                ".tone.tmp.png",
+                ".denoise.tmp.png",
+                ".transform.tmp.png",
+                ".tile.tmp.png",
+                ".recolor.tmp.png",
+                ".edit.tmp.png",
+                ".preview.tmp.png",
+                ".compare.tmp.png",
+                ".isolate.tmp.png",
+                ".previous.tmp.png",
+                ".tiles.tmp",
+            ];
+            *self.tile_pyramid.borrow_mut() = None;
+            for suffix in &temp_suffixes {
+                let temp_path = std::path::PathBuf::from(format!("{}{}", image_path_snapshot, suffix));
+                if temp_path.is_dir() {
+                    let _ = std::fs::remove_dir_all(&temp_path);
+                } else if temp_path.exists() {
+                    let _ = std::fs::remove_file(&temp_path);
+                }
+            }
+        }
+    }
+    fn start_watcher(&self, path: String) -> () {
+        // 启动文件监听，用于感知目标 PNG 文件变化，节流 60ms
+        let (tx, rx) = std::sync::mpsc::channel();
+        let result = notify::recommended_watcher(move |res| { let _ = tx.send(res); });
+        if let Ok(mut w) = result {
+            use notify::{RecursiveMode, EventKind};
+            let _ = w.watch(std::path::Path::new(&path), RecursiveMode::NonRecursive);
+            // 记录 watcher 并克隆一个发射器用于子线程触发 UI 刷新
+            *self.watcher.borrow_mut() = Some(w);
+            let mut emit_clone = unsafe { (&mut *self.emit.get()).clone() };
+            std::thread::spawn(move || {
+                let mut last = std::time::Instant::now();
+                while let Ok(res) = rx.recv() {
+                    if let Ok(event) = res {
+                        match event.kind {
+                            EventKind::Modify(_) | EventKind::Create(_) => {
+                                if last.elapsed() >= std::time::Duration::from_millis(60) {
+                                    emit_clone.image_path_changed();
+                                    emit_clone.result_path_changed();
+                                    last = std::time::Instant::now();
+                                }
+                            }
+                            _ => {}
+                        }
+                    } else { break; }
+                }
+            });
+        }
+    }
+}
+
+struct PaletteRow {
+    color: String,
+    stats: stats::ColorStats,
+}
+
+// 颜色统计列表模型：每行对应处理结果中的一种颜色，由 QML 在处理结果变化后以 result_path 调用 refresh
+pub struct PaletteStats {
+    emit: PaletteStatsEmitter,
+    model: PaletteStatsList,
+    rows: Vec<PaletteRow>,
+}
+
+impl PaletteStatsTrait for PaletteStats {
+    fn new(emit: PaletteStatsEmitter, model: PaletteStatsList) -> PaletteStats {
+        PaletteStats { emit, model, rows: Vec::new() }
+    }
+    fn emit(&mut self) -> &mut PaletteStatsEmitter {
+        &mut self.emit
+    }
+    fn refresh(&mut self, image_path: String) -> () {
+        // 读取失败时清空列表
+        let rows = match image::open(&image_path) {
+            Ok(img) => stats::color_stats(&img.to_rgba8())
+                .into_iter()
+                .map(|s| PaletteRow { color: format!("#{:02x}{:02x}{:02x}", s.color[0], s.color[1], s.color[2]), stats: s })
+                .collect(),
+            Err(e) => {
+                if !image_path.is_empty() {
+                    eprintln!("Failed to open image for palette statistics: {}", e);
+                }
+                Vec::new()
+            }
+        };
+        self.model.begin_reset_model();
+        self.rows = rows;
+        self.model.end_reset_model();
+    }
+    fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+    fn color(&self, index: usize) -> &str {
+        &self.rows[index].color
+    }
+    fn pixel_count(&self, index: usize) -> u64 {
+        self.rows[index].stats.pixels
+    }
+    fn percentage(&self, index: usize) -> f64 {
+        self.rows[index].stats.percentage
+    }
+    fn region_count(&self, index: usize) -> u32 {
+        self.rows[index].stats.regions
+    }
+    fn bbox_x(&self, index: usize) -> u32 {
+        self.rows[index].stats.x
+    }
+    fn bbox_y(&self, index: usize) -> u32 {
+        self.rows[index].stats.y
+    }
+    fn bbox_width(&self, index: usize) -> u32 {
+        self.rows[index].stats.width
+    }
+    fn bbox_height(&self, index: usize) -> u32 {
+        self.rows[index].stats.height
     }
 }
 
@@ -42,9 +42,18 @@ pub struct ImageViewerQObject {}
 
 pub struct ImageViewerEmitter {
     qobject: Arc<AtomicPtr<ImageViewerQObject>>,
+    defaults_changed: extern fn(*mut ImageViewerQObject),
     display_path_changed: extern fn(*mut ImageViewerQObject),
+    export_gray_codes_changed: extern fn(*mut ImageViewerQObject),
     has_pending_changed: extern fn(*mut ImageViewerQObject),
     image_path_changed: extern fn(*mut ImageViewerQObject),
+    last_export_dir_changed: extern fn(*mut ImageViewerQObject),
+    last_threshold_changed: extern fn(*mut ImageViewerQObject),
+    mask_overlay_path_changed: extern fn(*mut ImageViewerQObject),
+    preview_path_changed: extern fn(*mut ImageViewerQObject),
+    recent_files_changed: extern fn(*mut ImageViewerQObject),
+    result_path_changed: extern fn(*mut ImageViewerQObject),
+    window_state_changed: extern fn(*mut ImageViewerQObject),
 }
 
 unsafe impl Send for ImageViewerEmitter {}
@@ -59,21 +68,42 @@ impl ImageViewerEmitter {
     pub fn clone(&mut self) -> ImageViewerEmitter {
         ImageViewerEmitter {
             qobject: self.qobject.clone(),
+            defaults_changed: self.defaults_changed,
             display_path_changed: self.display_path_changed,
+            export_gray_codes_changed: self.export_gray_codes_changed,
             has_pending_changed: self.has_pending_changed,
             image_path_changed: self.image_path_changed,
+            last_export_dir_changed: self.last_export_dir_changed,
+            last_threshold_changed: self.last_threshold_changed,
+            mask_overlay_path_changed: self.mask_overlay_path_changed,
+            preview_path_changed: self.preview_path_changed,
+            recent_files_changed: self.recent_files_changed,
+            result_path_changed: self.result_path_changed,
+            window_state_changed: self.window_state_changed,
         }
     }
     fn clear(&self) {
         let n: *const ImageViewerQObject = null();
         self.qobject.store(n as *mut ImageViewerQObject, Ordering::SeqCst);
     }
+    pub fn defaults_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.defaults_changed)(ptr);
+        }
+    }
     pub fn display_path_changed(&mut self) {
         let ptr = self.qobject.load(Ordering::SeqCst);
         if !ptr.is_null() {
             (self.display_path_changed)(ptr);
         }
     }
+    pub fn export_gray_codes_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.export_gray_codes_changed)(ptr);
+        }
+    }
     pub fn has_pending_changed(&mut self) {
         let ptr = self.qobject.load(Ordering::SeqCst);
         if !ptr.is_null() {
@@ -86,36 +116,136 @@ impl ImageViewerEmitter {
             (self.image_path_changed)(ptr);
         }
     }
+    pub fn last_export_dir_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.last_export_dir_changed)(ptr);
+        }
+    }
+    pub fn last_threshold_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.last_threshold_changed)(ptr);
+        }
+    }
+    pub fn mask_overlay_path_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.mask_overlay_path_changed)(ptr);
+        }
+    }
+    pub fn preview_path_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.preview_path_changed)(ptr);
+        }
+    }
+    pub fn recent_files_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.recent_files_changed)(ptr);
+        }
+    }
+    pub fn result_path_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.result_path_changed)(ptr);
+        }
+    }
+    pub fn window_state_changed(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.window_state_changed)(ptr);
+        }
+    }
 }
 
 pub trait ImageViewerTrait {
     fn new(emit: ImageViewerEmitter) -> Self;
     fn emit(&mut self) -> &mut ImageViewerEmitter;
+    fn defaults(&self) -> &str;
     fn display_path(&self) -> &str;
+    fn export_gray_codes(&self) -> bool;
     fn has_pending(&self) -> bool;
     fn image_path(&self) -> &str;
+    fn last_export_dir(&self) -> &str;
+    fn last_threshold(&self) -> &str;
+    fn mask_overlay_path(&self) -> &str;
+    fn preview_path(&self) -> &str;
+    fn recent_files(&self) -> &str;
+    fn result_path(&self) -> &str;
+    fn window_state(&self) -> &str;
+    fn adjust_tone(&self, tone_json: String) -> ();
+    fn analyze_seams(&self, repeat_json: String) -> String;
+    fn apply_color_mapping(&self) -> ();
     fn apply_threshold_mapping(&self, thresholds_json: String) -> ();
-    fn cleanup_scattered_pixels(&self) -> ();
+    fn apply_transform(&self, transform_json: String) -> ();
+    fn cleanup_scattered_pixels(&self, wrap_mode: String) -> ();
     fn cleanup_temp_files(&self) -> ();
+    fn clear_recent_files(&self) -> ();
+    fn compare(&self, compare_json: String) -> String;
+    fn denoise(&self, denoise_json: String) -> ();
+    fn edit_pixels(&self, edit_json: String) -> u32;
+    fn export_color_mapping(&self, output_path: String, gray_codes: bool) -> ();
+    fn get_color_table(&self) -> String;
+    fn get_density(&self) -> String;
+    fn get_selection_mask(&self) -> String;
     fn gray_preview(&self) -> ();
+    fn grid_geometry(&self, grid_json: String) -> String;
+    fn inspect(&self, x: i32, y: i32) -> String;
+    fn isolate_level(&self, isolate_json: String) -> u32;
+    fn merge_levels(&self, merge_json: String) -> u32;
+    fn merge_small_regions(&self, regions_json: String) -> u32;
+    fn mode_filter(&self, mode_filter_json: String) -> String;
+    fn preview_threshold_mapping(&self, thresholds_json: String) -> ();
+    fn quantize_colors(&self, quantize_json: String) -> ();
+    fn redo_edit(&self) -> bool;
     fn refresh_display(&self) -> ();
+    fn replace_color(&self, replace_json: String) -> u32;
+    fn run_pipeline(&self, pipeline_json: String) -> String;
     fn save_processed(&self) -> ();
+    fn set_color_table(&self, color_table_json: String) -> ();
+    fn set_defaults(&self, defaults_json: String) -> ();
+    fn set_density(&self, density_json: String) -> ();
     fn set_image_path(&self, path: String) -> ();
+    fn set_selection_mask(&self, mask_json: String) -> ();
+    fn set_window_state(&self, window_json: String) -> ();
     fn start_watcher(&self, path: String) -> ();
+    fn tile_preview(&self, repeat_json: String) -> ();
+    fn undo_edit(&self) -> bool;
+    fn visible_tiles(&self, viewport_json: String) -> String;
 }
 
 #[no_mangle]
 pub extern "C" fn image_viewer_new(
     image_viewer: *mut ImageViewerQObject,
+    image_viewer_defaults_changed: extern fn(*mut ImageViewerQObject),
     image_viewer_display_path_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_export_gray_codes_changed: extern fn(*mut ImageViewerQObject),
     image_viewer_has_pending_changed: extern fn(*mut ImageViewerQObject),
     image_viewer_image_path_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_last_export_dir_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_last_threshold_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_mask_overlay_path_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_preview_path_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_recent_files_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_result_path_changed: extern fn(*mut ImageViewerQObject),
+    image_viewer_window_state_changed: extern fn(*mut ImageViewerQObject),
 ) -> *mut ImageViewer {
     let image_viewer_emit = ImageViewerEmitter {
         qobject: Arc::new(AtomicPtr::new(image_viewer)),
+        defaults_changed: image_viewer_defaults_changed,
         display_path_changed: image_viewer_display_path_changed,
+        export_gray_codes_changed: image_viewer_export_gray_codes_changed,
         has_pending_changed: image_viewer_has_pending_changed,
         image_path_changed: image_viewer_image_path_changed,
+        last_export_dir_changed: image_viewer_last_export_dir_changed,
+        last_threshold_changed: image_viewer_last_threshold_changed,
+        mask_overlay_path_changed: image_viewer_mask_overlay_path_changed,
+        preview_path_changed: image_viewer_preview_path_changed,
+        recent_files_changed: image_viewer_recent_files_changed,
+        result_path_changed: image_viewer_result_path_changed,
+        window_state_changed: image_viewer_window_state_changed,
     };
     let d_image_viewer = ImageViewer::new(image_viewer_emit);
     Box::into_raw(Box::new(d_image_viewer))
@@ -126,6 +256,18 @@ pub unsafe extern "C" fn image_viewer_free(ptr: *mut ImageViewer) {
     Box::from_raw(ptr).emit().clear();
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_defaults_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.defaults();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_display_path_get(
     ptr: *const ImageViewer,
@@ -138,6 +280,11 @@ pub unsafe extern "C" fn image_viewer_display_path_get(
     set(p, s, to_c_int(v.len()));
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_export_gray_codes_get(ptr: *const ImageViewer) -> bool {
+    (&*ptr).export_gray_codes()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_has_pending_get(ptr: *const ImageViewer) -> bool {
     (&*ptr).has_pending()
@@ -155,6 +302,114 @@ pub unsafe extern "C" fn image_viewer_image_path_get(
     set(p, s, to_c_int(v.len()));
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_last_export_dir_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.last_export_dir();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_last_threshold_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.last_threshold();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_mask_overlay_path_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.mask_overlay_path();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_preview_path_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.preview_path();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_recent_files_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.recent_files();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_result_path_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.result_path();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_window_state_get(
+    ptr: *const ImageViewer,
+    p: *mut QString,
+    set: extern fn(*mut QString, *const c_char, c_int),
+) {
+    let o = &*ptr;
+    let v = o.window_state();
+    let s: *const c_char = v.as_ptr() as *const c_char;
+    set(p, s, to_c_int(v.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_adjust_tone(ptr: *const ImageViewer, tone_json_str: *const c_ushort, tone_json_len: c_int) {
+    let mut tone_json = String::new();
+    set_string_from_utf16(&mut tone_json, tone_json_str, tone_json_len);
+    let o = &*ptr;
+    o.adjust_tone(tone_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_analyze_seams(ptr: *const ImageViewer, repeat_json_str: *const c_ushort, repeat_json_len: c_int, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let mut repeat_json = String::new();
+    set_string_from_utf16(&mut repeat_json, repeat_json_str, repeat_json_len);
+    let o = &*ptr;
+    let r = o.analyze_seams(repeat_json);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_apply_color_mapping(ptr: *const ImageViewer) {
+    let o = &*ptr;
+    o.apply_color_mapping()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_apply_threshold_mapping(ptr: *const ImageViewer, thresholds_json_str: *const c_ushort, thresholds_json_len: c_int) {
     let mut thresholds_json = String::new();
@@ -164,9 +419,19 @@ pub unsafe extern "C" fn image_viewer_apply_threshold_mapping(ptr: *const ImageV
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn image_viewer_cleanup_scattered_pixels(ptr: *const ImageViewer) {
+pub unsafe extern "C" fn image_viewer_apply_transform(ptr: *const ImageViewer, transform_json_str: *const c_ushort, transform_json_len: c_int) {
+    let mut transform_json = String::new();
+    set_string_from_utf16(&mut transform_json, transform_json_str, transform_json_len);
+    let o = &*ptr;
+    o.apply_transform(transform_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_cleanup_scattered_pixels(ptr: *const ImageViewer, wrap_mode_str: *const c_ushort, wrap_mode_len: c_int) {
+    let mut wrap_mode = String::new();
+    set_string_from_utf16(&mut wrap_mode, wrap_mode_str, wrap_mode_len);
     let o = &*ptr;
-    o.cleanup_scattered_pixels()
+    o.cleanup_scattered_pixels(wrap_mode)
 }
 
 #[no_mangle]
@@ -175,24 +440,204 @@ pub unsafe extern "C" fn image_viewer_cleanup_temp_files(ptr: *const ImageViewer
     o.cleanup_temp_files()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_clear_recent_files(ptr: *const ImageViewer) {
+    let o = &*ptr;
+    o.clear_recent_files()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_compare(ptr: *const ImageViewer, compare_json_str: *const c_ushort, compare_json_len: c_int, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let mut compare_json = String::new();
+    set_string_from_utf16(&mut compare_json, compare_json_str, compare_json_len);
+    let o = &*ptr;
+    let r = o.compare(compare_json);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_denoise(ptr: *const ImageViewer, denoise_json_str: *const c_ushort, denoise_json_len: c_int) {
+    let mut denoise_json = String::new();
+    set_string_from_utf16(&mut denoise_json, denoise_json_str, denoise_json_len);
+    let o = &*ptr;
+    o.denoise(denoise_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_edit_pixels(ptr: *const ImageViewer, edit_json_str: *const c_ushort, edit_json_len: c_int) -> u32 {
+    let mut edit_json = String::new();
+    set_string_from_utf16(&mut edit_json, edit_json_str, edit_json_len);
+    let o = &*ptr;
+    o.edit_pixels(edit_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_export_color_mapping(ptr: *const ImageViewer, output_path_str: *const c_ushort, output_path_len: c_int, gray_codes: bool) {
+    let mut output_path = String::new();
+    set_string_from_utf16(&mut output_path, output_path_str, output_path_len);
+    let o = &*ptr;
+    o.export_color_mapping(output_path, gray_codes)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_get_color_table(ptr: *const ImageViewer, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let o = &*ptr;
+    let r = o.get_color_table();
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_get_density(ptr: *const ImageViewer, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let o = &*ptr;
+    let r = o.get_density();
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_get_selection_mask(ptr: *const ImageViewer, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let o = &*ptr;
+    let r = o.get_selection_mask();
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_gray_preview(ptr: *const ImageViewer) {
     let o = &*ptr;
     o.gray_preview()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_grid_geometry(ptr: *const ImageViewer, grid_json_str: *const c_ushort, grid_json_len: c_int, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let mut grid_json = String::new();
+    set_string_from_utf16(&mut grid_json, grid_json_str, grid_json_len);
+    let o = &*ptr;
+    let r = o.grid_geometry(grid_json);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_inspect(ptr: *const ImageViewer, x: i32, y: i32, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let o = &*ptr;
+    let r = o.inspect(x, y);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_isolate_level(ptr: *const ImageViewer, isolate_json_str: *const c_ushort, isolate_json_len: c_int) -> u32 {
+    let mut isolate_json = String::new();
+    set_string_from_utf16(&mut isolate_json, isolate_json_str, isolate_json_len);
+    let o = &*ptr;
+    o.isolate_level(isolate_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_merge_levels(ptr: *const ImageViewer, merge_json_str: *const c_ushort, merge_json_len: c_int) -> u32 {
+    let mut merge_json = String::new();
+    set_string_from_utf16(&mut merge_json, merge_json_str, merge_json_len);
+    let o = &*ptr;
+    o.merge_levels(merge_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_merge_small_regions(ptr: *const ImageViewer, regions_json_str: *const c_ushort, regions_json_len: c_int) -> u32 {
+    let mut regions_json = String::new();
+    set_string_from_utf16(&mut regions_json, regions_json_str, regions_json_len);
+    let o = &*ptr;
+    o.merge_small_regions(regions_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_mode_filter(ptr: *const ImageViewer, mode_filter_json_str: *const c_ushort, mode_filter_json_len: c_int, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let mut mode_filter_json = String::new();
+    set_string_from_utf16(&mut mode_filter_json, mode_filter_json_str, mode_filter_json_len);
+    let o = &*ptr;
+    let r = o.mode_filter(mode_filter_json);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_preview_threshold_mapping(ptr: *const ImageViewer, thresholds_json_str: *const c_ushort, thresholds_json_len: c_int) {
+    let mut thresholds_json = String::new();
+    set_string_from_utf16(&mut thresholds_json, thresholds_json_str, thresholds_json_len);
+    let o = &*ptr;
+    o.preview_threshold_mapping(thresholds_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_quantize_colors(ptr: *const ImageViewer, quantize_json_str: *const c_ushort, quantize_json_len: c_int) {
+    let mut quantize_json = String::new();
+    set_string_from_utf16(&mut quantize_json, quantize_json_str, quantize_json_len);
+    let o = &*ptr;
+    o.quantize_colors(quantize_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_redo_edit(ptr: *const ImageViewer) -> bool {
+    let o = &*ptr;
+    o.redo_edit()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_refresh_display(ptr: *const ImageViewer) {
     let o = &*ptr;
     o.refresh_display()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_replace_color(ptr: *const ImageViewer, replace_json_str: *const c_ushort, replace_json_len: c_int) -> u32 {
+    let mut replace_json = String::new();
+    set_string_from_utf16(&mut replace_json, replace_json_str, replace_json_len);
+    let o = &*ptr;
+    o.replace_color(replace_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_run_pipeline(ptr: *const ImageViewer, pipeline_json_str: *const c_ushort, pipeline_json_len: c_int, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let mut pipeline_json = String::new();
+    set_string_from_utf16(&mut pipeline_json, pipeline_json_str, pipeline_json_len);
+    let o = &*ptr;
+    let r = o.run_pipeline(pipeline_json);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_save_processed(ptr: *const ImageViewer) {
     let o = &*ptr;
     o.save_processed()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_set_color_table(ptr: *const ImageViewer, color_table_json_str: *const c_ushort, color_table_json_len: c_int) {
+    let mut color_table_json = String::new();
+    set_string_from_utf16(&mut color_table_json, color_table_json_str, color_table_json_len);
+    let o = &*ptr;
+    o.set_color_table(color_table_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_set_defaults(ptr: *const ImageViewer, defaults_json_str: *const c_ushort, defaults_json_len: c_int) {
+    let mut defaults_json = String::new();
+    set_string_from_utf16(&mut defaults_json, defaults_json_str, defaults_json_len);
+    let o = &*ptr;
+    o.set_defaults(defaults_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_set_density(ptr: *const ImageViewer, density_json_str: *const c_ushort, density_json_len: c_int) {
+    let mut density_json = String::new();
+    set_string_from_utf16(&mut density_json, density_json_str, density_json_len);
+    let o = &*ptr;
+    o.set_density(density_json)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_set_image_path(ptr: *const ImageViewer, path_str: *const c_ushort, path_len: c_int) {
     let mut path = String::new();
@@ -201,6 +646,22 @@ pub unsafe extern "C" fn image_viewer_set_image_path(ptr: *const ImageViewer, pa
     o.set_image_path(path)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_set_selection_mask(ptr: *const ImageViewer, mask_json_str: *const c_ushort, mask_json_len: c_int) {
+    let mut mask_json = String::new();
+    set_string_from_utf16(&mut mask_json, mask_json_str, mask_json_len);
+    let o = &*ptr;
+    o.set_selection_mask(mask_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_set_window_state(ptr: *const ImageViewer, window_json_str: *const c_ushort, window_json_len: c_int) {
+    let mut window_json = String::new();
+    set_string_from_utf16(&mut window_json, window_json_str, window_json_len);
+    let o = &*ptr;
+    o.set_window_state(window_json)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn image_viewer_start_watcher(ptr: *const ImageViewer, path_str: *const c_ushort, path_len: c_int) {
     let mut path = String::new();
@@ -208,3 +669,275 @@ pub unsafe extern "C" fn image_viewer_start_watcher(ptr: *const ImageViewer, pat
     let o = &*ptr;
     o.start_watcher(path)
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_tile_preview(ptr: *const ImageViewer, repeat_json_str: *const c_ushort, repeat_json_len: c_int) {
+    let mut repeat_json = String::new();
+    set_string_from_utf16(&mut repeat_json, repeat_json_str, repeat_json_len);
+    let o = &*ptr;
+    o.tile_preview(repeat_json)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_undo_edit(ptr: *const ImageViewer) -> bool {
+    let o = &*ptr;
+    o.undo_edit()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn image_viewer_visible_tiles(ptr: *const ImageViewer, viewport_json_str: *const c_ushort, viewport_json_len: c_int, d: *mut QString, set: extern fn(*mut QString, *const c_char, c_int)) {
+    let mut viewport_json = String::new();
+    set_string_from_utf16(&mut viewport_json, viewport_json_str, viewport_json_len);
+    let o = &*ptr;
+    let r = o.visible_tiles(viewport_json);
+    let s: *const c_char = r.as_ptr() as *const c_char;
+    set(d, s, to_c_int(r.len()));
+}
+
+#[repr(C)]
+pub enum SortOrder {
+    Ascending = 0,
+    Descending = 1,
+}
+
+pub struct PaletteStatsQObject {}
+
+pub struct PaletteStatsEmitter {
+    qobject: Arc<AtomicPtr<PaletteStatsQObject>>,
+    new_data_ready: extern fn(*mut PaletteStatsQObject),
+}
+
+unsafe impl Send for PaletteStatsEmitter {}
+
+impl PaletteStatsEmitter {
+    /// Clone the emitter
+    ///
+    /// The emitter can only be cloned when it is mutable. The emitter calls
+    /// into C++ code which may call into Rust again. If emmitting is possible
+    /// from immutable structures, that might lead to access to a mutable
+    /// reference. That is undefined behaviour and forbidden.
+    pub fn clone(&mut self) -> PaletteStatsEmitter {
+        PaletteStatsEmitter {
+            qobject: self.qobject.clone(),
+            new_data_ready: self.new_data_ready,
+        }
+    }
+    fn clear(&self) {
+        let n: *const PaletteStatsQObject = null();
+        self.qobject.store(n as *mut PaletteStatsQObject, Ordering::SeqCst);
+    }
+    pub fn new_data_ready(&mut self) {
+        let ptr = self.qobject.load(Ordering::SeqCst);
+        if !ptr.is_null() {
+            (self.new_data_ready)(ptr);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PaletteStatsList {
+    qobject: *mut PaletteStatsQObject,
+    layout_about_to_be_changed: extern fn(*mut PaletteStatsQObject),
+    layout_changed: extern fn(*mut PaletteStatsQObject),
+    data_changed: extern fn(*mut PaletteStatsQObject, usize, usize),
+    begin_reset_model: extern fn(*mut PaletteStatsQObject),
+    end_reset_model: extern fn(*mut PaletteStatsQObject),
+    begin_insert_rows: extern fn(*mut PaletteStatsQObject, usize, usize),
+    end_insert_rows: extern fn(*mut PaletteStatsQObject),
+    begin_move_rows: extern fn(*mut PaletteStatsQObject, usize, usize, usize),
+    end_move_rows: extern fn(*mut PaletteStatsQObject),
+    begin_remove_rows: extern fn(*mut PaletteStatsQObject, usize, usize),
+    end_remove_rows: extern fn(*mut PaletteStatsQObject),
+}
+
+impl PaletteStatsList {
+    pub fn layout_about_to_be_changed(&mut self) {
+        (self.layout_about_to_be_changed)(self.qobject);
+    }
+    pub fn layout_changed(&mut self) {
+        (self.layout_changed)(self.qobject);
+    }
+    pub fn data_changed(&mut self, first: usize, last: usize) {
+        (self.data_changed)(self.qobject, first, last);
+    }
+    pub fn begin_reset_model(&mut self) {
+        (self.begin_reset_model)(self.qobject);
+    }
+    pub fn end_reset_model(&mut self) {
+        (self.end_reset_model)(self.qobject);
+    }
+    pub fn begin_insert_rows(&mut self, first: usize, last: usize) {
+        (self.begin_insert_rows)(self.qobject, first, last);
+    }
+    pub fn end_insert_rows(&mut self) {
+        (self.end_insert_rows)(self.qobject);
+    }
+    pub fn begin_move_rows(&mut self, first: usize, last: usize, destination: usize) {
+        (self.begin_move_rows)(self.qobject, first, last, destination);
+    }
+    pub fn end_move_rows(&mut self) {
+        (self.end_move_rows)(self.qobject);
+    }
+    pub fn begin_remove_rows(&mut self, first: usize, last: usize) {
+        (self.begin_remove_rows)(self.qobject, first, last);
+    }
+    pub fn end_remove_rows(&mut self) {
+        (self.end_remove_rows)(self.qobject);
+    }
+}
+
+pub trait PaletteStatsTrait {
+    fn new(emit: PaletteStatsEmitter, model: PaletteStatsList) -> Self;
+    fn emit(&mut self) -> &mut PaletteStatsEmitter;
+    fn refresh(&mut self, image_path: String) -> ();
+    fn row_count(&self) -> usize;
+    fn insert_rows(&mut self, _row: usize, _count: usize) -> bool { false }
+    fn remove_rows(&mut self, _row: usize, _count: usize) -> bool { false }
+    fn can_fetch_more(&self) -> bool {
+        false
+    }
+    fn fetch_more(&mut self) {}
+    fn sort(&mut self, _: u8, _: SortOrder) {}
+    fn bbox_height(&self, index: usize) -> u32;
+    fn bbox_width(&self, index: usize) -> u32;
+    fn bbox_x(&self, index: usize) -> u32;
+    fn bbox_y(&self, index: usize) -> u32;
+    fn color(&self, index: usize) -> &str;
+    fn percentage(&self, index: usize) -> f64;
+    fn pixel_count(&self, index: usize) -> u64;
+    fn region_count(&self, index: usize) -> u32;
+}
+
+#[no_mangle]
+pub extern "C" fn palette_stats_new(
+    palette_stats: *mut PaletteStatsQObject,
+    palette_stats_new_data_ready: extern fn(*mut PaletteStatsQObject),
+    palette_stats_layout_about_to_be_changed: extern fn(*mut PaletteStatsQObject),
+    palette_stats_layout_changed: extern fn(*mut PaletteStatsQObject),
+    palette_stats_data_changed: extern fn(*mut PaletteStatsQObject, usize, usize),
+    palette_stats_begin_reset_model: extern fn(*mut PaletteStatsQObject),
+    palette_stats_end_reset_model: extern fn(*mut PaletteStatsQObject),
+    palette_stats_begin_insert_rows: extern fn(*mut PaletteStatsQObject, usize, usize),
+    palette_stats_end_insert_rows: extern fn(*mut PaletteStatsQObject),
+    palette_stats_begin_move_rows: extern fn(*mut PaletteStatsQObject, usize, usize, usize),
+    palette_stats_end_move_rows: extern fn(*mut PaletteStatsQObject),
+    palette_stats_begin_remove_rows: extern fn(*mut PaletteStatsQObject, usize, usize),
+    palette_stats_end_remove_rows: extern fn(*mut PaletteStatsQObject),
+) -> *mut PaletteStats {
+    let palette_stats_emit = PaletteStatsEmitter {
+        qobject: Arc::new(AtomicPtr::new(palette_stats)),
+        new_data_ready: palette_stats_new_data_ready,
+    };
+    let model = PaletteStatsList {
+        qobject: palette_stats,
+        layout_about_to_be_changed: palette_stats_layout_about_to_be_changed,
+        layout_changed: palette_stats_layout_changed,
+        data_changed: palette_stats_data_changed,
+        begin_reset_model: palette_stats_begin_reset_model,
+        end_reset_model: palette_stats_end_reset_model,
+        begin_insert_rows: palette_stats_begin_insert_rows,
+        end_insert_rows: palette_stats_end_insert_rows,
+        begin_move_rows: palette_stats_begin_move_rows,
+        end_move_rows: palette_stats_end_move_rows,
+        begin_remove_rows: palette_stats_begin_remove_rows,
+        end_remove_rows: palette_stats_end_remove_rows,
+    };
+    let d_palette_stats = PaletteStats::new(palette_stats_emit, model);
+    Box::into_raw(Box::new(d_palette_stats))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_free(ptr: *mut PaletteStats) {
+    Box::from_raw(ptr).emit().clear();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_refresh(ptr: *mut PaletteStats, image_path_str: *const c_ushort, image_path_len: c_int) {
+    let mut image_path = String::new();
+    set_string_from_utf16(&mut image_path, image_path_str, image_path_len);
+    let o = &mut *ptr;
+    o.refresh(image_path)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_row_count(ptr: *const PaletteStats) -> c_int {
+    to_c_int((&*ptr).row_count())
+}
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_insert_rows(ptr: *mut PaletteStats, row: c_int, count: c_int) -> bool {
+    (&mut *ptr).insert_rows(to_usize(row), to_usize(count))
+}
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_remove_rows(ptr: *mut PaletteStats, row: c_int, count: c_int) -> bool {
+    (&mut *ptr).remove_rows(to_usize(row), to_usize(count))
+}
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_can_fetch_more(ptr: *const PaletteStats) -> bool {
+    (&*ptr).can_fetch_more()
+}
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_fetch_more(ptr: *mut PaletteStats) {
+    (&mut *ptr).fetch_more()
+}
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_sort(
+    ptr: *mut PaletteStats,
+    column: u8,
+    order: SortOrder,
+) {
+    (&mut *ptr).sort(column, order)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_bbox_height(ptr: *const PaletteStats, row: c_int) -> u32 {
+    let o = &*ptr;
+    o.bbox_height(to_usize(row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_bbox_width(ptr: *const PaletteStats, row: c_int) -> u32 {
+    let o = &*ptr;
+    o.bbox_width(to_usize(row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_bbox_x(ptr: *const PaletteStats, row: c_int) -> u32 {
+    let o = &*ptr;
+    o.bbox_x(to_usize(row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_bbox_y(ptr: *const PaletteStats, row: c_int) -> u32 {
+    let o = &*ptr;
+    o.bbox_y(to_usize(row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_color(
+    ptr: *const PaletteStats, row: c_int,
+    d: *mut QString,
+    set: extern fn(*mut QString, *const c_char, len: c_int),
+) {
+    let o = &*ptr;
+    let data = o.color(to_usize(row));
+    let s: *const c_char = data.as_ptr() as *const c_char;
+    set(d, s, to_c_int(data.len()));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_percentage(ptr: *const PaletteStats, row: c_int) -> f64 {
+    let o = &*ptr;
+    o.percentage(to_usize(row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_pixel_count(ptr: *const PaletteStats, row: c_int) -> u64 {
+    let o = &*ptr;
+    o.pixel_count(to_usize(row))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn palette_stats_data_region_count(ptr: *const PaletteStats, row: c_int) -> u32 {
+    let o = &*ptr;
+    o.region_count(to_usize(row))
+}
@@ -0,0 +1,109 @@
+// 循环边界：图案按循环织造时，图片边缘的邻域会从对边接续。
+// 清理散点、众数滤波与形态学按此解析越界的邻居坐标，使接缝处的像素以完整邻域判断。
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum WrapMode {
+    // 图片边缘即边界，越界的邻居忽略
+    None,
+    // 左右相接
+    Horizontal,
+    // 上下相接
+    Vertical,
+    // 左右、上下都相接
+    Both,
+    // 半落循环：越过左右边缘时同时错开半个图片高度（与 repeat.rs 的 halfDrop 一致），上下相接
+    HalfDrop,
+}
+
+impl Default for WrapMode {
+    fn default() -> Self {
+        WrapMode::None
+    }
+}
+
+impl WrapMode {
+    // 从 QML 传入的字符串解析；空字符串视为 none
+    pub fn parse(text: &str) -> Result<WrapMode, String> {
+        if text.trim().is_empty() {
+            return Ok(WrapMode::None);
+        }
+        serde_json::from_value(serde_json::Value::String(text.trim().to_string()))
+            .map_err(|_| format!("Unknown wrap mode: {}", text))
+    }
+
+    // 将可能越界的坐标解析为图片内坐标；该方向不相接时返回 None
+    pub fn resolve(self, x: i32, y: i32, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (w, h) = (width as i32, height as i32);
+        let (wrap_x, wrap_y) = match self {
+            WrapMode::None => (false, false),
+            WrapMode::Horizontal => (true, false),
+            WrapMode::Vertical => (false, true),
+            WrapMode::Both | WrapMode::HalfDrop => (true, true),
+        };
+        let (mut x, mut y) = (x, y);
+        if x < 0 || x >= w {
+            if !wrap_x {
+                return None;
+            }
+            let tiles = x.div_euclid(w);
+            x = x.rem_euclid(w);
+            if self == WrapMode::HalfDrop {
+                // 右侧单元向下错开半个高度：越过右边缘后对应原图中更靠上的行
+                y -= tiles * (h / 2);
+            }
+        }
+        if y < 0 || y >= h {
+            if !wrap_y {
+                return None;
+            }
+            y = y.rem_euclid(h);
+        }
+        Some((x as u32, y as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inside_coordinates_are_unchanged() {
+        for &mode in &[WrapMode::None, WrapMode::Horizontal, WrapMode::Vertical, WrapMode::Both, WrapMode::HalfDrop] {
+            assert_eq!(mode.resolve(3, 2, 5, 4), Some((3, 2)));
+        }
+    }
+
+    #[test]
+    fn only_wrapped_directions_resolve() {
+        assert_eq!(WrapMode::None.resolve(-1, 0, 5, 4), None);
+        assert_eq!(WrapMode::Horizontal.resolve(-1, 0, 5, 4), Some((4, 0)));
+        assert_eq!(WrapMode::Horizontal.resolve(0, 4, 5, 4), None);
+        assert_eq!(WrapMode::Vertical.resolve(0, -1, 5, 4), Some((0, 3)));
+        assert_eq!(WrapMode::Vertical.resolve(5, 0, 5, 4), None);
+        assert_eq!(WrapMode::Both.resolve(5, -1, 5, 4), Some((0, 3)));
+        assert_eq!(WrapMode::Both.resolve(-11, 9, 5, 4), Some((4, 1)));
+    }
+
+    #[test]
+    fn half_drop_shifts_rows_across_the_side_edges() {
+        // 越过右边缘进入下移半个高度的单元，对应原图中上方 h/2 行；越过左边缘则相反
+        assert_eq!(WrapMode::HalfDrop.resolve(5, 3, 5, 4), Some((0, 1)));
+        assert_eq!(WrapMode::HalfDrop.resolve(5, 0, 5, 4), Some((0, 2)));
+        assert_eq!(WrapMode::HalfDrop.resolve(-1, 0, 5, 4), Some((4, 2)));
+        assert_eq!(WrapMode::HalfDrop.resolve(2, -1, 5, 4), Some((2, 3)));
+        // 左右相邻的关系是对称的
+        for y in 0..4 {
+            let (x, ny) = WrapMode::HalfDrop.resolve(5, y, 5, 4).unwrap();
+            assert_eq!(WrapMode::HalfDrop.resolve(x as i32 - 1, ny as i32, 5, 4), Some((4, y as u32)));
+        }
+    }
+
+    #[test]
+    fn parses_mode_names() {
+        assert!(WrapMode::parse("") == Ok(WrapMode::None));
+        assert!(WrapMode::parse(" halfDrop ") == Ok(WrapMode::HalfDrop));
+        assert!(WrapMode::parse("diagonal").is_err());
+    }
+}